@@ -1,4 +1,5 @@
 pub mod logic;
+pub mod services;
 pub mod ui;
 pub mod utils;
 
@@ -0,0 +1,98 @@
+use crate::utils::json::{tokenize_line, LineIndex, TokenKind};
+use crate::{JsonCodeLine, JsonCodeSpan};
+use slint::{Model, ModelRc, ModelTracker, VecModel};
+use std::sync::Arc;
+
+/// 单行显示的最大字节数，超出部分不渲染（如压缩后的整行JSON）
+const MAX_LINE_BYTES: usize = 2000;
+
+/// 输出文本及其行索引。构建索引需要扫描全文，较大的文本应在产生它的后台任务中构建
+pub struct CodeText {
+    text: Arc<str>,
+    index: LineIndex,
+}
+
+impl CodeText {
+    pub fn new(text: impl Into<Arc<str>>) -> Self {
+        let text = text.into();
+        let index = LineIndex::new(&text);
+        Self { text, index }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+/// 输出文本的行模型：只保存文本和行索引，列表请求某行时才切分并高亮该行
+pub struct JsonCodeModel {
+    text: Arc<str>,
+    index: LineIndex,
+}
+
+impl JsonCodeModel {
+    pub fn new(code: CodeText) -> Self {
+        Self { text: code.text, index: code.index }
+    }
+}
+
+impl Model for JsonCodeModel {
+    type Data = JsonCodeLine;
+
+    fn row_count(&self) -> usize {
+        if self.text.is_empty() {
+            0
+        } else {
+            self.index.line_count()
+        }
+    }
+
+    fn row_data(&self, row: usize) -> Option<JsonCodeLine> {
+        if row >= self.row_count() {
+            return None;
+        }
+        let line = &self.text[self.index.line_range(row)];
+        let mut end = line.len().min(MAX_LINE_BYTES);
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        let shown = &line[..end];
+
+        let mut spans: Vec<JsonCodeSpan> = tokenize_line(shown)
+            .into_iter()
+            .map(|token| JsonCodeSpan {
+                text: shown[token.range].into(),
+                kind: token_kind_index(token.kind),
+            })
+            .collect();
+        if end < line.len() {
+            spans.push(JsonCodeSpan {
+                text: format!(" …（本行共{}KB，仅显示开头部分）", line.len() / 1024).into(),
+                kind: token_kind_index(TokenKind::Comment),
+            });
+        }
+        Some(JsonCodeLine {
+            spans: ModelRc::new(VecModel::from(spans)),
+            match_line: self.index.matching_line(row).map_or(-1, |line| line as i32),
+        })
+    }
+
+    // 文本不可变，内容变化时整体替换模型
+    fn model_tracker(&self) -> &dyn ModelTracker {
+        &()
+    }
+}
+
+fn token_kind_index(kind: TokenKind) -> i32 {
+    match kind {
+        TokenKind::Plain => 0,
+        TokenKind::Key => 1,
+        TokenKind::String => 2,
+        TokenKind::Number => 3,
+        TokenKind::Boolean => 4,
+        TokenKind::Null => 5,
+        TokenKind::Bracket => 6,
+        TokenKind::Punctuation => 7,
+        TokenKind::Comment => 8,
+    }
+}
@@ -1,23 +1,142 @@
-use slint::{Weak, SharedString, Timer};
-use crate::MainWindow;
+use slint::{Weak, SharedString, ModelRc, VecModel};
+use crate::{JsonDiffRow, JsonSchemaViolation, JsonTreeNode, MainWindow};
+use crate::logic::code_view::{CodeText, JsonCodeModel};
+#[cfg(feature = "file-drop")]
+use crate::logic::file_drop::{self, DropTarget};
 use crate::logic::time::TimeLogic;
 use crate::logic::json::JsonLogic;
-use crate::utils::json::ProcessUpdate;
+use crate::services::config::ConfigService;
+use crate::services::file::FileService;
+use crate::services::job::{JobContext, JobEvent, JobId, JobInfo, JobManager, JobOutcome};
+use crate::utils::json::{CancelToken, MAX_INPUT_SIZE, ChangeKind, CodeLanguage, DataFormat, DiffError, DiffOptions, FakeOptions, FAKE_MAX_COUNT, FlattenStyle, FormatOptions, IndentStyle, JsonDiff, JsonError, MaskMode, NdjsonAction, NdjsonReport, NestedColumns, PatchError, RedactRules, SchemaDraft, SchemaError, SchemaOptions, SchemaReport, SearchOptions, SqlDialect, SqlOptions, TreeRow, ValueDetector};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Instant;
+use std::time::Duration;
+
+/// 查找结果：(可见行, 匹配数, 文本视图下输出的文档)，任务被取消时为None
+type SearchResult = Result<Option<(Vec<TreeRow>, usize, Option<JsonOutput>)>, JsonError>;
+
+/// NDJSON处理结果：摘要、输出和第一处无效行的出错范围，在后台任务中整理好再交给界面
+struct NdjsonResult {
+    summary: String,
+    output: JsonOutput,
+    selection: Option<(usize, usize)>,
+}
+
+impl NdjsonResult {
+    fn new(input: &str, action: NdjsonAction, report: NdjsonReport) -> Self {
+        let summary = if report.errors.is_empty() {
+            format!("共{}行，全部有效", report.lines)
+        } else {
+            let numbers: Vec<String> = report.errors.iter().take(10).map(|e| e.line.to_string()).collect();
+            let more = if report.errors.len() > numbers.len() { "等" } else { "" };
+            let handling = match action {
+                NdjsonAction::Format | NdjsonAction::Minify => "，已原样保留",
+                NdjsonAction::ToArray => "，已跳过",
+                NdjsonAction::Validate => "",
+            };
+            format!(
+                "共{}行，{}行无效（第{}行{}）{}",
+                report.lines,
+                report.errors.len(),
+                numbers.join("、"),
+                more,
+                handling
+            )
+        };
+        let selection = report.errors.first().and_then(|first| {
+            let loc = first.error.location.as_ref()?;
+            let line_start: usize = input.split_inclusive('\n').take(first.line - 1).map(str::len).sum();
+            let start = line_start + loc.offset;
+            Some((start, start + loc.len))
+        });
+        let output = match action {
+            NdjsonAction::Validate if report.errors.is_empty() => summary.clone(),
+            _ => report.output,
+        };
+        Self {
+            summary,
+            output: JsonOutput::new(output),
+            selection,
+        }
+    }
+}
+
+/// 在文本视图中显示的结果：过大时只保留开头部分，完整结果留作保存
+struct JsonOutput {
+    shown: CodeText,
+    full: Option<Arc<str>>,
+}
+
+impl JsonOutput {
+    /// 截取预览并构建行索引，应在产生结果的后台任务中调用
+    fn new(output: String) -> Self {
+        if output.len() <= OUTPUT_DISPLAY_LIMIT {
+            return Self { shown: CodeText::new(output), full: None };
+        }
+        let note = format!("结果过大（{}KB），仅显示开头部分，请点击“保存结果”写入完整内容", output.len() / 1024);
+        let preview = EventHandler::text_preview(&output, &note);
+        Self {
+            shown: CodeText::new(preview),
+            full: Some(Arc::from(output)),
+        }
+    }
+}
+
+/// 后台任务管理器及当前正在运行的JSON任务，新任务开始前取消旧任务
+#[derive(Clone)]
+struct JsonJob {
+    jobs: JobManager,
+    current: Arc<Mutex<Option<JobId>>>,
+}
+
+impl JsonJob {
+    fn new(jobs: &JobManager) -> Self {
+        Self { jobs: jobs.clone(), current: Arc::new(Mutex::new(None)) }
+    }
+}
+
+// 后台任务的最长运行时间，超时后自动取消
+const JSON_JOB_TIMEOUT: Duration = Duration::from_secs(120);
+
+// 超过该大小的文本不放入输入框/输出视图，只显示开头的预览；输出视图只渲染可见行，可容纳更大的文本
+const INPUT_DISPLAY_LIMIT: usize = 1_000_000;
+const OUTPUT_DISPLAY_LIMIT: usize = 8_000_000;
+const PREVIEW_SIZE: usize = 64 * 1024;
+// 达到该大小的输入在后台任务中处理，较小的输入同步处理
+const BACKGROUND_INPUT_SIZE: usize = 5000;
 
 pub struct EventHandler {
     window: Weak<MainWindow>,
     time_logic: TimeLogic,
     json_logic: JsonLogic,
+    jobs: JobManager,
+    json_job: JsonJob,
+    // 查找、树形视图解析和读取文件各自只保留最新的任务
+    search_job: JsonJob,
+    tree_job: JsonJob,
+    file_job: JsonJob,
+    config_service: ConfigService,
+    file_service: FileService,
 }
 
 impl EventHandler {
     pub fn new(window: Weak<MainWindow>) -> Self {
+        // 工作线程数随CPU核数，限制在2-4个之间
+        let workers = thread::available_parallelism().map_or(2, |n| n.get().clamp(2, 4));
+        let jobs = JobManager::new(workers);
         Self {
             window: window.clone(),
             time_logic: TimeLogic::new(),
             json_logic: JsonLogic::new(),
+            json_job: JsonJob::new(&jobs),
+            search_job: JsonJob::new(&jobs),
+            tree_job: JsonJob::new(&jobs),
+            file_job: JsonJob::new(&jobs),
+            jobs,
+            config_service: ConfigService::new(),
+            file_service: FileService::new(),
         }
     }
 
@@ -26,6 +145,14 @@ impl EventHandler {
         self.handle_time_events();
         // 处理JSON相关事件
         self.handle_json_events();
+        // 处理JSON文件的打开、保存和拖放
+        self.handle_json_file_events();
+        // 处理输出视图
+        self.handle_json_output_events();
+        // 处理文档内的查找和过滤
+        self.handle_json_search_events();
+        // 处理后台任务指示
+        self.handle_job_events();
     }
 
     fn handle_time_events(&self) {
@@ -147,125 +274,1580 @@ impl EventHandler {
             
             window.on_show_warning(move |warning: SharedString| {
                 if let Some(window) = window_weak.upgrade() {
-                    window.set_output(warning.into());
+                    Self::show_output_message(&window, &warning);
                 }
             });
             
             // JSON格式化
             let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
             let window_weak = self.window.clone();
             window.on_format_json(move |input: SharedString| {
-                Self::process_json(&window_weak, &json_logic, &input, |logic, text| logic.format(text), "JSON格式化");
+                let options = window_weak.upgrade().map(|w| Self::json_format_options(&w)).unwrap_or_default();
+                let sync_options = options.clone();
+                Self::process_json(&window_weak, &json_logic, &json_job, &input, options, move |logic, text| logic.format(text, &sync_options), |logic, text, options, token, progress| logic.format_with_progress(text, options, token, progress), "JSON格式化");
             });
 
             // JSON压缩
             let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
             let window_weak = self.window.clone();
             window.on_minify_json(move |input: SharedString| {
-                Self::process_json(&window_weak, &json_logic, &input, |logic, text| logic.minify(text), "JSON压缩");
+                let options = window_weak.upgrade().map(|w| Self::json_format_options(&w)).unwrap_or_default();
+                let sync_options = options.clone();
+                Self::process_json(&window_weak, &json_logic, &json_job, &input, options, move |logic, text| logic.minify(text, &sync_options), |logic, text, options, token, progress| logic.minify_with_progress(text, options, token, progress), "JSON压缩");
+            });
+
+            // JSON规范化（RFC 8785）并计算摘要
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_canonicalize_json(move |input: SharedString| {
+                Self::canonicalize_json(&window_weak, &json_logic, &json_job, &input);
+            });
+
+            // JSON查询（JSONPath / jq）
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_query_json(move |input: SharedString, query: SharedString| {
+                if query.trim().is_empty() {
+                    if let Some(window) = window_weak.upgrade() {
+                        Self::show_output_message(&window, "请输入查询表达式，JSONPath以 $ 开头，其余按jq表达式处理");
+                    }
+                    return;
+                }
+                let options = window_weak.upgrade().map(|w| Self::json_format_options(&w)).unwrap_or_default();
+                let sync_options = options.clone();
+                let sync_query = query.to_string();
+                let query = query.to_string();
+                Self::process_json(&window_weak, &json_logic, &json_job, &input, options, move |logic, text| logic.query(text, &sync_query, &sync_options), move |logic, text, options, token, progress| logic.query_with_progress(text, &query, options, token, progress), "JSON查询");
+            });
+
+            // 格式化选项变更时保存到配置
+            let config_service = self.config_service.clone();
+            let window_weak = self.window.clone();
+            window.on_json_options_changed(move || {
+                if let Some(window) = window_weak.upgrade() {
+                    let mut config = config_service.load();
+                    config.json_format = Self::json_format_options(&window);
+                    if let Err(e) = config_service.save(&config) {
+                        eprintln!("{}", e);
+                    }
+                }
+            });
+
+            // 初始化时恢复上次保存的格式化选项
+            let config = self.config_service.load();
+            Self::apply_json_format_options(&window, &config.json_format);
+
+            // 结构化对比两个JSON文档
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_diff_json(move |left: SharedString, right: SharedString| {
+                Self::diff_json(&window_weak, &json_logic, &json_job, &left, &right);
+            });
+
+            // 将右侧补丁应用到左侧文档
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_apply_json_patch(move |document: SharedString, patch: SharedString| {
+                Self::apply_json_patch(&window_weak, &json_logic, &json_job, &document, &patch);
+            });
+
+            // 使用右侧的Schema校验输入
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_validate_json_schema(move |instance: SharedString, schema: SharedString| {
+                Self::validate_json_schema(&window_weak, &json_logic, &json_job, &instance, &schema);
+            });
+
+            // 从样本推断JSON Schema
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_infer_json_schema(move |input: SharedString| {
+                Self::infer_json_schema(&window_weak, &json_logic, &json_job, &input);
+            });
+
+            // 按输出格式选择生成类型定义
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_generate_json_code(move |input: SharedString, format_index: i32| {
+                if let Some(language) = Self::get_code_language_by_index(format_index) {
+                    Self::generate_json_code(&window_weak, &json_logic, &json_job, &input, language);
+                }
+            });
+
+            // 由对象数组生成建表语句和INSERT语句
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_generate_json_sql(move |input: SharedString, dialect: i32, table: SharedString, nested: i32| {
+                let sql_options = SqlOptions {
+                    dialect: Self::get_sql_dialect_by_index(dialect),
+                    table: table.to_string(),
+                    nested: if nested == 1 { NestedColumns::Flatten } else { NestedColumns::Json },
+                };
+                Self::generate_json_sql(&window_weak, &json_logic, &json_job, &input, sql_options);
+            });
+
+            // 在JSON、YAML和TOML之间转换
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_convert_json(move |input: SharedString, from_index: i32, to_index: i32| {
+                let from = Self::get_data_format_by_index(from_index);
+                let to = Self::get_data_format_by_index(to_index);
+                let options = window_weak.upgrade().map(|w| Self::json_format_options(&w)).unwrap_or_default();
+                let sync_options = options.clone();
+                let operation_name = format!("{}转{}", from.name(), to.name());
+                Self::process_json(&window_weak, &json_logic, &json_job, &input, options, move |logic, text| logic.convert(text, from, to, &sync_options), move |logic, text, options, token, progress| logic.convert_with_progress(text, from, to, options, token, progress), &operation_name);
+            });
+
+            // NDJSON逐行处理，4为JSON数组转NDJSON
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_process_ndjson(move |input: SharedString, action_index: i32| {
+                Self::process_ndjson(&window_weak, &json_logic, &json_job, &input, action_index);
+            });
+
+            // 展开以字符串形式嵌套的JSON
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_expand_nested_json(move |input: SharedString| {
+                Self::expand_nested_json(&window_weak, &json_logic, &json_job, &input);
+            });
+
+            // 扁平化/还原嵌套
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_flatten_json(move |input: SharedString, style: i32, separator: SharedString| {
+                let style = Self::get_flatten_style_by_index(style, &separator);
+                Self::flatten_json(&window_weak, &json_logic, &json_job, &input, style, false);
+            });
+
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_unflatten_json(move |input: SharedString, style: i32, separator: SharedString| {
+                let style = Self::get_flatten_style_by_index(style, &separator);
+                Self::flatten_json(&window_weak, &json_logic, &json_job, &input, style, true);
+            });
+
+            // 按规则脱敏敏感字段，执行时保存规则
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let config_service = self.config_service.clone();
+            let window_weak = self.window.clone();
+            window.on_redact_json(move |input: SharedString| {
+                Self::redact_json(&window_weak, &json_logic, &json_job, &config_service, &input);
+            });
+            Self::apply_json_redact_rules(&window, &config.json_redact);
+
+            // 按Schema或样本生成假数据
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_fake_json(move |input: SharedString, count: SharedString, seed: SharedString, ndjson: bool| {
+                Self::fake_json(&window_weak, &json_logic, &json_job, &input, &count, &seed, ndjson);
+            });
+
+            // 统计文档结构和体积构成
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_json_stats(move |input: SharedString| {
+                Self::json_stats(&window_weak, &json_logic, &json_job, &input);
+            });
+
+            // 将树形视图中选中的节点字符串化
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_stringify_json_node(move |input: SharedString, pointer: SharedString| {
+                let pointer = pointer.to_string();
+                Self::run_json_output_task(
+                    &window_weak,
+                    &json_logic,
+                    &json_job,
+                    &input,
+                    "请输入JSON内容",
+                    "字符串化节点",
+                    move |logic, input, options, token| logic.stringify_node(input, &pointer, options, token),
+                );
+            });
+
+            // 转义/去转义JSON字符串
+            let json_logic = self.json_logic.clone();
+            let window_weak = self.window.clone();
+            window.on_escape_json_string(move |input: SharedString| {
+                if let Some(window) = window_weak.upgrade() {
+                    let options = Self::json_format_options(&window);
+                    Self::show_output_message(&window, &json_logic.escape(&input, &options));
+                    window.set_json_view_mode(0);
+                }
+            });
+
+            let json_logic = self.json_logic.clone();
+            let window_weak = self.window.clone();
+            window.on_unescape_json_string(move |input: SharedString| {
+                if let Some(window) = window_weak.upgrade() {
+                    match json_logic.unescape(&input) {
+                        Ok(output) => {
+                            Self::show_output_message(&window, &output);
+                            window.set_json_view_mode(0);
+                        }
+                        Err(error) => Self::show_json_error(&window, &error),
+                    }
+                }
+            });
+
+            // 载入树形视图
+            let json_logic = self.json_logic.clone();
+            let tree_job = self.tree_job.clone();
+            let window_weak = self.window.clone();
+            window.on_load_json_tree(move |input: SharedString| {
+                Self::load_json_tree(&window_weak, &json_logic, &tree_job, &input);
+            });
+
+            // 展开/折叠树节点
+            let json_logic = self.json_logic.clone();
+            let window_weak = self.window.clone();
+            window.on_toggle_json_tree_node(move |pointer: SharedString| {
+                if let Some(window) = window_weak.upgrade() {
+                    Self::set_json_tree_rows(&window, json_logic.toggle_tree_node(&pointer));
+                }
+            });
+
+            let json_logic = self.json_logic.clone();
+            let window_weak = self.window.clone();
+            window.on_expand_json_tree(move || {
+                if let Some(window) = window_weak.upgrade() {
+                    Self::set_json_tree_rows(&window, json_logic.expand_tree());
+                }
+            });
+
+            let json_logic = self.json_logic.clone();
+            let window_weak = self.window.clone();
+            window.on_collapse_json_tree(move || {
+                if let Some(window) = window_weak.upgrade() {
+                    Self::set_json_tree_rows(&window, json_logic.collapse_tree());
+                }
+            });
+
+            // 复制节点路径（JSONPath或JSON Pointer）
+            let json_logic = self.json_logic.clone();
+            let window_weak = self.window.clone();
+            window.on_json_tree_node_path(move |pointer: SharedString, as_pointer: bool| {
+                let path = json_logic.tree_node_path(&pointer, as_pointer);
+                if let Some(window) = window_weak.upgrade() {
+                    let status = match &path {
+                        Some(path) if path.is_empty() => "已复制根节点路径（空JSON Pointer）".to_string(),
+                        Some(path) => format!("已复制: {}", path),
+                        None => "请先选中一个节点".to_string(),
+                    };
+                    window.set_json_tree_status(status.into());
+                }
+                path.unwrap_or_default().into()
+            });
+
+            // 取消正在进行的JSON处理
+            let json_job = self.json_job.clone();
+            let window_weak = self.window.clone();
+            window.on_cancel_json(move || {
+                Self::cancel_json_job(&json_job);
+                if let Some(window) = window_weak.upgrade() {
+                    window.set_json_processing(false);
+                    window.set_progress(0);
+                    Self::show_output_message(&window, "已取消处理");
+                }
             });
         }
     }
-    
-    fn process_json<F>(
-        window_weak: &Weak<MainWindow>, 
-        json_logic: &JsonLogic, 
-        input: &SharedString, 
-        processor: F, 
-        operation_name: &str
-    ) where 
-        F: Fn(&JsonLogic, &str) -> String + Send + 'static + Clone,
-    {
-        if let Some(window) = window_weak.upgrade() {
-            // 设置处理状态
-            window.set_json_processing(true);
-            window.set_progress(0);  // 重置进度
-            
-            // 处理空输入
-            if input.is_empty() {
-                window.set_output("请输入JSON内容".into());
-                window.set_json_processing(false);
-                return;
-            }
-            
-            // 对于小型JSON，直接同步处理
-            if input.len() < 5000 {
-                let result = processor(json_logic, input);
-                // 设置输出结果
-                window.set_output(result.into());
-                // 确保处理状态被重置
-                window.set_json_processing(false);
-                return;
+
+    fn handle_job_events(&self) {
+        if let Some(window) = self.window.upgrade() {
+            // 任务状态变化时刷新任务指示，当前JSON任务的进度显示在进度条上
+            let jobs = self.jobs.clone();
+            let json_job = self.json_job.clone();
+            // 监听器可能在多个工作线程中同时调用，窗口的弱引用不能跨线程共享，放入锁中
+            let window_weak = Mutex::new(self.window.clone());
+            self.jobs.set_listener(move |event| {
+                let jobs = jobs.clone();
+                let json_job = json_job.clone();
+                let window_weak = window_weak.lock().unwrap().clone();
+                let _ = window_weak.upgrade_in_event_loop(move |window| {
+                    let list = jobs.jobs();
+                    Self::show_job_indicator(&window, &list);
+                    if let JobEvent::Progress { id, percent } = event {
+                        if *json_job.current.lock().unwrap() != Some(id) || percent >= 100 {
+                            return;
+                        }
+                        if let Some(job) = list.iter().find(|job| job.id == id) {
+                            window.set_progress(percent as i32);
+                            let message = format!("正在{}... {}%，已用时{}秒", job.name, percent, job.elapsed.as_secs());
+                            Self::show_output_message(&window, &message);
+                        }
+                    }
+                });
+            });
+
+            // 取消全部后台任务
+            let jobs = self.jobs.clone();
+            let json_job = self.json_job.clone();
+            let search_job = self.search_job.clone();
+            let tree_job = self.tree_job.clone();
+            let file_job = self.file_job.clone();
+            let window_weak = self.window.clone();
+            window.on_cancel_all_jobs(move || {
+                jobs.cancel_all();
+                let had = |job: &JsonJob| job.current.lock().unwrap().take().is_some();
+                let Some(window) = window_weak.upgrade() else {
+                    return;
+                };
+                if had(&json_job) {
+                    window.set_json_processing(false);
+                    window.set_progress(0);
+                    Self::show_output_message(&window, "已取消处理");
+                }
+                if had(&search_job) {
+                    window.set_json_search_status("已取消查找".into());
+                }
+                if had(&tree_job) {
+                    window.set_json_tree_status("已取消解析".into());
+                }
+                if had(&file_job) {
+                    window.set_json_file_status("已取消读取文件".into());
+                }
+            });
+        }
+    }
+
+    /// 显示排队中和运行中的任务
+    fn show_job_indicator(window: &MainWindow, jobs: &[JobInfo]) {
+        let summary: Vec<String> = jobs
+            .iter()
+            .map(|job| match (job.running, job.progress) {
+                (false, _) => format!("{}（排队中）", job.name),
+                (true, Some(percent)) => format!("{} {}%", job.name, percent),
+                (true, None) => job.name.clone(),
+            })
+            .collect();
+        window.set_job_count(jobs.len() as i32);
+        window.set_job_summary(summary.join("、").into());
+    }
+
+    fn handle_json_search_events(&self) {
+        if let Some(window) = self.window.upgrade() {
+            let json_logic = self.json_logic.clone();
+            let search_job = self.search_job.clone();
+            let window_weak = self.window.clone();
+            window.on_search_json(move |input: SharedString, pattern: SharedString| {
+                Self::search_json(&window_weak, &json_logic, &search_job, &input, &pattern);
+            });
+
+            let json_logic = self.json_logic.clone();
+            let window_weak = self.window.clone();
+            window.on_step_json_match(move |forward: bool| {
+                if let Some(window) = window_weak.upgrade() {
+                    Self::step_json_match(&window, &json_logic, forward);
+                }
+            });
+        }
+    }
+
+    /// 在输入的文档中查找；文本视图下输出格式化（或过滤后）的文档，树形视图下展开到匹配节点。
+    /// 新的查找开始时取消尚未完成的旧查找，其结果不再显示
+    fn search_json(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, search_job: &JsonJob, input: &SharedString, pattern: &SharedString) {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        Self::cancel_json_job(search_job);
+        if pattern.is_empty() {
+            Self::set_json_tree_rows(&window, json_logic.clear_search());
+            window.set_json_search_status("".into());
+            return;
+        }
+        if input.is_empty() {
+            window.set_json_search_status("请输入JSON内容".into());
+            return;
+        }
+
+        let options = Self::json_format_options(&window);
+        let search_options = SearchOptions {
+            regex: window.get_json_search_regex(),
+            case_sensitive: window.get_json_search_case_sensitive(),
+        };
+        let filter = window.get_json_search_filter();
+        let text_view = window.get_json_view_mode() == 0;
+        let input = Self::json_input(json_logic, input);
+        let pattern = pattern.to_string();
+        let small = input.len() < BACKGROUND_INPUT_SIZE;
+
+        let search = {
+            let json_logic = json_logic.clone();
+            move |token: &CancelToken| -> SearchResult {
+                let Some((rows, count)) = json_logic.search(input, &pattern, &search_options, filter, &options, token)? else {
+                    return Ok(None);
+                };
+                let text = if text_view { json_logic.search_text(&options).map(JsonOutput::new) } else { None };
+                Ok(Some((rows, count, text)))
             }
-            
-            // 提示用户处理中
-            window.set_output("正在处理中，请稍候...".into());
-            
-            // 使用带进度的处理方法
-            let input_str = input.to_string();
-            let json_logic_clone = json_logic.clone();
-            let window_clone = window_weak.clone();
-            let operation = operation_name.to_string();
-            
-            // 创建一个线程处理JSON并更新进度
-            thread::spawn(move || {
-                // 获取带进度的处理器
-                let receiver = if operation.contains("格式化") {
-                    json_logic_clone.format_with_progress(input_str)
+        };
+        let json_logic = json_logic.clone();
+        let show = move |window: &MainWindow, result: SearchResult| match result {
+            Ok(Some((rows, count, text))) => {
+                Self::set_json_tree_rows(window, rows);
+                if let Some(text) = text {
+                    Self::show_json_output(window, &json_logic, text);
+                }
+                if count == 0 {
+                    window.set_json_search_status("没有找到匹配项".into());
                 } else {
-                    json_logic_clone.minify_with_progress(input_str)
+                    Self::step_json_match(window, &json_logic, true);
+                }
+            }
+            Ok(None) => {}
+            Err(error) => {
+                window.set_json_search_status(error.to_string().into());
+                if let Some(loc) = &error.location {
+                    window.invoke_select_json_input(loc.offset as i32, (loc.offset + loc.len) as i32);
+                }
+            }
+        };
+
+        if small {
+            show(&window, search(&CancelToken::new()));
+            return;
+        }
+        window.set_json_search_status("正在查找...".into());
+        Self::submit_current_job(window_weak, search_job, "查找", move |job| search(job.token()), show, |window, failure| {
+            window.set_json_search_status(failure.into())
+        });
+    }
+
+    /// 移动到下一个或上一个匹配，并在当前视图中滚动到该位置
+    fn step_json_match(window: &MainWindow, json_logic: &JsonLogic, forward: bool) {
+        let (rows, step) = json_logic.step_match(forward);
+        let Some((index, count, pointer)) = step else {
+            window.set_json_search_status("请先查找".into());
+            return;
+        };
+        let row = rows.iter().position(|row| row.pointer == pointer);
+        Self::set_json_tree_rows(window, rows);
+        let location = if pointer.is_empty() { "(根节点)" } else { pointer.as_str() };
+        window.set_json_search_status(format!("第 {}/{} 个匹配: {}", index + 1, count, location).into());
+
+        if window.get_json_view_mode() == 0 {
+            if let Some(line) = json_logic.search_line(index) {
+                window.invoke_show_json_output_line(line as i32);
+            }
+        } else if let Some(row) = row {
+            window.invoke_show_json_tree_row(row as i32, pointer.into());
+        }
+    }
+
+    fn handle_json_output_events(&self) {
+        if let Some(window) = self.window.upgrade() {
+            // 将输出中的字节偏移换算为行号
+            let window_weak = self.window.clone();
+            window.on_json_output_offset_line(move |offset: i32| {
+                let Some(window) = window_weak.upgrade() else {
+                    return 0;
                 };
-                
-                let start_time = Instant::now();
-                
-                // 处理接收到的更新
-                for update in receiver {
-                    if let Some(window) = window_clone.upgrade() {
-                        match update {
-                            ProcessUpdate::Progress(progress) => {
-                                window.set_progress(progress as i32);
-                                
-                                // 每隔一定时间更新处理状态消息
-                                let elapsed_secs = start_time.elapsed().as_secs();
-                                if progress < 100 {
-                                    let progress_msg = format!("正在处理中... {}%，已用时{}秒", progress, elapsed_secs);
-                                    window.set_output(progress_msg.into());
-                                }
-                            },
-                            ProcessUpdate::Result(result) => {
-                                // 设置最终结果
-                                window.set_output(result.into());
-                                window.set_json_processing(false);
-                                window.set_progress(0);  // 重置进度
-                                
-                                // 记录总处理时间
-                                let elapsed = start_time.elapsed();
-                                println!("{}总耗时: {:?}", operation, elapsed);
-                                break;
-                            }
-                        }
-                    } else {
-                        // 窗口已关闭，退出循环
-                        break;
+                let output = window.get_output();
+                let end = (offset.max(0) as usize).min(output.len());
+                output.as_bytes()[..end].iter().filter(|b| **b == b'\n').count() as i32
+            });
+        }
+    }
+
+    fn handle_json_file_events(&self) {
+        if let Some(window) = self.window.upgrade() {
+            // 打开文件
+            let json_logic = self.json_logic.clone();
+            let json_job = self.json_job.clone();
+            let file_job = self.file_job.clone();
+            let file_service = self.file_service.clone();
+            let window_weak = self.window.clone();
+            window.on_open_json_file(move |path: SharedString| {
+                if path.trim().is_empty() {
+                    if let Some(window) = window_weak.upgrade() {
+                        window.set_json_file_status("请输入文件路径，或将文件拖放到窗口".into());
                     }
+                    return;
                 }
+                Self::open_json_file(&window_weak, &json_logic, &json_job, &file_job, &file_service, PathBuf::from(path.trim()));
             });
-            
-            // 设置超时定时器
-            let window_timeout = window_weak.clone();
-            Timer::default().start(
-                slint::TimerMode::SingleShot,
-                std::time::Duration::from_secs(120), // 延长超时时间到120秒
-                move || {
-                    if let Some(window) = window_timeout.upgrade() {
-                        if window.get_json_processing() {
-                            window.set_json_processing(false);
-                            window.set_progress(0);  // 重置进度
-                            window.set_output("处理超时，JSON数据量可能过大或格式有误，请尝试分批处理".into());
-                        }
+
+            // 关闭文件，恢复为在输入框中编辑
+            let json_logic = self.json_logic.clone();
+            let file_job = self.file_job.clone();
+            let window_weak = self.window.clone();
+            window.on_close_json_file(move || {
+                Self::cancel_json_job(&file_job);
+                let file = json_logic.file_input();
+                json_logic.set_file_input(None);
+                if let Some(window) = window_weak.upgrade() {
+                    window.set_json_input_file("".into());
+                    // 完整显示过的内容留在输入框中继续编辑，只显示了开头部分的则清空
+                    if file.is_some_and(|text| text.len() > INPUT_DISPLAY_LIMIT) {
+                        window.set_json_input("".into());
                     }
+                    window.set_json_file_status("".into());
+                }
+            });
+
+            // 保存结果，过大未完整显示的结果直接写入完整内容
+            let json_logic = self.json_logic.clone();
+            let jobs = self.jobs.clone();
+            let file_service = self.file_service.clone();
+            let window_weak = self.window.clone();
+            window.on_save_json_output(move |path: SharedString| {
+                Self::save_json_output(&window_weak, &json_logic, &jobs, &file_service, PathBuf::from(path.trim()));
+            });
+
+            // 拖放到输入区的文件载入为输入，拖放到结果区的文件作为保存位置，需点击“保存结果”才写入
+            #[cfg(feature = "file-drop")]
+            {
+                let json_logic = self.json_logic.clone();
+                let json_job = self.json_job.clone();
+                let file_job = self.file_job.clone();
+                let file_service = self.file_service.clone();
+                let window_weak = self.window.clone();
+                file_drop::handle_file_drops(&window, move |window, path, target| {
+                    window.set_json_file_path(path.display().to_string().into());
+                    match target {
+                        DropTarget::Input => Self::open_json_file(&window_weak, &json_logic, &json_job, &file_job, &file_service, path),
+                        DropTarget::Output => window.set_json_file_status(
+                            format!("已将保存位置设为 {}，点击“保存结果”写入（将覆盖该文件）", path.display()).into(),
+                        ),
+                    }
+                });
+            }
+        }
+    }
+
+    /// 在后台读取文件，处理时直接使用读取的内容；输入框只用于显示，过大的文件只显示开头部分。
+    /// 连续打开多个文件时只载入最后一个
+    fn open_json_file(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, file_job: &JsonJob, file_service: &FileService, path: PathBuf) {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        Self::cancel_json_job(json_job);
+        Self::cancel_json_job(file_job);
+        window.set_json_file_status(format!("正在读取 {} ...", path.display()).into());
+
+        let json_logic = json_logic.clone();
+        let file_service = file_service.clone();
+        let read_path = path.clone();
+        let read = move |_: &JobContext| file_service.read_text(&read_path, MAX_INPUT_SIZE);
+        let fail = |window: &MainWindow, failure: &str| window.set_json_file_status(failure.into());
+        Self::submit_current_job(window_weak, file_job, "读取文件", read, move |window, result| match result {
+            Ok(text) => {
+                let size = text.len();
+                if size > INPUT_DISPLAY_LIMIT {
+                    let note = format!("已载入文件 {}（{}KB），内容过大，仅显示开头部分；各项处理将使用完整文件", path.display(), size / 1024);
+                    window.set_json_input(Self::text_preview(&text, &note).into());
+                } else {
+                    window.set_json_input(text.as_str().into());
+                }
+                window.set_json_input_file(path.display().to_string().into());
+                json_logic.set_file_input(Some(Arc::from(text)));
+                window.set_json_file_status(format!("已打开 {}（{}KB）", path.display(), size / 1024).into());
+            }
+            Err(e) => window.set_json_file_status(e.into()),
+        }, fail);
+    }
+
+    /// 将输出写入文件
+    fn save_json_output(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, jobs: &JobManager, file_service: &FileService, path: PathBuf) {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        if path.as_os_str().is_empty() {
+            window.set_json_file_status("请输入保存路径".into());
+            return;
+        }
+        let displayed = window.get_output();
+        let content = json_logic
+            .full_output(&displayed)
+            .unwrap_or_else(|| Arc::from(displayed.as_str()));
+        window.set_json_file_status(format!("正在保存到 {} ...", path.display()).into());
+
+        let file_service = file_service.clone();
+        let write = move |_: &CancelToken| match file_service.write_text(&path, &content) {
+            Ok(()) => format!("已保存到 {}（{}KB）", path.display(), content.len() / 1024),
+            Err(e) => e,
+        };
+        Self::run_background(
+            window_weak,
+            jobs,
+            "保存文件",
+            write,
+            |window, status| window.set_json_file_status(status.into()),
+            |window, failure| window.set_json_file_status(failure.into()),
+        );
+    }
+
+    /// 处理时使用的输入：已从文件载入时使用文件内容，否则使用输入框中的文本
+    fn json_input(json_logic: &JsonLogic, input: &SharedString) -> Arc<str> {
+        json_logic.file_input().unwrap_or_else(|| Arc::from(input.as_str()))
+    }
+
+    /// 显示处理结果，过大时只显示开头部分，完整结果保留用于保存
+    fn show_json_output(window: &MainWindow, json_logic: &JsonLogic, output: JsonOutput) {
+        json_logic.set_full_output(output.full.map(|full| (output.shown.as_str().to_string(), full)));
+        Self::show_code_text(window, output.shown);
+    }
+
+    /// 在文本视图中显示提示信息
+    fn show_output_message(window: &MainWindow, message: &str) {
+        Self::show_code_text(window, CodeText::new(message));
+    }
+
+    /// 设置输出文本及按行高亮的模型，列表只渲染可见行；行索引已随文本构建好，界面线程不再扫描全文
+    fn show_code_text(window: &MainWindow, code: CodeText) {
+        window.set_output(code.as_str().into());
+        window.set_json_output_lines(ModelRc::new(JsonCodeModel::new(code)));
+    }
+
+    /// 截取文本开头用于显示，尽量在行尾截断，并附加说明
+    fn text_preview(text: &str, note: &str) -> String {
+        let mut end = PREVIEW_SIZE.min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        if let Some(line_end) = text[..end].rfind('\n') {
+            end = line_end;
+        }
+        format!("{}\n……\n（{}）", &text[..end], note)
+    }
+
+    fn get_flatten_style_by_index(idx: i32, separator: &str) -> FlattenStyle {
+        match idx {
+            1 => FlattenStyle::Env { separator: separator.to_string() },
+            _ => FlattenStyle::Dotted,
+        }
+    }
+
+    fn get_indent_style_by_index(idx: i32) -> IndentStyle {
+        match idx {
+            0 => IndentStyle::Spaces(2),
+            1 => IndentStyle::Spaces(4),
+            2 => IndentStyle::Tab,
+            _ => IndentStyle::Spaces(2), // 默认缩进
+        }
+    }
+
+    /// 输入/输出格式选择器中的数据格式，0-2分别为JSON、YAML、TOML
+    fn get_data_format_by_index(idx: i32) -> DataFormat {
+        match idx {
+            1 => DataFormat::Yaml,
+            2 => DataFormat::Toml,
+            _ => DataFormat::Json,
+        }
+    }
+
+    /// 输出格式选择器中代码生成项对应的语言，0-2为数据格式
+    fn get_code_language_by_index(idx: i32) -> Option<CodeLanguage> {
+        match idx {
+            3 => Some(CodeLanguage::Rust),
+            4 => Some(CodeLanguage::TypeScript),
+            5 => Some(CodeLanguage::Go),
+            6 => Some(CodeLanguage::Java),
+            _ => None,
+        }
+    }
+
+    /// SQL方言选择器，0-2分别为MySQL、PostgreSQL、SQLite
+    fn get_sql_dialect_by_index(idx: i32) -> SqlDialect {
+        match idx {
+            1 => SqlDialect::PostgreSql,
+            2 => SqlDialect::Sqlite,
+            _ => SqlDialect::MySql,
+        }
+    }
+
+    fn get_indent_index(indent: IndentStyle) -> i32 {
+        match indent {
+            IndentStyle::Spaces(4) => 1,
+            IndentStyle::Tab => 2,
+            _ => 0,
+        }
+    }
+
+    /// 从界面读取JSON格式化选项
+    fn json_format_options(window: &MainWindow) -> FormatOptions {
+        FormatOptions {
+            indent: Self::get_indent_style_by_index(window.get_json_indent_index()),
+            sort_keys: window.get_json_sort_keys(),
+            inline_width: window.get_json_inline_width().max(0) as usize,
+            ensure_ascii: window.get_json_ensure_ascii(),
+            trailing_newline: window.get_json_trailing_newline(),
+            relaxed: window.get_json_relaxed(),
+            keep_comments: window.get_json_keep_comments(),
+        }
+    }
+
+    /// 将JSON格式化选项同步到界面
+    fn apply_json_format_options(window: &MainWindow, options: &FormatOptions) {
+        window.set_json_indent_index(Self::get_indent_index(options.indent));
+        window.set_json_sort_keys(options.sort_keys);
+        window.set_json_inline_width(options.inline_width as i32);
+        window.set_json_ensure_ascii(options.ensure_ascii);
+        window.set_json_trailing_newline(options.trailing_newline);
+        window.set_json_relaxed(options.relaxed);
+        window.set_json_keep_comments(options.keep_comments);
+    }
+
+    /// 显示JSON错误，并在输入框中选中出错位置
+    fn show_json_error(window: &MainWindow, error: &JsonError) {
+        Self::show_output_message(window, &error.to_string());
+        window.set_json_processing(false);
+        window.set_progress(0);
+        if let Some(loc) = &error.location {
+            window.invoke_select_json_input(loc.offset as i32, (loc.offset + loc.len) as i32);
+        }
+    }
+
+    /// 将树形视图的可见行同步到界面
+    fn set_json_tree_rows(window: &MainWindow, rows: Vec<TreeRow>) {
+        let nodes: Vec<JsonTreeNode> = rows
+            .into_iter()
+            .map(|row| JsonTreeNode {
+                depth: row.depth as i32,
+                expandable: row.is_container() && row.child_count > 0,
+                key: row.key.into(),
+                kind: row.kind.into(),
+                summary: row.summary.into(),
+                expanded: row.expanded,
+                pointer: row.pointer.into(),
+                matched: row.matched,
+            })
+            .collect();
+        window.set_json_tree_rows(ModelRc::new(VecModel::from(nodes)));
+    }
+
+    /// 解析输入并载入树形视图，较大的输入在后台线程解析；新的解析开始时取消尚未完成的旧解析
+    fn load_json_tree(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, tree_job: &JsonJob, input: &SharedString) {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        Self::cancel_json_job(tree_job);
+        if input.is_empty() {
+            json_logic.clear_tree();
+            Self::set_json_tree_rows(&window, Vec::new());
+            window.set_json_tree_status("请输入JSON内容".into());
+            return;
+        }
+
+        let options = Self::json_format_options(&window);
+        let show_result = |window: &MainWindow, result: Result<Option<Vec<TreeRow>>, JsonError>| match result {
+            Ok(Some(rows)) => {
+                window.set_json_tree_status("单击选中节点，双击或点击箭头展开/折叠".into());
+                Self::set_json_tree_rows(window, rows);
+            }
+            Ok(None) => {}
+            Err(error) => {
+                Self::set_json_tree_rows(window, Vec::new());
+                window.set_json_tree_status(error.to_string().into());
+                if let Some(loc) = &error.location {
+                    window.invoke_select_json_input(loc.offset as i32, (loc.offset + loc.len) as i32);
+                }
+            }
+        };
+
+        let input = Self::json_input(json_logic, input);
+        if input.len() < BACKGROUND_INPUT_SIZE {
+            show_result(&window, json_logic.load_tree(input, &options, &CancelToken::new()));
+            return;
+        }
+
+        window.set_json_tree_status("正在解析...".into());
+        let json_logic = json_logic.clone();
+        Self::submit_current_job(
+            window_weak,
+            tree_job,
+            "解析树形视图",
+            move |job| json_logic.load_tree(input, &options, job.token()),
+            show_result,
+            |window, failure| window.set_json_tree_status(failure.into()),
+        );
+    }
+
+    /// 显示对比结果：文本视图输出JSON Patch，差异视图列出每处变化
+    fn show_json_diff(window: &MainWindow, diff: &JsonDiff, patch: CodeText) {
+        let rows: Vec<JsonDiffRow> = diff
+            .entries
+            .iter()
+            .map(|entry| JsonDiffRow {
+                kind: match entry.kind {
+                    ChangeKind::Added => "added",
+                    ChangeKind::Removed => "removed",
+                    ChangeKind::Changed => "changed",
                 }
+                .into(),
+                path: entry.pointer.clone().into(),
+                old_value: entry.old_preview().into(),
+                new_value: entry.new_preview().into(),
+            })
+            .collect();
+        window.set_json_diff_rows(ModelRc::new(VecModel::from(rows)));
+        if diff.is_empty() {
+            Self::show_output_message(window, "两个JSON在结构上相同，没有差异");
+            window.set_json_view_mode(0);
+        } else {
+            Self::show_code_text(window, patch);
+            window.set_json_view_mode(2);
+        }
+        window.set_json_processing(false);
+        window.set_progress(0);
+    }
+
+    /// 显示对比失败原因，原JSON出错时在输入框中选中出错位置
+    fn show_json_diff_error(window: &MainWindow, error: &DiffError) {
+        if let DiffError::Left(e) = error {
+            Self::show_json_error(window, e);
+        }
+        Self::show_output_message(window, &error.to_string());
+        window.set_json_processing(false);
+        window.set_progress(0);
+    }
+
+    /// 对比两个JSON文档，较大的输入在后台线程中处理，可取消
+    fn diff_json(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, left: &SharedString, right: &SharedString) {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        Self::cancel_json_job(json_job);
+        if left.is_empty() || right.is_empty() {
+            Self::show_output_message(&window, "请在左右两侧分别输入要对比的JSON");
+            return;
+        }
+
+        let options = Self::json_format_options(&window);
+        let diff_options = DiffOptions {
+            ignore_key_order: window.get_json_diff_ignore_key_order(),
+            ignore_array_order: window.get_json_diff_ignore_array_order(),
+            ignore_paths: DiffOptions::parse_ignore_paths(&window.get_json_diff_ignore_paths()),
+        };
+        let (left, right) = (Self::json_input(json_logic, left), right.to_string());
+        let json_logic = json_logic.clone();
+        let large = left.len() + right.len() >= BACKGROUND_INPUT_SIZE;
+        Self::run_json_task(
+            window_weak,
+            json_job,
+            large,
+            "对比",
+            move |token| {
+                let diff = json_logic.diff(&left, &right, &options, &diff_options, token)?;
+                Ok(diff.map(|diff| {
+                    let patch = CodeText::new(if diff.is_empty() { String::new() } else { diff.patch_text(&options) });
+                    (diff, patch)
+                }))
+            },
+            |window, result| match result {
+                Ok(Some((diff, patch))) => Self::show_json_diff(window, &diff, patch),
+                Ok(None) => {}
+                Err(error) => Self::show_json_diff_error(window, &error),
+            },
+        );
+    }
+
+    /// 显示补丁应用失败原因，原JSON出错时在输入框中选中出错位置
+    fn show_json_patch_error(window: &MainWindow, error: &PatchError) {
+        if let PatchError::Document(e) = error {
+            Self::show_json_error(window, e);
+        }
+        Self::show_output_message(window, &error.to_string());
+        window.set_json_processing(false);
+        window.set_progress(0);
+    }
+
+    /// 将补丁应用到文档，结果显示在文本视图中
+    fn apply_json_patch(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, document: &SharedString, patch: &SharedString) {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        Self::cancel_json_job(json_job);
+        if document.is_empty() || patch.is_empty() {
+            Self::show_output_message(&window, "请在左侧输入原JSON，右侧输入补丁");
+            return;
+        }
+
+        let options = Self::json_format_options(&window);
+        let (document, patch) = (Self::json_input(json_logic, document), patch.to_string());
+        let json_logic = json_logic.clone();
+        let output_logic = json_logic.clone();
+        let large = document.len() + patch.len() >= BACKGROUND_INPUT_SIZE;
+        Self::run_json_task(
+            window_weak,
+            json_job,
+            large,
+            "应用补丁",
+            move |token| Ok(json_logic.apply_patch(&document, &patch, &options, token)?.map(|(kind, output)| (kind, JsonOutput::new(output)))),
+            move |window, result| match result {
+                Ok(Some((kind, output))) => {
+                    println!("已应用{}", kind.name());
+                    Self::finish_json_output(window, &output_logic, output);
+                }
+                Ok(None) => {}
+                Err(error) => Self::show_json_patch_error(window, &error),
+            },
+        );
+    }
+
+    /// 显示校验结果：文本视图输出格式化后的文档，校验视图列出每处违规
+    fn show_json_schema_report(window: &MainWindow, report: &SchemaReport, formatted: CodeText) {
+        let rows: Vec<JsonSchemaViolation> = report
+            .violations
+            .iter()
+            .map(|violation| {
+                let (start, end) = violation.span.map_or((-1, -1), |(s, e)| (s as i32, e as i32));
+                JsonSchemaViolation {
+                    instance_path: violation.instance_path.clone().into(),
+                    schema_path: violation.schema_path.clone().into(),
+                    message: violation.message.clone().into(),
+                    start,
+                    end,
+                }
+            })
+            .collect();
+        let summary = if rows.is_empty() {
+            format!("校验通过（{}）", report.draft.name())
+        } else {
+            format!("发现 {} 处违规（{}），点击条目可在下方定位", rows.len(), report.draft.name())
+        };
+        window.set_json_schema_violations(ModelRc::new(VecModel::from(rows)));
+        window.set_json_schema_summary(summary.into());
+        Self::show_code_text(window, formatted);
+        window.set_json_view_mode(3);
+        window.set_json_processing(false);
+        window.set_progress(0);
+    }
+
+    /// 显示无法校验的原因，待校验的JSON出错时在输入框中选中出错位置
+    fn show_json_schema_error(window: &MainWindow, error: &SchemaError) {
+        if let SchemaError::Instance(e) = error {
+            Self::show_json_error(window, e);
+        }
+        Self::show_output_message(window, &error.to_string());
+        window.set_json_view_mode(0);
+        window.set_json_processing(false);
+        window.set_progress(0);
+    }
+
+    /// 使用JSON Schema校验文档，较大的输入在后台线程中处理，可取消
+    fn validate_json_schema(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, instance: &SharedString, schema: &SharedString) {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        Self::cancel_json_job(json_job);
+        if instance.is_empty() || schema.is_empty() {
+            Self::show_output_message(&window, "请在左侧输入JSON，右侧输入JSON Schema");
+            return;
+        }
+
+        let options = Self::json_format_options(&window);
+        let base_dir = window.get_json_schema_base_dir().trim().to_string();
+        let schema_options = SchemaOptions {
+            draft: match window.get_json_schema_draft_index() {
+                1 => Some(SchemaDraft::Draft7),
+                2 => Some(SchemaDraft::Draft2019),
+                3 => Some(SchemaDraft::Draft2020),
+                _ => None,
+            },
+            base_dir: (!base_dir.is_empty()).then(|| base_dir.into()),
+            check_formats: window.get_json_schema_check_formats(),
+        };
+        let (instance, schema) = (Self::json_input(json_logic, instance), schema.to_string());
+        let json_logic = json_logic.clone();
+        let large = instance.len() + schema.len() >= BACKGROUND_INPUT_SIZE;
+        Self::run_json_task(
+            window_weak,
+            json_job,
+            large,
+            "校验",
+            move |token| {
+                let report = json_logic.validate_schema(&instance, &schema, &options, &schema_options, token)?;
+                // 违规位置按完整的格式化文本定位，因此不截取预览
+                Ok(report.map(|mut report| {
+                    let formatted = CodeText::new(std::mem::take(&mut report.formatted));
+                    (report, formatted)
+                }))
+            },
+            |window, result| match result {
+                Ok(Some((report, formatted))) => Self::show_json_schema_report(window, &report, formatted),
+                Ok(None) => {}
+                Err(error) => Self::show_json_schema_error(window, &error),
+            },
+        );
+    }
+
+    /// 从输入的样本推断JSON Schema，结果显示在文本视图中
+    fn infer_json_schema(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, input: &SharedString) {
+        Self::run_json_output_task(
+            window_weak,
+            json_logic,
+            json_job,
+            input,
+            "请输入样本JSON，多个样本可按行输入（JSON Lines）",
+            "推断Schema",
+            |logic, input, options, token| logic.infer_schema(input, options, token),
+        );
+    }
+
+    /// 由对象数组生成SQL，结果显示在文本视图中
+    fn generate_json_sql(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, input: &SharedString, sql_options: SqlOptions) {
+        Self::run_json_output_task(
+            window_weak,
+            json_logic,
+            json_job,
+            input,
+            "请输入对象数组，也可按行输入多个对象（JSON Lines）",
+            &format!("生成{} SQL", sql_options.dialect.name()),
+            move |logic, input, options, token| logic.generate_sql(input, options, &sql_options, token),
+        );
+    }
+
+    /// 从输入的样本生成类型定义，结果显示在文本视图中
+    fn generate_json_code(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, input: &SharedString, language: CodeLanguage) {
+        Self::run_json_output_task(
+            window_weak,
+            json_logic,
+            json_job,
+            input,
+            "请输入样本JSON，多个样本可按行输入（JSON Lines）",
+            &format!("生成{}代码", language.name()),
+            move |logic, input, options, token| logic.generate_code(input, options, language, token),
+        );
+    }
+
+    /// 展开以字符串形式嵌套的JSON，较大的输入在后台线程处理
+    fn expand_nested_json(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, input: &SharedString) {
+        Self::run_json_output_task(
+            window_weak,
+            json_logic,
+            json_job,
+            input,
+            "请输入JSON内容",
+            "展开嵌套JSON",
+            |logic, input, options, token| {
+                let expanded = logic.expand_nested(input, options, token)?;
+                Ok(expanded.map(|(output, count)| {
+                    if count == 0 {
+                        "未发现以字符串形式嵌套的JSON".to_string()
+                    } else {
+                        output
+                    }
+                }))
+            },
+        );
+    }
+
+    /// 按规则脱敏，较大的输入在后台线程处理，可取消
+    fn redact_json(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, config_service: &ConfigService, input: &SharedString) {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        let Some(input) = Self::begin_json_task(&window, json_logic, json_job, input, "请输入JSON内容") else {
+            return;
+        };
+
+        let rules = Self::json_redact_rules(&window);
+        let mut config = config_service.load();
+        if config.json_redact != rules {
+            config.json_redact = rules.clone();
+            if let Err(e) = config_service.save(&config) {
+                eprintln!("{}", e);
+            }
+        }
+
+        let options = Self::json_format_options(&window);
+        let json_logic = json_logic.clone();
+        let output_logic = json_logic.clone();
+        let large = input.len() >= BACKGROUND_INPUT_SIZE;
+        Self::run_json_task(
+            window_weak,
+            json_job,
+            large,
+            "脱敏",
+            move |token| Ok(json_logic.redact(&input, &rules, &options, token)?.map(|(output, count)| (JsonOutput::new(output), count))),
+            move |window, result| match result {
+                Ok(Some((output, count))) => {
+                    window.set_json_redact_status(format!("已脱敏 {} 处", count).into());
+                    Self::finish_json_output(window, &output_logic, output);
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    window.set_json_redact_status("".into());
+                    Self::show_json_error(window, &error);
+                }
+            },
+        );
+    }
+
+    /// 从界面读取脱敏规则，键名规则以逗号分隔，JSONPath以分号分隔
+    fn json_redact_rules(window: &MainWindow) -> RedactRules {
+        let split = |text: SharedString, separator: char| -> Vec<String> {
+            text.split(separator)
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        let detectors = [
+            (ValueDetector::Jwt, window.get_json_redact_jwt()),
+            (ValueDetector::Email, window.get_json_redact_email()),
+            (ValueDetector::Phone, window.get_json_redact_phone()),
+            (ValueDetector::ChineseId, window.get_json_redact_id_number()),
+            (ValueDetector::CreditCard, window.get_json_redact_card()),
+        ];
+        RedactRules {
+            key_patterns: split(window.get_json_redact_keys(), ','),
+            detectors: detectors.into_iter().filter(|(_, on)| *on).map(|(d, _)| d).collect(),
+            paths: split(window.get_json_redact_paths(), ';'),
+            mode: match window.get_json_redact_mode() {
+                1 => MaskMode::Partial,
+                2 => MaskMode::Hash,
+                _ => MaskMode::Full,
+            },
+        }
+    }
+
+    /// 将保存的脱敏规则同步到界面
+    fn apply_json_redact_rules(window: &MainWindow, rules: &RedactRules) {
+        window.set_json_redact_keys(rules.key_patterns.join(", ").into());
+        window.set_json_redact_paths(rules.paths.join("; ").into());
+        window.set_json_redact_jwt(rules.detectors.contains(&ValueDetector::Jwt));
+        window.set_json_redact_email(rules.detectors.contains(&ValueDetector::Email));
+        window.set_json_redact_phone(rules.detectors.contains(&ValueDetector::Phone));
+        window.set_json_redact_id_number(rules.detectors.contains(&ValueDetector::ChineseId));
+        window.set_json_redact_card(rules.detectors.contains(&ValueDetector::CreditCard));
+        window.set_json_redact_mode(match rules.mode {
+            MaskMode::Full => 0,
+            MaskMode::Partial => 1,
+            MaskMode::Hash => 2,
+        });
+    }
+
+    /// 按Schema或样本生成假数据；未填写种子时使用当前时间生成种子并回填，便于复现
+    fn fake_json(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, input: &SharedString, count: &str, seed: &str, ndjson: bool) {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        let Some(input) = Self::begin_json_task(&window, json_logic, json_job, input, "请输入JSON Schema或样本数据") else {
+            return;
+        };
+        let count = match count.trim().parse::<usize>() {
+            Ok(count) if (1..=FAKE_MAX_COUNT).contains(&count) => count,
+            _ => {
+                Self::show_output_message(&window, &format!("生成数量须为1到{}之间的整数", FAKE_MAX_COUNT));
+                return;
+            }
+        };
+        let seed = match seed.trim() {
+            "" => {
+                let seed = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_nanos() as u64 % 1_000_000_000);
+                window.set_json_fake_seed(seed.to_string().into());
+                seed
+            }
+            text => match text.parse::<u64>() {
+                Ok(seed) => seed,
+                Err(_) => {
+                    Self::show_output_message(&window, "随机种子须为非负整数");
+                    return;
+                }
+            },
+        };
+
+        let options = Self::json_format_options(&window);
+        let fake = FakeOptions {
+            count,
+            seed,
+            ndjson,
+            time_format: window.get_time_format().to_string(),
+        };
+        let json_logic = json_logic.clone();
+        let output_logic = json_logic.clone();
+        let large = input.len() >= BACKGROUND_INPUT_SIZE || count >= 1000;
+        Self::run_json_task(
+            window_weak,
+            json_job,
+            large,
+            "生成假数据",
+            move |token| Ok(json_logic.fake(&input, &fake, &options, token)?.map(JsonOutput::new)),
+            move |window, result| match result {
+                Ok(Some(output)) => Self::finish_json_output(window, &output_logic, output),
+                Ok(None) => {}
+                Err(error) => Self::show_json_error(window, &error),
+            },
+        );
+    }
+
+    /// 扁平化（inverse为true时还原嵌套）JSON，较大的输入在后台线程中处理，可取消
+    fn flatten_json(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, input: &SharedString, style: FlattenStyle, inverse: bool) {
+        Self::run_json_output_task(
+            window_weak,
+            json_logic,
+            json_job,
+            input,
+            "请输入JSON内容",
+            if inverse { "还原嵌套结构" } else { "扁平化" },
+            move |logic, input, options, token| {
+                if inverse {
+                    logic.unflatten(input, &style, options, token)
+                } else {
+                    logic.flatten(input, &style, options, token)
+                }
+            },
+        );
+    }
+
+    /// 输出规范化JSON，并在输出区下方显示其摘要
+    fn canonicalize_json(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, input: &SharedString) {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        window.set_json_digests("".into());
+        let Some(input) = Self::begin_json_task(&window, json_logic, json_job, input, "请输入JSON内容") else {
+            return;
+        };
+
+        let options = Self::json_format_options(&window);
+        let json_logic = json_logic.clone();
+        let output_logic = json_logic.clone();
+        let large = input.len() >= BACKGROUND_INPUT_SIZE;
+        Self::run_json_task(
+            window_weak,
+            json_job,
+            large,
+            "规范化",
+            move |token| {
+                let canonical = json_logic.canonicalize(&input, &options, token)?;
+                Ok(canonical.map(|canonical| (JsonOutput::new(canonical.text), canonical.digests)))
+            },
+            move |window, result| match result {
+                Ok(Some((output, digests))) => {
+                    let digests: Vec<String> = digests.iter().map(|(name, digest)| format!("{}: {}", name, digest)).collect();
+                    Self::finish_json_output(window, &output_logic, output);
+                    window.set_json_digests(digests.join("\n").into());
+                }
+                Ok(None) => {}
+                Err(error) => Self::show_json_error(window, &error),
+            },
+        );
+    }
+
+    /// 统计文档并以文本报告显示在输出区
+    fn json_stats(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, input: &SharedString) {
+        Self::run_json_output_task(
+            window_weak,
+            json_logic,
+            json_job,
+            input,
+            "请输入JSON内容",
+            "统计",
+            |logic, input, options, token| Ok(logic.stats(input, options, token)?.map(|stats| stats.to_string())),
+        );
+    }
+
+    /// NDJSON操作选择对应的处理方式，None表示JSON数组转NDJSON
+    fn get_ndjson_action_by_index(idx: i32) -> Option<NdjsonAction> {
+        match idx {
+            0 => Some(NdjsonAction::Format),
+            1 => Some(NdjsonAction::Minify),
+            2 => Some(NdjsonAction::Validate),
+            3 => Some(NdjsonAction::ToArray),
+            _ => None,
+        }
+    }
+
+    /// 逐行处理NDJSON或将JSON数组转换为NDJSON
+    fn process_ndjson(window_weak: &Weak<MainWindow>, json_logic: &JsonLogic, json_job: &JsonJob, input: &SharedString, action_index: i32) {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        window.set_json_ndjson_summary("".into());
+        let Some(input) = Self::begin_json_task(&window, json_logic, json_job, input, "请输入NDJSON内容，每行一个JSON文档") else {
+            return;
+        };
+
+        let options = Self::json_format_options(&window);
+        let json_logic = json_logic.clone();
+        let output_logic = json_logic.clone();
+        let large = input.len() >= BACKGROUND_INPUT_SIZE;
+        let Some(action) = Self::get_ndjson_action_by_index(action_index) else {
+            Self::run_json_task(
+                window_weak,
+                json_job,
+                large,
+                "转换为NDJSON",
+                move |token| Ok(json_logic.array_to_ndjson(&input, &options, token)?.map(JsonOutput::new)),
+                move |window, result| match result {
+                    Ok(Some(output)) => Self::finish_json_output(window, &output_logic, output),
+                    Ok(None) => {}
+                    Err(error) => Self::show_json_error(window, &error),
+                },
             );
+            return;
+        };
+        Self::run_json_task(
+            window_weak,
+            json_job,
+            large,
+            "逐行处理NDJSON",
+            move |token| Ok(json_logic.ndjson(&input, action, &options, token)?.map(|report| NdjsonResult::new(&input, action, report))),
+            move |window, result| match result {
+                Ok(Some(result)) => Self::show_ndjson_result(window, &output_logic, result),
+                Ok(None) => {}
+                Err(error) => Self::show_json_error(window, &error),
+            },
+        );
+    }
+
+    /// 显示NDJSON处理结果，并在输入中选中第一处无效行的出错位置
+    fn show_ndjson_result(window: &MainWindow, json_logic: &JsonLogic, result: NdjsonResult) {
+        Self::show_json_output(window, json_logic, result.output);
+        window.set_json_ndjson_summary(result.summary.into());
+        window.set_json_view_mode(0);
+        window.set_json_processing(false);
+        window.set_progress(0);
+
+        if let Some((start, end)) = result.selection {
+            window.invoke_select_json_input(start as i32, end as i32);
+        }
+    }
+
+    /// 执行双栏任务：输入较小时同步执行，否则提交到后台任务管理器，可通过取消按钮中止
+    fn run_json_task<T, W, S>(window_weak: &Weak<MainWindow>, json_job: &JsonJob, large: bool, name: &str, work: W, show: S)
+    where
+        T: Send + 'static,
+        W: FnOnce(&CancelToken) -> T + Send + 'static,
+        S: FnOnce(&MainWindow, T) + Send + 'static,
+    {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        if !large {
+            show(&window, work(&CancelToken::new()));
+            return;
+        }
+        Self::submit_json_job(&window, window_weak, json_job, name, move |job| work(job.token()), show);
+    }
+
+    /// 执行输出为文本的单栏任务：取消旧任务、检查空输入，按输入大小决定是否在后台执行，
+    /// 完成后在文本视图中显示结果
+    fn run_json_output_task<W>(
+        window_weak: &Weak<MainWindow>,
+        json_logic: &JsonLogic,
+        json_job: &JsonJob,
+        input: &SharedString,
+        empty_message: &str,
+        name: &str,
+        work: W,
+    ) where
+        W: FnOnce(&JsonLogic, &str, &FormatOptions, &CancelToken) -> Result<Option<String>, JsonError> + Send + 'static,
+    {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        let Some(input) = Self::begin_json_task(&window, json_logic, json_job, input, empty_message) else {
+            return;
+        };
+
+        let options = Self::json_format_options(&window);
+        let large = input.len() >= BACKGROUND_INPUT_SIZE;
+        let json_logic = json_logic.clone();
+        let output_logic = json_logic.clone();
+        Self::run_json_task(
+            window_weak,
+            json_job,
+            large,
+            name,
+            move |token| Ok(work(&json_logic, &input, &options, token)?.map(JsonOutput::new)),
+            move |window, result| match result {
+                Ok(Some(output)) => Self::finish_json_output(window, &output_logic, output),
+                Ok(None) => {}
+                Err(error) => Self::show_json_error(window, &error),
+            },
+        );
+    }
+
+    /// 开始单栏任务：取消旧任务，输入为空时提示并返回None，否则返回处理时使用的输入
+    fn begin_json_task(window: &MainWindow, json_logic: &JsonLogic, json_job: &JsonJob, input: &SharedString, empty_message: &str) -> Option<Arc<str>> {
+        Self::cancel_json_job(json_job);
+        if input.is_empty() {
+            Self::show_output_message(window, empty_message);
+            return None;
+        }
+        Some(Self::json_input(json_logic, input))
+    }
+
+    /// 在文本视图中显示结果并结束处理状态
+    fn finish_json_output(window: &MainWindow, json_logic: &JsonLogic, output: JsonOutput) {
+        Self::show_json_output(window, json_logic, output);
+        window.set_json_view_mode(0);
+        window.set_json_processing(false);
+        window.set_progress(0);
+    }
+
+    /// 将JSON任务提交到后台，超时后自动取消；只有仍是当前任务时才显示结果
+    fn submit_json_job<T, W, S>(window: &MainWindow, window_weak: &Weak<MainWindow>, json_job: &JsonJob, name: &str, work: W, show: S)
+    where
+        T: Send + 'static,
+        W: FnOnce(&JobContext) -> T + Send + 'static,
+        S: FnOnce(&MainWindow, T) + Send + 'static,
+    {
+        window.set_json_processing(true);
+        window.set_progress(0);
+        Self::show_output_message(window, &format!("正在{}，请稍候...", name));
+
+        Self::submit_current_job(window_weak, json_job, name, work, show, |window, failure| {
+            window.set_json_processing(false);
+            window.set_progress(0);
+            Self::show_output_message(window, failure);
+        });
+    }
+
+    /// 将任务提交到后台并登记为json_job的当前任务，超时后自动取消；
+    /// 任务已被取消或被新任务取代时丢弃结果，超时或异常中止时以说明调用fail
+    fn submit_current_job<T, W, S, F>(window_weak: &Weak<MainWindow>, json_job: &JsonJob, name: &str, work: W, show: S, fail: F)
+    where
+        T: Send + 'static,
+        W: FnOnce(&JobContext) -> T + Send + 'static,
+        S: FnOnce(&MainWindow, T) + Send + 'static,
+        F: FnOnce(&MainWindow, &str) + Send + 'static,
+    {
+        let current = json_job.current.clone();
+        let window_weak = window_weak.clone();
+        let id = json_job.jobs.submit(name, Some(JSON_JOB_TIMEOUT), work, move |id, outcome| {
+            let _ = window_weak.upgrade_in_event_loop(move |window| {
+                // 任务已取消或已被新任务取代，丢弃结果
+                {
+                    let mut current = current.lock().unwrap();
+                    if *current != Some(id) {
+                        return;
+                    }
+                    *current = None;
+                }
+                match Self::job_result(outcome) {
+                    Ok(result) => show(&window, result),
+                    Err(Some(failure)) => fail(&window, failure),
+                    Err(None) => {}
+                }
+            });
+        });
+        *json_job.current.lock().unwrap() = Some(id);
+    }
+
+    /// 在后台执行任务（读写文件、解析等），超时后自动取消；完成后在界面线程显示结果，
+    /// 超时或异常中止时以说明调用fail
+    fn run_background<T, W, S, F>(window_weak: &Weak<MainWindow>, jobs: &JobManager, name: &str, work: W, show: S, fail: F)
+    where
+        T: Send + 'static,
+        W: FnOnce(&CancelToken) -> T + Send + 'static,
+        S: FnOnce(&MainWindow, T) + Send + 'static,
+        F: FnOnce(&MainWindow, &str) + Send + 'static,
+    {
+        let window_weak = window_weak.clone();
+        jobs.submit(name, Some(JSON_JOB_TIMEOUT), move |job| work(job.token()), move |_, outcome| {
+            let _ = window_weak.upgrade_in_event_loop(move |window| match Self::job_result(outcome) {
+                Ok(result) => show(&window, result),
+                Err(Some(failure)) => fail(&window, failure),
+                Err(None) => {}
+            });
+        });
+    }
+
+    /// 任务的结果；未正常完成时返回界面上显示的说明，被取消的任务没有说明
+    fn job_result<T>(outcome: JobOutcome<T>) -> Result<T, Option<&'static str>> {
+        match outcome {
+            JobOutcome::Done(result) => Ok(result),
+            JobOutcome::Cancelled => Err(None),
+            JobOutcome::TimedOut => Err(Some("处理超时，已取消任务。JSON数据量可能过大或格式有误，请尝试分批处理")),
+            JobOutcome::Panicked => Err(Some("处理失败：任务异常中止")),
+        }
+    }
+
+    /// 取消当前JSON任务，其后续结果将被丢弃
+    fn cancel_json_job(json_job: &JsonJob) {
+        if let Some(id) = json_job.current.lock().unwrap().take() {
+            json_job.jobs.cancel(id);
+        }
+    }
+    
+    #[allow(clippy::too_many_arguments)]
+    fn process_json<F, P>(
+        window_weak: &Weak<MainWindow>, 
+        json_logic: &JsonLogic, 
+        json_job: &JsonJob,
+        input: &SharedString, 
+        options: FormatOptions,
+        processor: F, 
+        progress_processor: P,
+        operation_name: &str
+    ) where 
+        F: Fn(&JsonLogic, &str) -> Result<String, JsonError>,
+        P: FnOnce(&JsonLogic, &str, &FormatOptions, &CancelToken, &dyn Fn(usize)) -> Result<Option<String>, JsonError> + Send + 'static,
+    {
+        let Some(window) = window_weak.upgrade() else {
+            return;
+        };
+        // 新任务开始前取消旧任务，避免旧结果覆盖输出
+        Self::cancel_json_job(json_job);
+        window.set_json_digests("".into());
+        let input = Self::json_input(json_logic, input);
+
+        // 处理空输入
+        if input.is_empty() {
+            Self::show_output_message(&window, "请输入JSON内容");
+            window.set_json_processing(false);
+            return;
+        }
+
+        // 对于小型JSON，直接同步处理
+        if input.len() < BACKGROUND_INPUT_SIZE {
+            match processor(json_logic, &input) {
+                Ok(result) => {
+                    Self::show_json_output(&window, json_logic, JsonOutput::new(result));
+                    window.set_json_processing(false);
+                    window.set_progress(0);
+                }
+                Err(error) => Self::show_json_error(&window, &error),
+            }
+            return;
         }
+
+        // 使用带进度的处理方法，进度经任务管理器显示在进度条上
+        let json_logic = json_logic.clone();
+        let output_logic = json_logic.clone();
+        Self::submit_json_job(
+            &window,
+            window_weak,
+            json_job,
+            operation_name,
+            move |job| Ok(progress_processor(&json_logic, &input, &options, job.token(), &|percent| job.report_progress(percent))?.map(JsonOutput::new)),
+            move |window, result| match result {
+                Ok(Some(output)) => {
+                    Self::show_json_output(window, &output_logic, output);
+                    window.set_json_processing(false);
+                    window.set_progress(0);
+                }
+                Ok(None) => {}
+                Err(error) => Self::show_json_error(window, &error),
+            },
+        );
     }
-} 
\ No newline at end of file
+}
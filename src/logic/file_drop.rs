@@ -0,0 +1,69 @@
+use crate::MainWindow;
+use i_slint_backend_winit::winit::dpi::PhysicalPosition;
+use i_slint_backend_winit::winit::event::WindowEvent;
+use i_slint_backend_winit::{WinitWindowAccessor, WinitWindowEventResult};
+use slint::ComponentHandle;
+use std::path::PathBuf;
+
+/// 文件拖放到的区域
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropTarget {
+    Input,
+    Output,
+}
+
+/// 监听拖放到窗口的文件，按落点在输入区还是结果区调用on_drop
+///
+/// Slint没有公开的文件拖放接口，这里通过其winit后端读取窗口事件，只有本模块依赖该内部crate。
+/// winit 0.30的拖放事件不带落点：Windows上在放下时读取光标位置，其他平台等放下后的第一个指针事件再确定
+pub fn handle_file_drops(window: &MainWindow, on_drop: impl Fn(&MainWindow, PathBuf, DropTarget) + 'static) {
+    let window_weak = window.as_weak();
+    let mut pending: Vec<PathBuf> = Vec::new();
+    window.window().on_winit_window_event(move |slint_window, event| {
+        let position = match event {
+            WindowEvent::DroppedFile(path) => {
+                pending.push(path.clone());
+                cursor_position(slint_window)
+            }
+            WindowEvent::CursorMoved { position, .. } if !pending.is_empty() => Some(*position),
+            _ => None,
+        };
+        if let (Some(position), Some(window)) = (position, window_weak.upgrade()) {
+            let target = drop_target(&window, position);
+            for path in pending.drain(..) {
+                on_drop(&window, path, target);
+            }
+        }
+        WinitWindowEventResult::Propagate
+    });
+}
+
+/// 结果区位于输入区下方，按落点的纵坐标区分
+fn drop_target(window: &MainWindow, position: PhysicalPosition<f64>) -> DropTarget {
+    let y = position.y as f32 / window.window().scale_factor();
+    if y >= window.get_json_output_area_top() {
+        DropTarget::Output
+    } else {
+        DropTarget::Input
+    }
+}
+
+/// 光标在窗口客户区中的位置
+#[cfg(windows)]
+fn cursor_position(window: &slint::Window) -> Option<PhysicalPosition<f64>> {
+    use winapi::shared::windef::POINT;
+    use winapi::um::winuser::GetCursorPos;
+
+    let mut point = POINT { x: 0, y: 0 };
+    // SAFETY: GetCursorPos只写入传入的POINT
+    if unsafe { GetCursorPos(&mut point) } == 0 {
+        return None;
+    }
+    let origin = window.with_winit_window(|w| w.inner_position().ok()).flatten()?;
+    Some(PhysicalPosition::new((point.x - origin.x) as f64, (point.y - origin.y) as f64))
+}
+
+#[cfg(not(windows))]
+fn cursor_position(_window: &slint::Window) -> Option<PhysicalPosition<f64>> {
+    None
+}
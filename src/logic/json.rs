@@ -1,41 +1,299 @@
 use crate::utils::json;
-use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
 
-#[derive(Clone, Copy)]
-pub struct JsonLogic;
+/// 过大未完整显示的结果：(显示的预览, 完整内容)
+type FullOutput = Option<(String, Arc<str>)>;
+
+/// 载入树形视图的文档及其来源，输入和解析方式不变时查找直接复用
+struct LoadedTree {
+    input: Arc<str>,
+    relaxed: bool,
+    tree: json::JsonTree,
+}
+
+impl LoadedTree {
+    /// 任务被取消时返回Ok(None)
+    fn parse(input: Arc<str>, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<Self>, json::JsonError> {
+        let Some(value) = json::parse_json_document(&input, options, token)? else {
+            return Ok(None);
+        };
+        Ok(Some(Self { input, relaxed: options.relaxed, tree: json::JsonTree::new(value) }))
+    }
+
+    /// 是否由同一输入按同样的方式解析而来
+    fn is_from(&self, input: &Arc<str>, options: &json::FormatOptions) -> bool {
+        self.relaxed == options.relaxed && (Arc::ptr_eq(&self.input, input) || self.input == *input)
+    }
+}
+
+#[derive(Clone)]
+pub struct JsonLogic {
+    tree: Arc<Mutex<Option<LoadedTree>>>, // 当前载入树形视图的文档
+    file_input: Arc<Mutex<Option<Arc<str>>>>, // 从文件载入的内容，处理时代替输入框中的文本
+    full_output: Arc<Mutex<FullOutput>>,
+    search_lines: Arc<Mutex<Vec<usize>>>, // 查找结果在格式化文本中所在的行
+}
 
 impl JsonLogic {
     pub fn new() -> Self {
-        Self
+        Self {
+            tree: Arc::new(Mutex::new(None)),
+            file_input: Arc::new(Mutex::new(None)),
+            full_output: Arc::new(Mutex::new(None)),
+            search_lines: Arc::new(Mutex::new(Vec::new())),
+        }
     }
 
-    /// 格式化JSON字符串
-    pub fn format(&self, json_str: &str) -> String {
-        json::format_json(json_str)
+    /// 格式化JSON字符串，按选项输出，保留键顺序和数字精度
+    pub fn format(&self, json_str: &str, options: &json::FormatOptions) -> Result<String, json::JsonError> {
+        json::format_json(json_str, options)
     }
 
-    /// 压缩JSON字符串
-    pub fn minify(&self, json_str: &str) -> String {
-        json::minify_json(json_str)
+    /// 压缩JSON字符串，按选项输出，保留键顺序和数字精度
+    pub fn minify(&self, json_str: &str, options: &json::FormatOptions) -> Result<String, json::JsonError> {
+        json::minify_json(json_str, options)
     }
     
-    /// 异步格式化JSON字符串
-    pub fn format_async(&self, json_str: String) -> mpsc::Receiver<String> {
-        json::format_json_async(json_str)
+    /// 带进度反馈的JSON格式化，可通过令牌取消，任务被取消时返回Ok(None)
+    pub fn format_with_progress(&self, json_str: &str, options: &json::FormatOptions, token: &json::CancelToken, progress: &dyn Fn(usize)) -> Result<Option<String>, json::JsonError> {
+        json::format_json_with_progress(json_str, options, token, progress)
     }
     
-    /// 异步压缩JSON字符串
-    pub fn minify_async(&self, json_str: String) -> mpsc::Receiver<String> {
-        json::minify_json_async(json_str)
+    /// 带进度反馈的JSON压缩，可通过令牌取消，任务被取消时返回Ok(None)
+    pub fn minify_with_progress(&self, json_str: &str, options: &json::FormatOptions, token: &json::CancelToken, progress: &dyn Fn(usize)) -> Result<Option<String>, json::JsonError> {
+        json::minify_json_with_progress(json_str, options, token, progress)
     }
-    
-    /// 带进度反馈的异步JSON格式化
-    pub fn format_with_progress(&self, json_str: String) -> mpsc::Receiver<json::ProcessUpdate> {
-        json::format_json_with_progress(json_str)
+
+    /// 对JSON执行查询，`$` 开头按JSONPath处理，否则按jq表达式处理
+    pub fn query(&self, json_str: &str, query: &str, options: &json::FormatOptions) -> Result<String, json::JsonError> {
+        json::query_json(json_str, query, options)
     }
-    
-    /// 带进度反馈的异步JSON压缩
-    pub fn minify_with_progress(&self, json_str: String) -> mpsc::Receiver<json::ProcessUpdate> {
-        json::minify_json_with_progress(json_str)
+
+    /// 带进度反馈的JSON查询，可通过令牌取消，任务被取消时返回Ok(None)
+    pub fn query_with_progress(&self, json_str: &str, query: &str, options: &json::FormatOptions, token: &json::CancelToken, progress: &dyn Fn(usize)) -> Result<Option<String>, json::JsonError> {
+        json::query_json_with_progress(json_str, query, options, token, progress)
+    }
+
+    /// 在JSON、YAML和TOML之间转换
+    pub fn convert(&self, input: &str, from: json::DataFormat, to: json::DataFormat, options: &json::FormatOptions) -> Result<String, json::JsonError> {
+        json::convert_data(input, from, to, options)
+    }
+
+    /// 带进度反馈的格式转换，可通过令牌取消，任务被取消时返回Ok(None)
+    pub fn convert_with_progress(&self, input: &str, from: json::DataFormat, to: json::DataFormat, options: &json::FormatOptions, token: &json::CancelToken, progress: &dyn Fn(usize)) -> Result<Option<String>, json::JsonError> {
+        json::convert_data_with_progress(input, from, to, options, token, progress)
+    }
+
+    /// 逐行处理NDJSON，任务被取消时返回Ok(None)
+    pub fn ndjson(&self, input: &str, action: json::NdjsonAction, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<json::NdjsonReport>, json::JsonError> {
+        json::process_ndjson(input, action, options, token)
+    }
+
+    /// 将JSON数组转换为NDJSON，任务被取消时返回Ok(None)
+    pub fn array_to_ndjson(&self, input: &str, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<String>, json::JsonError> {
+        json::json_array_to_ndjson(input, options, token)
+    }
+
+    /// 展开以字符串形式嵌套的JSON，返回结果和展开的个数；任务被取消时返回Ok(None)
+    pub fn expand_nested(&self, input: &str, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<(String, usize)>, json::JsonError> {
+        json::expand_nested_json(input, options, token)
+    }
+
+    /// 按RFC 8785输出规范化JSON及其摘要，任务被取消时返回Ok(None)
+    pub fn canonicalize(&self, input: &str, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<json::CanonicalJson>, json::JsonError> {
+        json::canonicalize_json(input, options, token)
+    }
+
+    /// 统计文档结构和体积构成，任务被取消时返回Ok(None)
+    pub fn stats(&self, input: &str, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<json::JsonStats>, json::JsonError> {
+        json::json_stats(input, options, token)
+    }
+
+    /// 将指定节点压缩为JSON字符串，任务被取消时返回Ok(None)
+    pub fn stringify_node(&self, input: &str, pointer: &str, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<String>, json::JsonError> {
+        json::stringify_json_node(input, pointer, options, token)
+    }
+
+    /// 将对象或数组扁平化为一层的键值映射
+    pub fn flatten(&self, input: &str, style: &json::FlattenStyle, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<String>, json::JsonError> {
+        json::flatten_json(input, style, options, token)
+    }
+
+    /// 将扁平的键值映射还原为嵌套的JSON
+    pub fn unflatten(&self, input: &str, style: &json::FlattenStyle, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<String>, json::JsonError> {
+        json::unflatten_json(input, style, options, token)
+    }
+
+    /// 按规则脱敏敏感字段，返回结果和脱敏的值的个数
+    pub fn redact(&self, input: &str, rules: &json::RedactRules, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<(String, usize)>, json::JsonError> {
+        json::redact_json(input, rules, options, token)
+    }
+
+    /// 按Schema或样本生成假数据
+    pub fn fake(&self, input: &str, fake: &json::FakeOptions, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<String>, json::JsonError> {
+        json::fake_json(input, fake, options, token)
+    }
+
+    /// 将文本转义为JSON字符串内容
+    pub fn escape(&self, text: &str, options: &json::FormatOptions) -> String {
+        json::escape_string(text, options.ensure_ascii)
+    }
+
+    /// 还原JSON字符串字面量
+    pub fn unescape(&self, literal: &str) -> Result<String, json::JsonError> {
+        json::unescape_string(literal)
+    }
+    /// 结构化对比两个JSON文档，可通过令牌取消，取消时返回Ok(None)
+    pub fn diff(&self, left: &str, right: &str, options: &json::FormatOptions, diff_options: &json::DiffOptions, token: &json::CancelToken) -> Result<Option<json::JsonDiff>, json::DiffError> {
+        json::diff_json(left, right, options, diff_options, token)
+    }
+
+    /// 将补丁应用到JSON文档，自动识别JSON Patch（RFC 6902）或Merge Patch（RFC 7396）；任务被取消时返回Ok(None)
+    pub fn apply_patch(&self, document: &str, patch: &str, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<(json::PatchKind, String)>, json::PatchError> {
+        json::patch_json(document, patch, options, token)
+    }
+
+    /// 使用JSON Schema校验文档，返回格式化后的文档和违规列表；任务被取消时返回Ok(None)
+    pub fn validate_schema(&self, instance: &str, schema: &str, options: &json::FormatOptions, schema_options: &json::SchemaOptions, token: &json::CancelToken) -> Result<Option<json::SchemaReport>, json::SchemaError> {
+        json::validate_json_schema(instance, schema, options, schema_options, token)
+    }
+
+    /// 从样本推断JSON Schema，输入可以是单个文档或JSON Lines；任务被取消时返回Ok(None)
+    pub fn infer_schema(&self, input: &str, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<String>, json::JsonError> {
+        json::infer_json_schema(input, options, token)
+    }
+
+    /// 从样本生成Rust、TypeScript、Go或Java类型定义；任务被取消时返回Ok(None)
+    pub fn generate_code(&self, input: &str, options: &json::FormatOptions, language: json::CodeLanguage, token: &json::CancelToken) -> Result<Option<String>, json::JsonError> {
+        json::generate_json_code(input, options, language, "Root", token)
+    }
+
+    /// 由对象数组生成建表语句和分批的INSERT语句；任务被取消时返回Ok(None)
+    pub fn generate_sql(&self, input: &str, options: &json::FormatOptions, sql_options: &json::SqlOptions, token: &json::CancelToken) -> Result<Option<String>, json::JsonError> {
+        json::generate_json_sql(input, options, sql_options, token)
+    }
+
+    /// 解析JSON并载入树形视图，返回初始可见行；任务被取消时返回Ok(None)，已载入的文档保持不变
+    pub fn load_tree(&self, json_str: Arc<str>, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<Vec<json::TreeRow>>, json::JsonError> {
+        let Some(loaded) = LoadedTree::parse(json_str, options, token)? else {
+            return Ok(None);
+        };
+        let rows = loaded.tree.rows();
+        *self.tree.lock().unwrap() = Some(loaded);
+        Ok(Some(rows))
+    }
+
+    /// 查找匹配的键或值，filter为true时隐藏没有匹配的分支；返回可见行和匹配数
+    /// 输入与已载入的文档相同时直接在其上查找，否则重新解析；解析时任务被取消则返回Ok(None)
+    pub fn search(&self, json_str: Arc<str>, pattern: &str, search_options: &json::SearchOptions, filter: bool, options: &json::FormatOptions, token: &json::CancelToken) -> Result<Option<(Vec<json::TreeRow>, usize)>, json::JsonError> {
+        let matcher = json::Matcher::new(pattern, search_options).map_err(json::JsonError::new)?;
+        let loaded = self.tree.lock().unwrap().as_ref().is_some_and(|loaded| loaded.is_from(&json_str, options));
+        let parsed = if loaded {
+            None
+        } else {
+            match LoadedTree::parse(json_str, options, token)? {
+                Some(parsed) => Some(parsed),
+                None => return Ok(None),
+            }
+        };
+
+        let mut guard = self.tree.lock().unwrap();
+        if let Some(parsed) = parsed {
+            *guard = Some(parsed);
+        }
+        self.search_lines.lock().unwrap().clear();
+        let Some(loaded) = guard.as_mut() else {
+            return Ok(Some((Vec::new(), 0)));
+        };
+        let count = loaded.tree.search(matcher, filter);
+        Ok(Some((loaded.tree.rows(), count)))
+    }
+
+    /// 移动到下一个或上一个匹配，返回更新后的可见行及(序号, 匹配总数, JSON Pointer)
+    pub fn step_match(&self, forward: bool) -> (Vec<json::TreeRow>, Option<(usize, usize, String)>) {
+        let mut step = None;
+        let rows = self.update_tree(|tree| step = tree.step_match(forward));
+        (rows, step)
+    }
+
+    /// 清除查找和过滤，返回更新后的可见行
+    pub fn clear_search(&self) -> Vec<json::TreeRow> {
+        self.search_lines.lock().unwrap().clear();
+        self.update_tree(|tree| tree.clear_search())
+    }
+
+    /// 将查找的文档（过滤时只含匹配的分支）格式化为文本，并记录各匹配所在的行
+    pub fn search_text(&self, options: &json::FormatOptions) -> Option<String> {
+        let (text, lines) = self.tree.lock().unwrap().as_ref()?.tree.search_text(options);
+        *self.search_lines.lock().unwrap() = lines;
+        Some(text)
+    }
+
+    /// 第index个匹配在search_text输出中所在的行
+    pub fn search_line(&self, index: usize) -> Option<usize> {
+        self.search_lines.lock().unwrap().get(index).copied()
+    }
+
+    /// 清空树形视图中的文档
+    pub fn clear_tree(&self) {
+        *self.tree.lock().unwrap() = None;
+    }
+
+    /// 展开或折叠节点，返回更新后的可见行
+    pub fn toggle_tree_node(&self, pointer: &str) -> Vec<json::TreeRow> {
+        self.update_tree(|tree| tree.toggle(pointer))
+    }
+
+    /// 展开全部节点，返回更新后的可见行
+    pub fn expand_tree(&self) -> Vec<json::TreeRow> {
+        self.update_tree(|tree| tree.expand_all())
+    }
+
+    /// 折叠全部节点，返回更新后的可见行
+    pub fn collapse_tree(&self) -> Vec<json::TreeRow> {
+        self.update_tree(|tree| tree.collapse_all())
+    }
+
+    /// 获取节点路径，as_pointer为true时返回JSON Pointer，否则返回JSONPath
+    pub fn tree_node_path(&self, pointer: &str, as_pointer: bool) -> Option<String> {
+        let guard = self.tree.lock().unwrap();
+        let tree = &guard.as_ref()?.tree;
+        if as_pointer {
+            tree.root().pointer(pointer).map(|_| pointer.to_string())
+        } else {
+            tree.jsonpath(pointer)
+        }
+    }
+
+    fn update_tree<F: FnOnce(&mut json::JsonTree)>(&self, update: F) -> Vec<json::TreeRow> {
+        match self.tree.lock().unwrap().as_mut() {
+            Some(loaded) => {
+                update(&mut loaded.tree);
+                loaded.tree.rows()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// 设置从文件载入的输入，None表示改用输入框中的内容
+    pub fn set_file_input(&self, content: Option<Arc<str>>) {
+        *self.file_input.lock().unwrap() = content;
+    }
+
+    /// 从文件载入的输入
+    pub fn file_input(&self) -> Option<Arc<str>> {
+        self.file_input.lock().unwrap().clone()
+    }
+
+    /// 记录过大而只显示了预览的结果
+    pub fn set_full_output(&self, output: FullOutput) {
+        *self.full_output.lock().unwrap() = output;
+    }
+
+    /// 输出框当前显示的是预览时返回完整结果
+    pub fn full_output(&self, displayed: &str) -> Option<Arc<str>> {
+        let guard = self.full_output.lock().unwrap();
+        let (preview, full) = guard.as_ref()?;
+        (preview == displayed).then(|| full.clone())
     }
-} 
\ No newline at end of file
+}
@@ -1,3 +1,6 @@
+pub mod code_view;
 pub mod event;
+#[cfg(feature = "file-drop")]
+pub mod file_drop;
 pub mod json;
 pub mod time;
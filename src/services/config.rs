@@ -1,3 +1,4 @@
+use crate::utils::json::{FormatOptions, RedactRules};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -8,6 +9,10 @@ pub struct Config {
     pub theme: String,
     pub language: String,
     pub time_format: String,
+    #[serde(default)]
+    pub json_format: FormatOptions,
+    #[serde(default)]
+    pub json_redact: RedactRules,
 }
 
 impl Default for Config {
@@ -16,6 +21,8 @@ impl Default for Config {
             theme: "light".to_string(),
             language: "zh-CN".to_string(),
             time_format: "YYYY-MM-DD HH:mm:ss".to_string(),
+            json_format: FormatOptions::default(),
+            json_redact: RedactRules::default(),
         }
     }
 }
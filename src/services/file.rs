@@ -0,0 +1,37 @@
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Default)]
+pub struct FileService;
+
+impl FileService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 读取UTF-8文本文件，超过大小上限时直接拒绝；会去掉开头的BOM
+    pub fn read_text(&self, path: &Path, max_size: usize) -> Result<String, String> {
+        let size = fs::metadata(path)
+            .map_err(|e| format!("读取文件失败: {}", e))?
+            .len();
+        if size > max_size as u64 {
+            return Err(format!(
+                "文件过大（{}MB），已超过处理限制（{}MB）",
+                size / (1024 * 1024),
+                max_size / (1024 * 1024)
+            ));
+        }
+        let bytes = fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
+        let mut text = String::from_utf8(bytes)
+            .map_err(|e| format!("文件不是有效的UTF-8文本（字节偏移 {}）", e.utf8_error().valid_up_to()))?;
+        if text.starts_with('\u{feff}') {
+            text.drain(..'\u{feff}'.len_utf8());
+        }
+        Ok(text)
+    }
+
+    /// 将文本写入文件，文件已存在时覆盖
+    pub fn write_text(&self, path: &Path, content: &str) -> Result<(), String> {
+        fs::write(path, content).map_err(|e| format!("保存文件失败: {}", e))
+    }
+}
@@ -0,0 +1,393 @@
+use crate::utils::json::CancelToken;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 任务编号，按提交顺序递增
+pub type JobId = u64;
+
+/// 任务的结束方式
+pub enum JobOutcome<T> {
+    Done(T),
+    Cancelled,
+    TimedOut,
+    /// 任务执行中发生panic
+    Panicked,
+}
+
+/// 任务状态变化，用于刷新界面上的任务指示和进度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobEvent {
+    /// 有任务排队、开始运行或结束
+    Changed,
+    Progress { id: JobId, percent: usize },
+}
+
+/// 排队中或运行中任务的概况
+#[derive(Debug, Clone)]
+pub struct JobInfo {
+    pub id: JobId,
+    pub name: String,
+    pub running: bool,
+    /// 任务报告过的进度（0-100）
+    pub progress: Option<usize>,
+    /// 开始运行后经过的时间，排队中为0
+    pub elapsed: Duration,
+}
+
+/// 任务执行时的上下文，提供取消令牌和进度报告
+pub struct JobContext {
+    id: JobId,
+    token: CancelToken,
+    timed_out: Arc<AtomicBool>,
+    progress: Arc<AtomicUsize>,
+    shared: Arc<Shared>,
+}
+
+impl JobContext {
+    pub fn token(&self) -> &CancelToken {
+        &self.token
+    }
+
+    /// 报告进度（0-100），与上次相同时不发出事件
+    pub fn report_progress(&self, percent: usize) {
+        let percent = percent.min(100);
+        // 未报告过进度时记为usize::MAX
+        if self.progress.swap(percent, Ordering::Relaxed) != percent {
+            self.shared.notify(JobEvent::Progress { id: self.id, percent });
+        }
+    }
+}
+
+type Task = Box<dyn FnOnce(&JobContext) + Send>;
+type Listener = Arc<dyn Fn(JobEvent) + Send + Sync>;
+
+struct Job {
+    id: JobId,
+    name: String,
+    token: CancelToken,
+    timed_out: Arc<AtomicBool>,
+    progress: Arc<AtomicUsize>,
+    timeout: Option<Duration>,
+    started: Option<Instant>,
+    task: Option<Task>,
+}
+
+#[derive(Default)]
+struct State {
+    next_id: JobId,
+    /// 按提交顺序排列，排队中的任务仍持有task
+    jobs: VecDeque<Job>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    /// 有新任务排队
+    queued: Condvar,
+    /// 运行中任务的截止时间有变化
+    deadlines: Condvar,
+    listener: Mutex<Option<Listener>>,
+}
+
+impl Shared {
+    /// 先取出监听器再调用，监听器中可以再提交任务或更换监听器
+    fn notify(&self, event: JobEvent) {
+        let listener = self.listener.lock().unwrap().clone();
+        if let Some(listener) = listener {
+            listener(event);
+        }
+    }
+}
+
+/// 后台任务管理器：固定数量的工作线程按提交顺序执行任务，
+/// 支持取消、超时和进度报告。克隆后共享同一组工作线程
+#[derive(Clone)]
+pub struct JobManager {
+    shared: Arc<Shared>,
+}
+
+impl JobManager {
+    /// 创建管理器，最多同时运行workers个任务
+    pub fn new(workers: usize) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            queued: Condvar::new(),
+            deadlines: Condvar::new(),
+            listener: Mutex::new(None),
+        });
+        for i in 0..workers.max(1) {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name(format!("job-worker-{}", i))
+                .spawn(move || worker_loop(&shared))
+                .expect("无法创建任务线程");
+        }
+        let watchdog = shared.clone();
+        thread::Builder::new()
+            .name("job-watchdog".to_string())
+            .spawn(move || watchdog_loop(&watchdog))
+            .expect("无法创建任务线程");
+        Self { shared }
+    }
+
+    /// 设置任务状态变化的监听器，在提交任务或执行任务的线程中调用，应尽快返回
+    pub fn set_listener(&self, listener: impl Fn(JobEvent) + Send + Sync + 'static) {
+        *self.shared.listener.lock().unwrap() = Some(Arc::new(listener));
+    }
+
+    /// 提交任务，返回任务编号。任务结束后在工作线程中以任务编号和结束方式调用done；
+    /// 被取消或超时的任务，其结果会被丢弃
+    pub fn submit<T, W, D>(&self, name: &str, timeout: Option<Duration>, work: W, done: D) -> JobId
+    where
+        T: Send + 'static,
+        W: FnOnce(&JobContext) -> T + Send + 'static,
+        D: FnOnce(JobId, JobOutcome<T>) + Send + 'static,
+    {
+        let task: Task = Box::new(move |context: &JobContext| {
+            let outcome = if context.token.is_cancelled() {
+                JobOutcome::Cancelled
+            } else {
+                match panic::catch_unwind(AssertUnwindSafe(|| work(context))) {
+                    Err(_) => JobOutcome::Panicked,
+                    Ok(_) if context.timed_out.load(Ordering::Relaxed) => JobOutcome::TimedOut,
+                    Ok(_) if context.token.is_cancelled() => JobOutcome::Cancelled,
+                    Ok(result) => JobOutcome::Done(result),
+                }
+            };
+            done(context.id, outcome);
+        });
+
+        let id = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.next_id += 1;
+            let id = state.next_id;
+            state.jobs.push_back(Job {
+                id,
+                name: name.to_string(),
+                token: CancelToken::new(),
+                timed_out: Arc::new(AtomicBool::new(false)),
+                progress: Arc::new(AtomicUsize::new(usize::MAX)),
+                timeout,
+                started: None,
+                task: Some(task),
+            });
+            id
+        };
+        self.shared.queued.notify_one();
+        self.shared.notify(JobEvent::Changed);
+        id
+    }
+
+    /// 取消任务：排队中的任务不再执行，运行中的任务通过取消令牌协作停止
+    pub fn cancel(&self, id: JobId) {
+        let state = self.shared.state.lock().unwrap();
+        if let Some(job) = state.jobs.iter().find(|job| job.id == id) {
+            job.token.cancel();
+        }
+    }
+
+    /// 取消全部任务
+    pub fn cancel_all(&self) {
+        let state = self.shared.state.lock().unwrap();
+        for job in &state.jobs {
+            job.token.cancel();
+        }
+    }
+
+    /// 排队中和运行中的任务，按提交顺序排列
+    pub fn jobs(&self) -> Vec<JobInfo> {
+        let state = self.shared.state.lock().unwrap();
+        state
+            .jobs
+            .iter()
+            .map(|job| JobInfo {
+                id: job.id,
+                name: job.name.clone(),
+                running: job.started.is_some(),
+                progress: match job.progress.load(Ordering::Relaxed) {
+                    usize::MAX => None,
+                    percent => Some(percent),
+                },
+                elapsed: job.started.map_or(Duration::ZERO, |started| started.elapsed()),
+            })
+            .collect()
+    }
+}
+
+fn worker_loop(shared: &Arc<Shared>) {
+    loop {
+        let (context, task) = {
+            let mut state = shared.state.lock().unwrap();
+            loop {
+                if let Some(job) = state.jobs.iter_mut().find(|job| job.task.is_some()) {
+                    job.started = Some(Instant::now());
+                    let context = JobContext {
+                        id: job.id,
+                        token: job.token.clone(),
+                        timed_out: job.timed_out.clone(),
+                        progress: job.progress.clone(),
+                        shared: shared.clone(),
+                    };
+                    break (context, job.task.take());
+                }
+                state = shared.queued.wait(state).unwrap();
+            }
+        };
+        shared.deadlines.notify_one();
+        shared.notify(JobEvent::Changed);
+
+        // work的panic已在任务内部处理；done发生panic时也要移除任务，保持工作线程存活
+        if let Some(task) = task {
+            if panic::catch_unwind(AssertUnwindSafe(|| task(&context))).is_err() {
+                println!("任务 {} 的结果处理异常中止", context.id);
+            }
+        }
+
+        shared.state.lock().unwrap().jobs.retain(|job| job.id != context.id);
+        shared.notify(JobEvent::Changed);
+    }
+}
+
+/// 取消超过时限的任务
+fn watchdog_loop(shared: &Arc<Shared>) {
+    let mut state = shared.state.lock().unwrap();
+    loop {
+        let now = Instant::now();
+        let mut next_deadline: Option<Instant> = None;
+        for job in &state.jobs {
+            let (Some(started), Some(timeout)) = (job.started, job.timeout) else {
+                continue;
+            };
+            if job.token.is_cancelled() {
+                continue;
+            }
+            let deadline = started + timeout;
+            if deadline <= now {
+                job.timed_out.store(true, Ordering::Relaxed);
+                job.token.cancel();
+                println!("任务“{}”超时，已取消", job.name);
+            } else {
+                next_deadline = Some(next_deadline.map_or(deadline, |next| next.min(deadline)));
+            }
+        }
+        state = match next_deadline {
+            Some(deadline) => shared.deadlines.wait_timeout(state, deadline - now).unwrap().0,
+            None => shared.deadlines.wait(state).unwrap(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    const WAIT: Duration = Duration::from_secs(10);
+
+    /// 提交任务，结束方式通过通道返回
+    fn submit<T, W>(jobs: &JobManager, timeout: Option<Duration>, work: W) -> (JobId, mpsc::Receiver<JobOutcome<T>>)
+    where
+        T: Send + 'static,
+        W: FnOnce(&JobContext) -> T + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let id = jobs.submit("test", timeout, work, move |_, outcome| {
+            let _ = tx.send(outcome);
+        });
+        (id, rx)
+    }
+
+    /// 一直运行到被取消
+    fn wait_cancelled(job: &JobContext) {
+        while !job.token().is_cancelled() {
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn wait_idle(jobs: &JobManager) {
+        let start = Instant::now();
+        while !jobs.jobs().is_empty() {
+            assert!(start.elapsed() < WAIT, "任务没有结束");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn runs_job_and_reports_progress() {
+        let jobs = JobManager::new(1);
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        jobs.set_listener(move |event| {
+            if let JobEvent::Progress { percent, .. } = event {
+                let _ = tx.lock().unwrap().send(percent);
+            }
+        });
+        let (_, outcome) = submit(&jobs, None, |job| {
+            job.report_progress(50);
+            job.report_progress(50);
+            job.report_progress(100);
+            42
+        });
+        assert!(matches!(outcome.recv_timeout(WAIT), Ok(JobOutcome::Done(42))));
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![50, 100]);
+        wait_idle(&jobs);
+    }
+
+    #[test]
+    fn cancels_running_and_queued_jobs() {
+        let jobs = JobManager::new(1);
+        let (running, running_outcome) = submit(&jobs, None, wait_cancelled);
+        let ran = Arc::new(AtomicBool::new(false));
+        let flag = ran.clone();
+        let (queued, queued_outcome) = submit(&jobs, None, move |_| flag.store(true, Ordering::Relaxed));
+        jobs.cancel(queued);
+        jobs.cancel(running);
+        assert!(matches!(running_outcome.recv_timeout(WAIT), Ok(JobOutcome::Cancelled)));
+        assert!(matches!(queued_outcome.recv_timeout(WAIT), Ok(JobOutcome::Cancelled)));
+        assert!(!ran.load(Ordering::Relaxed), "已取消的排队任务不应执行");
+        wait_idle(&jobs);
+    }
+
+    #[test]
+    fn times_out_long_job() {
+        let jobs = JobManager::new(1);
+        let (_, outcome) = submit(&jobs, Some(Duration::from_millis(50)), wait_cancelled);
+        assert!(matches!(outcome.recv_timeout(WAIT), Ok(JobOutcome::TimedOut)));
+        wait_idle(&jobs);
+    }
+
+    #[test]
+    fn survives_panics_in_work_and_done() {
+        let jobs = JobManager::new(1);
+        let (_, outcome) = submit(&jobs, None, |_| -> i32 { panic!("work") });
+        assert!(matches!(outcome.recv_timeout(WAIT), Ok(JobOutcome::Panicked)));
+
+        jobs.submit("test", None, |_| (), |_, _| panic!("done"));
+        wait_idle(&jobs);
+
+        // 唯一的工作线程仍可执行后续任务
+        let (_, outcome) = submit(&jobs, None, |_| 1);
+        assert!(matches!(outcome.recv_timeout(WAIT), Ok(JobOutcome::Done(1))));
+    }
+
+    #[test]
+    fn listener_can_submit_jobs() {
+        let jobs = JobManager::new(1);
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let inner = jobs.clone();
+        jobs.set_listener(move |event| {
+            if let JobEvent::Progress { percent: 100, .. } = event {
+                let tx = tx.lock().unwrap().clone();
+                inner.submit("follow-up", None, |_| (), move |_, _| {
+                    let _ = tx.send(());
+                });
+            }
+        });
+        submit(&jobs, None, |job| job.report_progress(100));
+        assert!(rx.recv_timeout(WAIT).is_ok());
+    }
+}
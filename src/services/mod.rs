@@ -1,2 +1,4 @@
 pub mod config;
-pub mod storage; 
\ No newline at end of file
+pub mod file;
+pub mod job;
+pub mod storage;
\ No newline at end of file
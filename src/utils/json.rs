@@ -1,40 +1,518 @@
-use serde_json::{from_str, to_string_pretty, Value};
-use std::sync::mpsc;
-use std::thread;
-use std::time::{Duration, Instant};
+use serde_json::{from_str, Value};
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+mod canonical;
+mod codegen;
+mod convert;
+mod diff;
+mod error;
+mod fake;
+mod flatten;
+mod format;
+mod highlight;
+mod infer;
+mod jq;
+mod jsonpath;
+mod locate;
+mod ndjson;
+mod nested;
+mod patch;
+mod redact;
+mod relaxed;
+mod schema;
+mod search;
+mod shape;
+mod sql;
+mod stats;
+mod tree;
+
+pub use canonical::CanonicalJson;
+pub use codegen::{generate_code, CodeLanguage};
+pub use convert::DataFormat;
+pub use diff::{diff_values, ChangeKind, DiffEntry, DiffError, DiffOptions, JsonDiff};
+pub use error::{ErrorLocation, JsonError};
+pub use fake::{FakeOptions, FAKE_MAX_COUNT};
+pub use flatten::FlattenStyle;
+pub use format::{write_json, write_json_with_comments, FormatOptions, IndentStyle};
+pub use highlight::{tokenize_line, HighlightToken, LineIndex, TokenKind};
+pub use patch::{apply_json_patch, apply_merge_patch, apply_patch, PatchError, PatchKind};
+pub use redact::{MaskMode, RedactRules, ValueDetector};
+pub use infer::infer_schema;
+pub use locate::locate_pointers;
+pub use ndjson::{NdjsonAction, NdjsonLineError, NdjsonReport};
+pub use nested::{escape_string, unescape_string};
+pub use relaxed::{CommentMap, NodeComments};
+pub use schema::{
+    validate_schema, SchemaDraft, SchemaError, SchemaOptions, SchemaReport, SchemaViolation,
+};
+pub use search::{Matcher, SearchOptions};
+pub use sql::{NestedColumns, SqlDialect, SqlOptions};
+pub use stats::{DuplicateValue, JsonStats, TypeCounts};
+pub use tree::{JsonTree, TreeRow};
+
+/// 取消令牌，后台任务在各处理阶段协作检查
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 请求取消任务
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
 
-/// 进度信息枚举，用于传递处理进度
-pub enum ProcessUpdate {
-    Progress(usize), // 处理进度 (0-100)
-    Result(String),  // 最终结果
+    /// 任务是否已被取消
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
 }
 
+/// 读取时检查取消令牌，使解析过程可以被中断
+struct CancellableReader<'a> {
+    inner: &'a [u8],
+    token: &'a CancelToken,
+}
+
+impl Read for CancellableReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.token.is_cancelled() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, ERR_CANCELLED));
+        }
+        self.inner.read(buf)
+    }
+}
+
+
 // 错误消息常量
 const ERR_INVALID_JSON: &str = "无效的JSON格式";
-const ERR_FORMAT_FAILED: &str = "JSON格式化失败";
-const ERR_MINIFY_FAILED: &str = "JSON压缩失败";
+const ERR_CANCELLED: &str = "任务已取消";
 
 // 定义大小阈值
-const MAX_INPUT_SIZE: usize = 20_000_000; // 最大输入限制 20MB
+pub const MAX_INPUT_SIZE: usize = 20_000_000; // 最大输入限制 20MB
 const LARGE_JSON_THRESHOLD: usize = 500_000; // 大型JSON阈值 500KB
 const MB_SIZE: usize = 1024 * 1024;
 const KB_SIZE: usize = 1024;
 
-/// 生成超过最大输入限制的错误消息
-fn size_limit_error_msg(size: usize) -> String {
-    format!(
+/// 生成超过最大输入限制的错误
+fn size_limit_error(size: usize) -> JsonError {
+    JsonError::new(format!(
         "JSON数据过大（{}MB），已超过处理限制，请分批处理",
         size / MB_SIZE
-    )
+    ))
+}
+
+/// 快速检查未通过时，定位输入中的具体错误
+fn invalid_input_error(json_str: &str) -> JsonError {
+    match from_str::<serde::de::IgnoredAny>(json_str) {
+        Err(e) => JsonError::from_serde(&e, json_str),
+        Ok(_) => JsonError::new(format!("{}: 顶层必须是对象或数组", ERR_INVALID_JSON)),
+    }
+}
+
+/// 按选项输出JSON文本（格式化或压缩）
+fn render_json(value: &Value, comments: &CommentMap, options: &FormatOptions, format: bool) -> String {
+    write_json_with_comments(value, comments, options, format, &CancelToken::new()).unwrap_or_default()
+}
+
+/// 解析JSON字符串，默认保留键的原始顺序和数字的原始精度，可选按键排序；
+/// 宽松模式下接受JSON5/JSONC语法，并按选项收集注释
+fn parse_json(json_str: &str, options: &FormatOptions) -> Result<(Value, CommentMap), JsonError> {
+    let (mut value, comments) = if options.relaxed {
+        match relaxed::parse_relaxed(json_str, &CancelToken::new()) {
+            Ok(parsed) => parsed,
+            Err(TaskError::Failed(e)) => return Err(e),
+            Err(TaskError::Cancelled) => return Err(JsonError::new(ERR_CANCELLED)),
+        }
+    } else {
+        let value = from_str::<Value>(json_str).map_err(|e| {
+            println!("JSON解析错误: {}", e);
+            JsonError::from_serde(&e, json_str)
+        })?;
+        (value, CommentMap::default())
+    };
+
+    if options.sort_keys {
+        value.sort_all_objects();
+    }
+    if !options.keep_comments {
+        return Ok((value, CommentMap::default()));
+    }
+    Ok((value, comments))
+}
+
+/// 解析JSON文档，供树形视图等需要直接访问结构的功能使用；任务被取消时返回Ok(None)
+pub fn parse_json_document(json_str: &str, options: &FormatOptions, token: &CancelToken) -> Result<Option<Value>, JsonError> {
+    if json_str.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(json_str.len()));
+    }
+    match parse_json_cancellable(json_str, options, token) {
+        Ok((value, _)) => Ok(Some(value)),
+        Err(TaskError::Cancelled) => Ok(None),
+        Err(TaskError::Failed(e)) => Err(e),
+    }
+}
+
+/// 解析两个JSON文档并进行结构化对比；任务被取消时返回Ok(None)
+pub fn diff_json(
+    left: &str,
+    right: &str,
+    options: &FormatOptions,
+    diff_options: &DiffOptions,
+    token: &CancelToken,
+) -> Result<Option<JsonDiff>, DiffError> {
+    let start_time = Instant::now();
+    let Some(left) = parse_json_document(left, options, token).map_err(DiffError::Left)? else {
+        return Ok(None);
+    };
+    let Some(right) = parse_json_document(right, options, token).map_err(DiffError::Right)? else {
+        return Ok(None);
+    };
+    let result = diff_values(&left, &right, diff_options, token);
+    println!("JSON对比耗时: {:?}", start_time.elapsed());
+    Ok(result)
+}
+
+/// 将补丁应用到JSON文档，自动识别JSON Patch或Merge Patch，返回补丁类型和格式化后的结果；
+/// 任务被取消时返回Ok(None)
+pub fn patch_json(
+    document: &str,
+    patch: &str,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<(PatchKind, String)>, PatchError> {
+    let start_time = Instant::now();
+    let Some(document) = parse_json_document(document, options, token).map_err(PatchError::Document)? else {
+        return Ok(None);
+    };
+    let Some(patch) = parse_json_document(patch, options, token).map_err(PatchError::Patch)? else {
+        return Ok(None);
+    };
+    let Some((kind, result)) = apply_patch(document, &patch, token)? else {
+        return Ok(None);
+    };
+    println!("JSON补丁应用耗时: {:?}", start_time.elapsed());
+    Ok(write_json(&result, options, true, token).map(|output| (kind, output)))
+}
+
+/// 展开以字符串形式嵌套的JSON（包括整体被字符串化的文档），返回格式化结果和展开的个数；
+/// 任务被取消时返回Ok(None)
+pub fn expand_nested_json(
+    input: &str,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<(String, usize)>, JsonError> {
+    let start_time = Instant::now();
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input.len()));
+    }
+    let (mut value, comments) = match parse_json_cancellable(input, options, token) {
+        Ok(parsed) => parsed,
+        Err(TaskError::Cancelled) => return Ok(None),
+        Err(TaskError::Failed(e)) => return Err(e),
+    };
+    let Some(count) = nested::expand_nested(&mut value, token) else {
+        return Ok(None);
+    };
+    // 展开出的内容与原文无关，只需按选项重新排序
+    if options.sort_keys {
+        value.sort_all_objects();
+    }
+    println!("嵌套JSON展开耗时: {:?}（{}处）", start_time.elapsed(), count);
+    let output = write_json_with_comments(&value, &comments, options, true, token);
+    Ok(output.map(|output| (output, count)))
+}
+
+/// 统计文档结构和体积构成；任务被取消时返回Ok(None)
+pub fn json_stats(input: &str, options: &FormatOptions, token: &CancelToken) -> Result<Option<JsonStats>, JsonError> {
+    let start_time = Instant::now();
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input.len()));
+    }
+    let value = match parse_json_cancellable(input, options, token) {
+        Ok((value, _)) => value,
+        Err(TaskError::Cancelled) => return Ok(None),
+        Err(TaskError::Failed(e)) => return Err(e),
+    };
+    let stats = stats::compute_stats(&value, input.len(), options, token);
+    println!("JSON统计耗时: {:?}", start_time.elapsed());
+    Ok(stats)
+}
+
+/// 将JSON Pointer指向的节点压缩为JSON字符串，返回格式化后的整个文档；任务被取消时返回Ok(None)
+pub fn stringify_json_node(
+    input: &str,
+    pointer: &str,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<String>, JsonError> {
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input.len()));
+    }
+    let (mut value, comments) = match parse_json_cancellable(input, options, token) {
+        Ok(parsed) => parsed,
+        Err(TaskError::Cancelled) => return Ok(None),
+        Err(TaskError::Failed(e)) => return Err(e),
+    };
+    nested::stringify_node(&mut value, pointer, options).map_err(JsonError::new)?;
+    Ok(write_json_with_comments(&value, &comments, options, true, token))
+}
+
+/// 将对象或数组扁平化为一层的键值映射（点号路径或环境变量风格）；任务被取消时返回Ok(None)
+pub fn flatten_json(
+    input: &str,
+    style: &FlattenStyle,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<String>, JsonError> {
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input.len()));
+    }
+    let value = match parse_json_cancellable(input, options, token) {
+        Ok((value, _)) => value,
+        Err(TaskError::Cancelled) => return Ok(None),
+        Err(TaskError::Failed(e)) => return Err(e),
+    };
+    let Some(mut map) = flatten::flatten(&value, style, token).map_err(JsonError::new)? else {
+        return Ok(None);
+    };
+    if options.sort_keys {
+        map.sort_keys();
+    }
+    Ok(write_json(&Value::Object(map), options, true, token))
+}
+
+/// 将扁平的键值映射还原为嵌套的JSON，数字下标还原为数组；任务被取消时返回Ok(None)
+pub fn unflatten_json(
+    input: &str,
+    style: &FlattenStyle,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<String>, JsonError> {
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input.len()));
+    }
+    let map = match parse_json_cancellable(input, options, token) {
+        Ok((Value::Object(map), _)) => map,
+        Ok(_) => return Err(JsonError::new("只能还原扁平的键值对象")),
+        Err(TaskError::Cancelled) => return Ok(None),
+        Err(TaskError::Failed(e)) => return Err(e),
+    };
+    let Some(mut value) = flatten::unflatten(&map, style, token).map_err(JsonError::new)? else {
+        return Ok(None);
+    };
+    if options.sort_keys {
+        value.sort_all_objects();
+    }
+    Ok(write_json(&value, options, true, token))
+}
+
+/// 按规则脱敏敏感字段，返回格式化结果和脱敏的值的个数；任务被取消时返回Ok(None)
+pub fn redact_json(
+    input: &str,
+    rules: &RedactRules,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<(String, usize)>, JsonError> {
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input.len()));
+    }
+    let (mut value, comments) = match parse_json_cancellable(input, options, token) {
+        Ok(parsed) => parsed,
+        Err(TaskError::Cancelled) => return Ok(None),
+        Err(TaskError::Failed(e)) => return Err(e),
+    };
+    let Some(count) = redact::redact(&mut value, rules, token).map_err(JsonError::new)? else {
+        return Ok(None);
+    };
+    let output = write_json_with_comments(&value, &comments, options, true, token);
+    Ok(output.map(|output| (output, count)))
+}
+
+/// 按JSON Schema或样本文档生成随机假数据，输出JSON数组或NDJSON；任务被取消时返回Ok(None)
+pub fn fake_json(
+    input: &str,
+    fake: &FakeOptions,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<String>, JsonError> {
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input.len()));
+    }
+    let value = match parse_json_cancellable(input, options, token) {
+        Ok((value, _)) => value,
+        Err(TaskError::Cancelled) => return Ok(None),
+        Err(TaskError::Failed(e)) => return Err(e),
+    };
+    fake::generate_fake(&value, fake, options, token).map_err(JsonError::new)
+}
+
+/// 使用JSON Schema校验文档，返回格式化后的文档和全部违规项（附带其在格式化文本中的位置）；
+/// 任务被取消时返回Ok(None)
+pub fn validate_json_schema(
+    instance: &str,
+    schema: &str,
+    options: &FormatOptions,
+    schema_options: &SchemaOptions,
+    token: &CancelToken,
+) -> Result<Option<SchemaReport>, SchemaError> {
+    let start_time = Instant::now();
+    let Some(instance) = parse_json_document(instance, options, token).map_err(SchemaError::Instance)? else {
+        return Ok(None);
+    };
+    let Some(schema) = parse_json_document(schema, options, token).map_err(SchemaError::Schema)? else {
+        return Ok(None);
+    };
+    let Some(mut violations) = validate_schema(&schema, &instance, schema_options, token) else {
+        return Ok(None);
+    };
+
+    let Some(formatted) = write_json(&instance, options, true, token) else {
+        return Ok(None);
+    };
+    let pointers = violations.iter().map(|v| v.instance_path.clone()).collect();
+    let spans = locate_pointers(&formatted, &pointers);
+    for violation in &mut violations {
+        violation.span = spans.get(&violation.instance_path).copied();
+    }
+    println!("JSON Schema校验耗时: {:?}", start_time.elapsed());
+    Ok(Some(SchemaReport {
+        draft: schema::detect_draft(&schema, schema_options),
+        formatted,
+        violations,
+    }))
+}
+
+/// 解析样本：整段输入是一个JSON文档时作为单个样本，否则按JSON Lines（或空白分隔的多个文档）逐个读取
+pub fn parse_json_samples(input: &str, options: &FormatOptions) -> Result<Vec<Value>, JsonError> {
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input.len()));
+    }
+    let error = match parse_json(input, options) {
+        Ok((value, _)) => return Ok(vec![value]),
+        Err(e) if options.relaxed => return Err(e),
+        Err(e) => e,
+    };
+
+    let mut samples = Vec::new();
+    for value in serde_json::Deserializer::from_str(input).into_iter::<Value>() {
+        let mut value = value.map_err(|e| JsonError::from_serde(&e, input))?;
+        if options.sort_keys {
+            value.sort_all_objects();
+        }
+        samples.push(value);
+    }
+    if samples.len() < 2 {
+        return Err(error);
+    }
+    Ok(samples)
+}
+
+/// 从样本推断JSON Schema（Draft 2020-12）；任务被取消时返回Ok(None)
+pub fn infer_json_schema(
+    input: &str,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<String>, JsonError> {
+    let start_time = Instant::now();
+    let samples = parse_json_samples(input, options)?;
+    let Some(schema) = infer_schema(&samples, token) else {
+        return Ok(None);
+    };
+    println!("JSON Schema推断耗时: {:?}（{}个样本）", start_time.elapsed(), samples.len());
+    Ok(Some(render_json(&schema, &CommentMap::default(), options, true)))
+}
+
+/// 从样本生成指定语言的类型定义：先推断Schema，样本中缺少的键生成为可选字段；
+/// 任务被取消时返回Ok(None)
+pub fn generate_json_code(
+    input: &str,
+    options: &FormatOptions,
+    language: CodeLanguage,
+    root_name: &str,
+    token: &CancelToken,
+) -> Result<Option<String>, JsonError> {
+    let start_time = Instant::now();
+    let samples = parse_json_samples(input, options)?;
+    let Some(schema) = infer_schema(&samples, token) else {
+        return Ok(None);
+    };
+    let code = generate_code(&schema, language, root_name).map_err(JsonError::new)?;
+    println!("{}代码生成耗时: {:?}", language.name(), start_time.elapsed());
+    Ok(Some(code))
+}
+
+/// 由对象数组或JSON Lines生成建表语句和INSERT语句；任务被取消时返回Ok(None)
+pub fn generate_json_sql(
+    input: &str,
+    options: &FormatOptions,
+    sql_options: &SqlOptions,
+    token: &CancelToken,
+) -> Result<Option<String>, JsonError> {
+    let start_time = Instant::now();
+    let mut samples = parse_json_samples(input, options)?;
+    // 单个数组文档的元素即为各行
+    if let [Value::Array(items)] = samples.as_mut_slice() {
+        samples = std::mem::take(items);
+    }
+    let sql = sql::generate_sql(&samples, sql_options, token).map_err(JsonError::new)?;
+    println!("{} SQL生成耗时: {:?}", sql_options.dialect.name(), start_time.elapsed());
+    Ok(sql)
+}
+
+/// 逐行处理NDJSON（JSON Lines），无效行记录在报告中而不中断处理；任务被取消时返回Ok(None)
+pub fn process_ndjson(
+    input: &str,
+    action: NdjsonAction,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<NdjsonReport>, JsonError> {
+    let start_time = Instant::now();
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input.len()));
+    }
+    let report = ndjson::process_ndjson(input, action, options, token);
+    if let Some(report) = &report {
+        println!("NDJSON处理耗时: {:?}（{}行，{}行无效）", start_time.elapsed(), report.lines, report.errors.len());
+    }
+    Ok(report)
+}
+
+/// 将顶层为数组的JSON转换为NDJSON，每个元素一行；任务被取消时返回Ok(None)
+pub fn json_array_to_ndjson(
+    input: &str,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<String>, JsonError> {
+    let start_time = Instant::now();
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input.len()));
+    }
+    let value = match parse_json_cancellable(input, options, token) {
+        Ok((value, _)) => value,
+        Err(TaskError::Cancelled) => return Ok(None),
+        Err(TaskError::Failed(e)) => return Err(e),
+    };
+    let Value::Array(items) = value else {
+        return Err(JsonError::new("无法转换为NDJSON: 顶层必须是数组"));
+    };
+    let output = ndjson::array_to_ndjson(&items, options, token);
+    println!("数组转NDJSON耗时: {:?}（{}个元素）", start_time.elapsed(), items.len());
+    Ok(output)
 }
 
 /// 格式化JSON字符串，标准实现
-pub fn format_json(json_str: &str) -> String {
+pub fn format_json(json_str: &str, options: &FormatOptions) -> Result<String, JsonError> {
     let start_time = Instant::now();
 
     // 对超大JSON进行直接拒绝处理
     if json_str.len() > MAX_INPUT_SIZE {
-        return size_limit_error_msg(json_str.len());
+        return Err(size_limit_error(json_str.len()));
     }
 
     // 记录大型JSON处理信息
@@ -43,26 +521,20 @@ pub fn format_json(json_str: &str) -> String {
     }
 
     // 解析和格式化JSON
-    let result = match from_str::<Value>(json_str) {
-        Ok(json) => to_string_pretty(&json).unwrap_or_else(|_| String::from(ERR_FORMAT_FAILED)),
-        Err(e) => {
-            let error_msg = format!("{}: {}", ERR_INVALID_JSON, e);
-            println!("JSON解析错误: {}", error_msg);
-            error_msg
-        }
-    };
+    let result = parse_json(json_str, options)
+        .map(|(json, comments)| render_json(&json, &comments, options, true));
 
     println!("JSON格式化耗时: {:?}", start_time.elapsed());
     result
 }
 
 /// 压缩JSON字符串，标准实现
-pub fn minify_json(json_str: &str) -> String {
+pub fn minify_json(json_str: &str, options: &FormatOptions) -> Result<String, JsonError> {
     let start_time = Instant::now();
 
     // 对超大JSON进行直接拒绝处理
     if json_str.len() > MAX_INPUT_SIZE {
-        return size_limit_error_msg(json_str.len());
+        return Err(size_limit_error(json_str.len()));
     }
 
     // 记录大型JSON处理信息
@@ -71,231 +543,335 @@ pub fn minify_json(json_str: &str) -> String {
     }
 
     // 解析和压缩JSON
-    let result = match from_str::<Value>(json_str) {
-        Ok(json) => serde_json::to_string(&json).unwrap_or_else(|_| String::from(ERR_MINIFY_FAILED)),
-        Err(e) => {
-            let error_msg = format!("{}: {}", ERR_INVALID_JSON, e);
-            println!("JSON解析错误: {}", error_msg);
-            error_msg
-        }
-    };
+    let result = parse_json(json_str, options)
+        .map(|(json, comments)| render_json(&json, &comments, options, false));
 
     println!("JSON压缩耗时: {:?}", start_time.elapsed());
     result
 }
 
-/// 异步格式化JSON字符串
-pub fn format_json_async(json_str: String) -> mpsc::Receiver<String> {
-    let (tx, rx) = mpsc::channel();
-
-    thread::spawn(move || {
-        let start_time = Instant::now();
+/// 按RFC 8785（JCS）输出规范化JSON，并计算规范化字节的摘要；任务被取消时返回Ok(None)
+pub fn canonicalize_json(
+    json_str: &str,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<CanonicalJson>, JsonError> {
+    let start_time = Instant::now();
+    if json_str.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(json_str.len()));
+    }
+    let value = match parse_json_cancellable(json_str, options, token) {
+        Ok((value, _)) => value,
+        Err(TaskError::Cancelled) => return Ok(None),
+        Err(TaskError::Failed(e)) => return Err(e),
+    };
+    let result = canonical::canonicalize(&value, token).map_err(JsonError::new);
+    println!("JSON规范化耗时: {:?}", start_time.elapsed());
+    result
+}
 
-        // 快速有效性检查
-        if !is_valid_json_quick_check(&json_str) {
-            let _ = tx.send(String::from(ERR_INVALID_JSON));
-            return;
-        }
+/// 可取消任务的失败原因
+enum TaskError {
+    Cancelled,
+    Failed(JsonError),
+}
 
-        // 验证并格式化JSON
-        let result = match from_str::<Value>(&json_str) {
-            Ok(json) => to_string_pretty(&json).unwrap_or_else(|_| String::from(ERR_FORMAT_FAILED)),
-            Err(_) => String::from(ERR_INVALID_JSON),
+/// 解析JSON，解析过程中持续检查取消令牌
+fn parse_json_cancellable(
+    json_str: &str,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<(Value, CommentMap), TaskError> {
+    let (mut value, comments) = if options.relaxed {
+        relaxed::parse_relaxed(json_str, token)?
+    } else {
+        let reader = CancellableReader {
+            inner: json_str.as_bytes(),
+            token,
         };
+        let value: Value = serde_json::from_reader(reader).map_err(|e| {
+            if token.is_cancelled() {
+                TaskError::Cancelled
+            } else {
+                TaskError::Failed(JsonError::from_serde(&e, json_str))
+            }
+        })?;
+        (value, CommentMap::default())
+    };
 
-        println!("异步JSON格式化耗时: {:?}", start_time.elapsed());
-        let _ = tx.send(result);
-    });
+    if options.sort_keys {
+        value.sort_all_objects();
+    }
+    if !options.keep_comments {
+        return Ok((value, CommentMap::default()));
+    }
+    Ok((value, comments))
+}
 
-    rx
+/// 按源格式解析文档；YAML和TOML中的注释不会保留
+fn parse_data_cancellable(
+    text: &str,
+    source: DataFormat,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<(Value, CommentMap), TaskError> {
+    let mut value = match source {
+        DataFormat::Json => return parse_json_cancellable(text, options, token),
+        DataFormat::Yaml => convert::parse_yaml(text, token)?,
+        DataFormat::Toml => convert::parse_toml(text, token)?,
+    };
+    if options.sort_keys {
+        value.sort_all_objects();
+    }
+    Ok((value, CommentMap::default()))
 }
 
-/// 异步压缩JSON字符串
-pub fn minify_json_async(json_str: String) -> mpsc::Receiver<String> {
-    let (tx, rx) = mpsc::channel();
+/// 按目标格式输出文档，JSON输出为格式化文本
+fn write_data_cancellable(
+    (value, comments): &(Value, CommentMap),
+    target: DataFormat,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<String, TaskError> {
+    match target {
+        DataFormat::Json => write_json_with_comments(value, comments, options, true, token).ok_or(TaskError::Cancelled),
+        DataFormat::Yaml => convert::write_yaml(value, comments, options, token),
+        DataFormat::Toml => convert::write_toml(value, comments, options, token),
+    }
+}
 
-    thread::spawn(move || {
-        let start_time = Instant::now();
+/// 将处理结果转换为相应格式，序列化过程中持续检查取消令牌
+fn process_json_result_cancellable(
+    (value, comments): &(Value, CommentMap),
+    format: bool,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<String, TaskError> {
+    write_json_with_comments(value, comments, options, format, token).ok_or(TaskError::Cancelled)
+}
 
-        // 快速有效性检查
-        if !is_valid_json_quick_check(&json_str) {
-            let _ = tx.send(String::from(ERR_INVALID_JSON));
-            return;
+/// 执行查询并输出结果：以 `$` 开头的按JSONPath处理，输出匹配节点组成的数组；
+/// 其余按jq表达式处理，每个结果单独格式化后按行拼接
+fn run_query(
+    value: &Value,
+    query: &str,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<String, TaskError> {
+    let query = query.trim();
+    if query.starts_with('$') {
+        let nodes = jsonpath::query(query, value, token)
+            .map_err(|e| TaskError::Failed(JsonError::new(e)))?
+            .ok_or(TaskError::Cancelled)?;
+        let result = Value::Array(nodes.into_iter().cloned().collect());
+        write_json(&result, options, true, token).ok_or(TaskError::Cancelled)
+    } else {
+        let results = jq::query(query, value, token)
+            .map_err(|e| TaskError::Failed(JsonError::new(e)))?
+            .ok_or(TaskError::Cancelled)?;
+        let mut output = Vec::with_capacity(results.len());
+        for result in &results {
+            output.push(write_json(result, options, true, token).ok_or(TaskError::Cancelled)?);
         }
+        Ok(output.join("\n"))
+    }
+}
 
-        // 验证并压缩JSON
-        let result = match from_str::<Value>(&json_str) {
-            Ok(json) => serde_json::to_string(&json).unwrap_or_else(|_| String::from(ERR_MINIFY_FAILED)),
-            Err(_) => String::from(ERR_INVALID_JSON),
-        };
+/// 对JSON执行JSONPath或jq查询，标准实现
+pub fn query_json(json_str: &str, query: &str, options: &FormatOptions) -> Result<String, JsonError> {
+    let start_time = Instant::now();
+
+    if json_str.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(json_str.len()));
+    }
 
-        println!("异步JSON压缩耗时: {:?}", start_time.elapsed());
-        let _ = tx.send(result);
-    });
+    let (json, _) = parse_json(json_str, options)?;
+    let result = match run_query(&json, query, options, &CancelToken::new()) {
+        Ok(output) => Ok(output),
+        Err(TaskError::Failed(e)) => Err(e),
+        Err(TaskError::Cancelled) => Err(JsonError::new(ERR_CANCELLED)),
+    };
 
-    rx
+    println!("JSON查询耗时: {:?}", start_time.elapsed());
+    result
 }
 
-/// 将处理结果转换为相应格式（格式化或压缩）
-fn process_json_result(value: &Value, format: bool) -> Result<String, String> {
-    if format {
-        to_string_pretty(value).map_err(|_| ERR_FORMAT_FAILED.to_string())
-    } else {
-        serde_json::to_string(value).map_err(|_| ERR_MINIFY_FAILED.to_string())
+/// 带进度反馈的JSON处理基础函数，解析完成后由transform生成最终输出；任务被取消时返回Ok(None)
+fn process_json_with_progress<F>(
+    json_str: &str,
+    source: DataFormat,
+    options: &FormatOptions,
+    operation_name: &str,
+    token: &CancelToken,
+    progress: &dyn Fn(usize),
+    transform: F,
+) -> Result<Option<String>, JsonError>
+where
+    F: FnOnce(&(Value, CommentMap), &FormatOptions, &CancelToken) -> Result<String, TaskError>,
+{
+    let start_time = Instant::now();
+    let input_size = json_str.len();
+
+    // 检查数据大小并拒绝过大的输入
+    if input_size > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input_size));
     }
-}
 
-/// 带进度反馈的异步JSON处理基础函数
-fn process_json_with_progress(
-    json_str: String, 
-    format: bool,
-    operation_name: &str
-) -> mpsc::Receiver<ProcessUpdate> {
-    let (tx, rx) = mpsc::channel();
-    let operation_name = operation_name.to_string(); // 克隆到闭包中
-
-    thread::spawn(move || {
-        let start_time = Instant::now();
-        let input_size = json_str.len();
-
-        // 检查数据大小并拒绝过大的输入
-        if input_size > MAX_INPUT_SIZE {
-            let error_msg = format!(
-                "JSON数据过大（{}MB），请减小数据量后重试",
-                input_size / MB_SIZE
-            );
-            let _ = tx.send(ProcessUpdate::Result(error_msg));
-            return;
-        }
+    // 先进行快速检查，宽松模式下开头可能是注释，跳过检查
+    if source == DataFormat::Json && !options.relaxed && !is_valid_json_quick_check(json_str) {
+        return Err(invalid_input_error(json_str));
+    }
 
-        // 先进行快速检查
-        if !is_valid_json_quick_check(&json_str) {
-            let _ = tx.send(ProcessUpdate::Result(String::from(ERR_INVALID_JSON)));
-            return;
-        }
+    // 报告初始进度
+    progress(5);
 
-        // 发送初始进度
-        let _ = tx.send(ProcessUpdate::Progress(5));
-
-        // 大数据分批处理优化
-        if input_size > LARGE_JSON_THRESHOLD {
-            // 预解析检查
-            let _ = tx.send(ProcessUpdate::Progress(10));
-            let pre_check = from_str::<Value>(&json_str);
-            if pre_check.is_err() {
-                let _ = tx.send(ProcessUpdate::Result(String::from(ERR_INVALID_JSON)));
-                return;
-            }
+    let result = if input_size > LARGE_JSON_THRESHOLD {
+        // 使用分批处理策略处理大型JSON
+        progress(10);
+        process_large_json_chunked(json_str, source, progress, options, token, transform)
+    } else {
+        // 正常处理中等大小的JSON
+        progress(30);
+        parse_data_cancellable(json_str, source, options, token).and_then(|json| {
+            progress(60);
+            transform(&json, options, token)
+        })
+    };
 
-            let _ = tx.send(ProcessUpdate::Progress(15));
-
-            // 使用分批处理策略处理大型JSON
-            match process_large_json_chunked(&json_str, &tx, format) {
-                Ok(result) => {
-                    let _ = tx.send(ProcessUpdate::Progress(100));
-                    let _ = tx.send(ProcessUpdate::Result(result));
-                }
-                Err(err) => {
-                    let _ = tx.send(ProcessUpdate::Result(format!("处理失败: {}", err)));
-                }
-            }
-        } else {
-            // 正常处理中等大小的JSON
-            let _ = tx.send(ProcessUpdate::Progress(30));
-
-            match from_str::<Value>(&json_str) {
-                Ok(json) => {
-                    let _ = tx.send(ProcessUpdate::Progress(60));
-
-                    // 增加延迟以避免UI线程阻塞
-                    thread::sleep(Duration::from_millis(50));
-
-                    match process_json_result(&json, format) {
-                        Ok(processed) => {
-                            let _ = tx.send(ProcessUpdate::Progress(90));
-                            let _ = tx.send(ProcessUpdate::Result(processed));
-                        }
-                        Err(err) => {
-                            let _ = tx.send(ProcessUpdate::Result(String::from(err)));
-                        }
-                    }
-                }
-                Err(_) => {
-                    let _ = tx.send(ProcessUpdate::Result(String::from(ERR_INVALID_JSON)));
-                }
-            }
+    let output = match result {
+        Ok(processed) => {
+            progress(100);
+            processed
         }
+        Err(TaskError::Cancelled) => {
+            println!("JSON{}已取消", operation_name);
+            return Ok(None);
+        }
+        Err(TaskError::Failed(err)) => return Err(err),
+    };
 
-        println!("JSON{}总耗时: {:?}", operation_name, start_time.elapsed());
-    });
-
-    rx
+    println!("JSON{}总耗时: {:?}", operation_name, start_time.elapsed());
+    Ok(Some(output))
 }
 
-/// 带进度反馈的异步JSON格式化
-pub fn format_json_with_progress(json_str: String) -> mpsc::Receiver<ProcessUpdate> {
-    process_json_with_progress(json_str, true, "格式化")
+/// 带进度反馈的JSON格式化；任务被取消时返回Ok(None)
+pub fn format_json_with_progress(
+    json_str: &str,
+    options: &FormatOptions,
+    token: &CancelToken,
+    progress: &dyn Fn(usize),
+) -> Result<Option<String>, JsonError> {
+    process_json_with_progress(json_str, DataFormat::Json, options, "格式化", token, progress, |json, options, token| {
+        process_json_result_cancellable(json, true, options, token)
+    })
 }
 
-/// 带进度反馈的异步JSON压缩
-pub fn minify_json_with_progress(json_str: String) -> mpsc::Receiver<ProcessUpdate> {
-    process_json_with_progress(json_str, false, "压缩")
+/// 带进度反馈的JSON压缩；任务被取消时返回Ok(None)
+pub fn minify_json_with_progress(
+    json_str: &str,
+    options: &FormatOptions,
+    token: &CancelToken,
+    progress: &dyn Fn(usize),
+) -> Result<Option<String>, JsonError> {
+    process_json_with_progress(json_str, DataFormat::Json, options, "压缩", token, progress, |json, options, token| {
+        process_json_result_cancellable(json, false, options, token)
+    })
 }
 
-/// 处理大型JSON数据，使用分块策略并报告进度
-fn process_large_json_chunked(
+/// 带进度反馈的JSON查询；任务被取消时返回Ok(None)
+pub fn query_json_with_progress(
     json_str: &str,
-    tx: &mpsc::Sender<ProcessUpdate>,
-    format: bool,
-) -> Result<String, String> {
-    // 辅助函数：更新进度并允许UI线程刷新
-    let update_progress = |progress: usize| {
-        let _ = tx.send(ProcessUpdate::Progress(progress));
-        thread::sleep(Duration::from_millis(10)); // 给UI线程喘息的机会
-    };
+    query: &str,
+    options: &FormatOptions,
+    token: &CancelToken,
+    progress: &dyn Fn(usize),
+) -> Result<Option<String>, JsonError> {
+    process_json_with_progress(json_str, DataFormat::Json, options, "查询", token, progress, |json, options, token| {
+        run_query(&json.0, query, options, token)
+    })
+}
 
-    // 确定解析策略
-    let trimmed = json_str.trim();
-    let first_char = trimmed.chars().next().unwrap();
+/// 在JSON、YAML和TOML之间转换，标准实现
+pub fn convert_data(
+    input: &str,
+    from: DataFormat,
+    to: DataFormat,
+    options: &FormatOptions,
+) -> Result<String, JsonError> {
+    let start_time = Instant::now();
 
-    update_progress(20);
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(size_limit_error(input.len()));
+    }
 
-    // 解析整个JSON
-    let json_value = match from_str::<Value>(json_str) {
-        Ok(value) => value,
-        Err(e) => return Err(format!("JSON解析错误: {}", e)),
+    let token = CancelToken::new();
+    let result = match parse_data_cancellable(input, from, options, &token)
+        .and_then(|document| write_data_cancellable(&document, to, options, &token))
+    {
+        Ok(output) => Ok(output),
+        Err(TaskError::Failed(e)) => Err(e),
+        Err(TaskError::Cancelled) => Err(JsonError::new(ERR_CANCELLED)),
     };
 
-    update_progress(50);
+    println!("{}转{}耗时: {:?}", from.name(), to.name(), start_time.elapsed());
+    result
+}
+
+/// 带进度反馈的格式转换；任务被取消时返回Ok(None)
+pub fn convert_data_with_progress(
+    input: &str,
+    from: DataFormat,
+    to: DataFormat,
+    options: &FormatOptions,
+    token: &CancelToken,
+    progress: &dyn Fn(usize),
+) -> Result<Option<String>, JsonError> {
+    let operation_name = format!("{}转{}", from.name(), to.name());
+    process_json_with_progress(input, from, options, &operation_name, token, progress, |document, options, token| {
+        write_data_cancellable(document, to, options, token)
+    })
+}
+
+/// 处理大型JSON数据，使用分块策略并报告进度
+fn process_large_json_chunked<F>(
+    json_str: &str,
+    source: DataFormat,
+    progress: &dyn Fn(usize),
+    options: &FormatOptions,
+    token: &CancelToken,
+    transform: F,
+) -> Result<String, TaskError>
+where
+    F: FnOnce(&(Value, CommentMap), &FormatOptions, &CancelToken) -> Result<String, TaskError>,
+{
+    // 辅助函数：更新进度，已取消时中止
+    let update_progress = |percent: usize| {
+        if token.is_cancelled() {
+            return Err(TaskError::Cancelled);
+        }
+        progress(percent);
+        Ok(())
+    };
 
-    // 数组特殊处理
-    if first_char == '[' {
-        println!("处理大型JSON数组...");
+    update_progress(20)?;
 
-        // 分析数组大小
-        if let Value::Array(array) = &json_value {
-            println!("JSON数组元素数量: {}", array.len());
+    // 解析整个文档
+    let json_value = parse_data_cancellable(json_str, source, options, token)?;
 
-            // 如果是大型数组，使用分批处理策略
-            if array.len() > 1000 {
-                update_progress(60);
+    update_progress(50)?;
 
-                // 模拟分批处理的进度更新
-                for i in (60..90).step_by(5) {
-                    update_progress(i);
-                }
-            }
-        }
-    } else {
-        // 对象特殊处理
-        println!("处理大型JSON对象...");
-        update_progress(70);
+    match &json_value.0 {
+        Value::Array(array) => println!("处理大型JSON数组，元素数量: {}", array.len()),
+        _ => println!("处理大型JSON对象..."),
     }
 
-    update_progress(90);
+    update_progress(70)?;
 
     // 生成最终结果
-    process_json_result(&json_value, format)
+    let result = transform(&json_value, options, token)?;
+
+    update_progress(90)?;
+    Ok(result)
 }
 
 /// 快速检查JSON是否有效，不进行完整解析
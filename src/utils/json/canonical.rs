@@ -0,0 +1,146 @@
+use super::CancelToken;
+use md5::Md5;
+use serde_json::{Number, Value};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+/// 规范化（RFC 8785 JCS）结果及其摘要
+#[derive(Debug, Clone)]
+pub struct CanonicalJson {
+    pub text: String,
+    /// (算法名, 小写十六进制摘要)
+    pub digests: Vec<(&'static str, String)>,
+}
+
+/// 按RFC 8785输出规范化JSON：键按UTF-16码元排序、数字按ECMAScript规则输出、字符串最小转义；
+/// 任务被取消时返回Ok(None)
+pub fn canonicalize(value: &Value, token: &CancelToken) -> Result<Option<CanonicalJson>, String> {
+    let mut out = String::new();
+    if !write_canonical(&mut out, value, token)? {
+        return Ok(None);
+    }
+    let bytes = out.as_bytes();
+    let digests = vec![
+        ("SHA-256", hex(&Sha256::digest(bytes))),
+        ("SHA-512", hex(&Sha512::digest(bytes))),
+        ("SHA-1", hex(&Sha1::digest(bytes))),
+        ("MD5", hex(&Md5::digest(bytes))),
+    ];
+    Ok(Some(CanonicalJson { text: out, digests }))
+}
+
+/// 写出规范化文本，被取消时返回Ok(false)
+fn write_canonical(out: &mut String, value: &Value, token: &CancelToken) -> Result<bool, String> {
+    if token.is_cancelled() {
+        return Ok(false);
+    }
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&format_number(n)?),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                if !write_canonical(out, item, token)? {
+                    return Ok(false);
+                }
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            // 键按UTF-16码元序比较，与按字符序在代理对范围内结果不同
+            let mut entries: Vec<(Vec<u16>, &String, &Value)> =
+                map.iter().map(|(k, v)| (k.encode_utf16().collect(), k, v)).collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            out.push('{');
+            for (i, (_, key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                if !write_canonical(out, item, token)? {
+                    return Ok(false);
+                }
+            }
+            out.push('}');
+        }
+    }
+    Ok(true)
+}
+
+/// 最小转义：只转义引号、反斜杠和控制字符，其余字符按UTF-8原样输出
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{0C}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// 按ECMAScript的Number.prototype.toString规则输出数字（先转换为双精度浮点数）
+fn format_number(n: &Number) -> Result<String, String> {
+    let text = n.to_string();
+    let value: f64 = text
+        .parse()
+        .map_err(|_| format!("无法规范化数字 {}", text))?;
+    if !value.is_finite() {
+        return Err(format!("数字 {} 超出双精度浮点数范围，无法规范化", text));
+    }
+    if value == 0.0 {
+        return Ok("0".to_string());
+    }
+
+    // {:e} 输出最短的可往返表示，如 "1.2345e-7"
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let k = digits.len() as i32;
+    let n = exponent + 1; // 小数点位于第n位数字之后
+
+    let mut out = String::new();
+    if value < 0.0 {
+        out.push('-');
+    }
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.push_str(&"0".repeat((-n) as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push(if n - 1 < 0 { '-' } else { '+' });
+        out.push_str(&(n - 1).abs().to_string());
+    }
+    Ok(out)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,530 @@
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// 代码生成的目标语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeLanguage {
+    Rust, // serde 结构体
+    TypeScript,
+    Go,
+    Java, // record，使用Jackson注解
+}
+
+impl CodeLanguage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CodeLanguage::Rust => "Rust",
+            CodeLanguage::TypeScript => "TypeScript",
+            CodeLanguage::Go => "Go",
+            CodeLanguage::Java => "Java",
+        }
+    }
+}
+
+const RUST_KEYWORDS: [&str; 51] = [
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in",
+    "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+    "trait", "true", "type", "unsafe", "use", "where", "while", "async", "await", "dyn", "abstract", "become", "box",
+    "do", "final", "macro", "override", "priv", "typeof", "unsized", "virtual", "yield", "try",
+];
+
+const JAVA_KEYWORDS: [&str; 53] = [
+    "abstract", "assert", "boolean", "break", "byte", "case", "catch", "char", "class", "const", "continue",
+    "default", "do", "double", "else", "enum", "extends", "final", "finally", "float", "for", "goto", "if",
+    "implements", "import", "instanceof", "int", "interface", "long", "native", "new", "package", "private",
+    "protected", "public", "return", "short", "static", "strictfp", "super", "switch", "synchronized", "this",
+    "throw", "throws", "transient", "try", "void", "volatile", "while", "true", "false", "null",
+];
+
+/// Go 中按惯例全大写的缩写词
+const GO_INITIALISMS: [&str; 8] = ["id", "url", "uri", "api", "http", "json", "uuid", "ip"];
+
+/// 字段类型
+#[derive(Debug, Clone)]
+enum Ty {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Any,            // 无法确定类型
+    Map,            // 没有已知属性的对象
+    Array(Box<Ty>),
+    Struct(String),
+    Union(Vec<Ty>), // 多种类型，仅TypeScript可直接表示
+    Nullable(Box<Ty>),
+}
+
+struct Field {
+    key: String,
+    ty: Ty,
+    optional: bool, // 部分样本中缺少该键
+}
+
+struct TypeDef {
+    name: String,
+    fields: Vec<Field>,
+}
+
+/// 根据Schema（通常由样本推断得到）生成指定语言的类型定义，Schema中没有对象时返回错误
+pub fn generate_code(schema: &Value, language: CodeLanguage, root_name: &str) -> Result<String, String> {
+    let mut builder = Builder::default();
+    let root_name = pascal_case(root_name);
+    // 根节点为数组时，元素类型以 Item 结尾，根类型作为别名
+    let root = match schema_types(schema).as_slice() {
+        ["array"] => {
+            let item_name = format!("{}Item", root_name);
+            Ty::Array(Box::new(
+                schema.get("items").map_or(Ty::Any, |items| builder.ty(items, &item_name)),
+            ))
+        }
+        _ => builder.ty(schema, &root_name),
+    };
+
+    if builder.defs.is_empty() {
+        return Err("无法生成类型定义：根节点必须是对象或对象数组".to_string());
+    }
+    Ok(match language {
+        CodeLanguage::Rust => render_rust(&builder.defs, &root, &root_name),
+        CodeLanguage::TypeScript => render_typescript(&builder.defs, &root, &root_name),
+        CodeLanguage::Go => render_go(&builder.defs, &root, &root_name),
+        CodeLanguage::Java => render_java(&builder.defs, &root),
+    })
+}
+
+fn schema_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 将Schema转换为类型定义，嵌套对象按键名生成具名类型
+#[derive(Default)]
+struct Builder {
+    defs: Vec<TypeDef>,
+    names: HashSet<String>,
+}
+
+impl Builder {
+    fn ty(&mut self, schema: &Value, name_hint: &str) -> Ty {
+        let types = schema_types(schema);
+        let nullable = types.contains(&"null");
+        let non_null: Vec<&str> = types.into_iter().filter(|t| *t != "null").collect();
+        let ty = match non_null.as_slice() {
+            [] => return Ty::Any,
+            [single] => self.single(schema, single, name_hint),
+            many => Ty::Union(many.iter().map(|t| self.single(schema, t, name_hint)).collect()),
+        };
+        if nullable {
+            Ty::Nullable(Box::new(ty))
+        } else {
+            ty
+        }
+    }
+
+    fn single(&mut self, schema: &Value, kind: &str, name_hint: &str) -> Ty {
+        match kind {
+            "string" => Ty::String,
+            "integer" => Ty::Integer,
+            "number" => Ty::Number,
+            "boolean" => Ty::Boolean,
+            "array" => {
+                let item_hint = singular(name_hint);
+                Ty::Array(Box::new(schema.get("items").map_or(Ty::Any, |items| self.ty(items, &item_hint))))
+            }
+            "object" => self.object(schema, name_hint),
+            _ => Ty::Any,
+        }
+    }
+
+    fn object(&mut self, schema: &Value, name_hint: &str) -> Ty {
+        let Some(properties) = schema.get("properties").and_then(Value::as_object).filter(|p| !p.is_empty()) else {
+            return Ty::Map;
+        };
+        let name = self.unique_name(&pascal_case(name_hint));
+        let index = self.defs.len();
+        self.defs.push(TypeDef {
+            name: name.clone(),
+            fields: Vec::new(),
+        });
+
+        let required: HashSet<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let fields = properties
+            .iter()
+            .map(|(key, sub)| Field {
+                key: key.clone(),
+                ty: self.ty(sub, key),
+                optional: !required.contains(key.as_str()),
+            })
+            .collect();
+        self.defs[index].fields = fields;
+        Ty::Struct(name)
+    }
+
+    /// 类型名重复时追加序号
+    fn unique_name(&mut self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut n = 2;
+        while !self.names.insert(name.clone()) {
+            name = format!("{}{}", base, n);
+            n += 1;
+        }
+        name
+    }
+}
+
+/// 将键拆分为小写单词，识别分隔符和驼峰边界
+fn split_words(key: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let chars: Vec<char> = key.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|j| chars[j]);
+        let next = chars.get(i + 1);
+        let boundary = c.is_uppercase()
+            && prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit()
+                || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase())));
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    if words.first().is_none_or(|w| w.starts_with(|c: char| c.is_ascii_digit())) {
+        words.insert(0, "field".to_string());
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn pascal_case(key: &str) -> String {
+    split_words(key).iter().map(|w| capitalize(w)).collect()
+}
+
+fn snake_case(key: &str) -> String {
+    split_words(key).join("_")
+}
+
+fn camel_case(key: &str) -> String {
+    let words = split_words(key);
+    let mut out = words[0].clone();
+    for word in &words[1..] {
+        out.push_str(&capitalize(word));
+    }
+    out
+}
+
+/// 数组元素的类型名：去掉复数词尾，无法处理时追加 Item
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies").filter(|s| s.len() > 1) {
+        format!("{}y", stem)
+    } else if let Some(stem) = name.strip_suffix('s').filter(|s| s.len() > 2 && !s.ends_with('s')) {
+        stem.to_string()
+    } else {
+        format!("{}Item", name)
+    }
+}
+
+/// 为同一类型中的字段生成不重复的名称：基础名重复时追加 suffix 和序号，finish 负责处理关键字
+fn field_names(fields: &[Field], base: fn(&str) -> String, suffix: &str, finish: fn(String) -> String) -> Vec<String> {
+    let mut used = HashSet::new();
+    fields
+        .iter()
+        .map(|field| {
+            let base = base(&field.key);
+            let mut name = finish(base.clone());
+            let mut n = 2;
+            while !used.insert(name.clone()) {
+                name = finish(format!("{}{}{}", base, suffix, n));
+                n += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+fn rust_field_name(name: String) -> String {
+    if !RUST_KEYWORDS.contains(&name.as_str()) {
+        return name;
+    }
+    match name.as_str() {
+        "self" | "Self" | "super" | "crate" => format!("{}_", name),
+        _ => format!("r#{}", name),
+    }
+}
+
+fn java_field_name(mut name: String) -> String {
+    if JAVA_KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+fn is_identifier(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+fn rust_type(ty: &Ty) -> String {
+    match ty {
+        Ty::String => "String".to_string(),
+        Ty::Integer => "i64".to_string(),
+        Ty::Number => "f64".to_string(),
+        Ty::Boolean => "bool".to_string(),
+        Ty::Any | Ty::Union(_) => "serde_json::Value".to_string(),
+        Ty::Map => "serde_json::Map<String, serde_json::Value>".to_string(),
+        Ty::Array(item) => format!("Vec<{}>", rust_type(item)),
+        Ty::Struct(name) => name.clone(),
+        Ty::Nullable(inner) => format!("Option<{}>", rust_type(inner)),
+    }
+}
+
+fn render_rust(defs: &[TypeDef], root: &Ty, root_name: &str) -> String {
+    let mut out = String::from("use serde::{Deserialize, Serialize};\n");
+    if let Ty::Array(_) = root {
+        let _ = write!(out, "\npub type {} = {};\n", root_name, rust_type(root));
+    }
+    for def in defs {
+        let _ = write!(out, "\n#[derive(Debug, Clone, Serialize, Deserialize)]\npub struct {} {{\n", def.name);
+        let names = field_names(&def.fields, snake_case, "_", rust_field_name);
+        for (field, name) in def.fields.iter().zip(names) {
+            let mut attrs = Vec::new();
+            // serde按去掉 r# 后的字段名序列化，与键不同时需要重命名
+            if name.strip_prefix("r#").unwrap_or(&name) != field.key {
+                attrs.push(format!("rename = {}", serde_json::to_string(&field.key).unwrap_or_default()));
+            }
+            // 缺少键时反序列化为None，序列化时也省略
+            let ty = match &field.ty {
+                Ty::Nullable(_) => rust_type(&field.ty),
+                other if field.optional => format!("Option<{}>", rust_type(other)),
+                other => rust_type(other),
+            };
+            if field.optional {
+                attrs.push("default, skip_serializing_if = \"Option::is_none\"".to_string());
+            }
+            if !attrs.is_empty() {
+                let _ = writeln!(out, "    #[serde({})]", attrs.join(", "));
+            }
+            let _ = writeln!(out, "    pub {}: {},", name, ty);
+        }
+        out.push_str("}\n");
+    }
+    out
+}
+
+fn typescript_type(ty: &Ty) -> String {
+    match ty {
+        Ty::String => "string".to_string(),
+        Ty::Integer | Ty::Number => "number".to_string(),
+        Ty::Boolean => "boolean".to_string(),
+        Ty::Any => "unknown".to_string(),
+        Ty::Map => "Record<string, unknown>".to_string(),
+        Ty::Array(item) => match item.as_ref() {
+            Ty::Union(_) | Ty::Nullable(_) => format!("({})[]", typescript_type(item)),
+            _ => format!("{}[]", typescript_type(item)),
+        },
+        Ty::Struct(name) => name.clone(),
+        Ty::Union(types) => types.iter().map(typescript_type).collect::<Vec<_>>().join(" | "),
+        Ty::Nullable(inner) => format!("{} | null", typescript_type(inner)),
+    }
+}
+
+fn render_typescript(defs: &[TypeDef], root: &Ty, root_name: &str) -> String {
+    let mut out = String::new();
+    if let Ty::Array(_) = root {
+        let _ = writeln!(out, "export type {} = {};", root_name, typescript_type(root));
+    }
+    for def in defs {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let _ = writeln!(out, "export interface {} {{", def.name);
+        for field in &def.fields {
+            let key = if is_identifier(&field.key) {
+                field.key.clone()
+            } else {
+                serde_json::to_string(&field.key).unwrap_or_default()
+            };
+            let optional = if field.optional { "?" } else { "" };
+            let _ = writeln!(out, "  {}{}: {};", key, optional, typescript_type(&field.ty));
+        }
+        out.push_str("}\n");
+    }
+    out
+}
+
+fn go_type(ty: &Ty) -> String {
+    match ty {
+        Ty::String => "string".to_string(),
+        Ty::Integer => "int64".to_string(),
+        Ty::Number => "float64".to_string(),
+        Ty::Boolean => "bool".to_string(),
+        Ty::Any | Ty::Union(_) => "interface{}".to_string(),
+        Ty::Map => "map[string]interface{}".to_string(),
+        Ty::Array(item) => format!("[]{}", go_type(item)),
+        Ty::Struct(name) => name.clone(),
+        Ty::Nullable(inner) => go_pointer(inner),
+    }
+}
+
+/// 可为空的值使用指针，切片、映射和接口本身可以为nil
+fn go_pointer(ty: &Ty) -> String {
+    match ty {
+        Ty::Array(_) | Ty::Map | Ty::Any | Ty::Union(_) | Ty::Nullable(_) => go_type(ty),
+        other => format!("*{}", go_type(other)),
+    }
+}
+
+fn go_field_name(key: &str) -> String {
+    split_words(key)
+        .iter()
+        .map(|w| if GO_INITIALISMS.contains(&w.as_str()) { w.to_uppercase() } else { capitalize(w) })
+        .collect()
+}
+
+fn render_go(defs: &[TypeDef], root: &Ty, root_name: &str) -> String {
+    let mut out = String::new();
+    if let Ty::Array(_) = root {
+        let _ = writeln!(out, "type {} {}", root_name, go_type(root));
+    }
+    for def in defs {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let names = field_names(&def.fields, go_field_name, "", |name| name);
+        let rows: Vec<(String, String, String)> = def
+            .fields
+            .iter()
+            .zip(names)
+            .map(|(field, name)| {
+                let ty = if field.optional { go_pointer(&field.ty) } else { go_type(&field.ty) };
+                let omit = if field.optional { ",omitempty" } else { "" };
+                let tag = format!("`json:\"{}{}\"`", field.key.replace('"', "\\\""), omit);
+                (name, ty, tag)
+            })
+            .collect();
+        // 与gofmt一致，对齐字段名和类型列
+        let name_width = rows.iter().map(|r| r.0.chars().count()).max().unwrap_or(0);
+        let type_width = rows.iter().map(|r| r.1.chars().count()).max().unwrap_or(0);
+        let _ = writeln!(out, "type {} struct {{", def.name);
+        for (name, ty, tag) in rows {
+            let _ = writeln!(out, "\t{:name_width$} {:type_width$} {}", name, ty, tag);
+        }
+        out.push_str("}\n");
+    }
+    out
+}
+
+fn java_type(ty: &Ty, boxed: bool) -> String {
+    match ty {
+        Ty::String => "String".to_string(),
+        Ty::Integer => if boxed { "Long" } else { "long" }.to_string(),
+        Ty::Number => if boxed { "Double" } else { "double" }.to_string(),
+        Ty::Boolean => if boxed { "Boolean" } else { "boolean" }.to_string(),
+        Ty::Any | Ty::Union(_) => "Object".to_string(),
+        Ty::Map => "Map<String, Object>".to_string(),
+        Ty::Array(item) => format!("List<{}>", java_type(item, true)),
+        Ty::Struct(name) => name.clone(),
+        Ty::Nullable(inner) => java_type(inner, true),
+    }
+}
+
+fn render_java(defs: &[TypeDef], root: &Ty) -> String {
+    let mut out = String::from(
+        "import com.fasterxml.jackson.annotation.JsonProperty;\nimport java.util.List;\nimport java.util.Map;\n",
+    );
+    if let Ty::Array(_) = root {
+        let _ = write!(out, "\n// 根节点为数组: {}\n", java_type(root, true));
+    }
+    for (i, def) in defs.iter().enumerate() {
+        let visibility = if i == 0 { "public " } else { "" };
+        let _ = write!(out, "\n{}record {}(\n", visibility, def.name);
+        let names = field_names(&def.fields, camel_case, "", java_field_name);
+        let components: Vec<String> = def
+            .fields
+            .iter()
+            .zip(names)
+            .map(|(field, name)| {
+                let annotation = if name != field.key {
+                    format!("@JsonProperty({}) ", serde_json::to_string(&field.key).unwrap_or_default())
+                } else {
+                    String::new()
+                };
+                format!("    {}{} {}", annotation, java_type(&field.ty, field.optional), name)
+            })
+            .collect();
+        out.push_str(&components.join(",\n"));
+        out.push_str("\n) {}\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn generate(language: CodeLanguage) -> String {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "self": {"type": "string"},
+                "type": {"type": "string"},
+                "userId": {"type": "integer"},
+                "user_id": {"type": "integer"},
+                "class": {"type": "boolean"}
+            },
+            "required": ["self", "type", "userId", "user_id", "class"]
+        });
+        generate_code(&schema, language, "root").unwrap()
+    }
+
+    #[test]
+    fn renames_mangled_and_duplicate_rust_fields() {
+        let code = generate(CodeLanguage::Rust);
+        let expected = [
+            "    #[serde(rename = \"self\")]\n    pub self_: String,",
+            "    pub r#type: String,",
+            "    #[serde(rename = \"userId\")]\n    pub user_id: i64,",
+            "    #[serde(rename = \"user_id\")]\n    pub user_id_2: i64,",
+            "    pub class: bool,",
+        ];
+        for line in expected {
+            assert!(code.contains(line), "缺少 {:?}:\n{}", line, code);
+        }
+        assert!(!code.contains("rename = \"type\""), "{}", code);
+    }
+
+    #[test]
+    fn deduplicates_go_and_java_fields() {
+        let go = generate(CodeLanguage::Go);
+        assert!(go.contains("\tUserID  ") && go.contains("`json:\"userId\"`"), "{}", go);
+        assert!(go.contains("\tUserID2 ") && go.contains("`json:\"user_id\"`"), "{}", go);
+
+        let java = generate(CodeLanguage::Java);
+        assert!(java.contains("    long userId,"), "{}", java);
+        assert!(java.contains("    @JsonProperty(\"user_id\") long userId2,"), "{}", java);
+        assert!(java.contains("    @JsonProperty(\"class\") boolean class_"), "{}", java);
+    }
+}
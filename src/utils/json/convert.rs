@@ -0,0 +1,596 @@
+use super::relaxed::push_pointer_segment;
+use super::{CancelToken, CommentMap, FormatOptions, IndentStyle, JsonError, NodeComments, TaskError};
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+
+/// 可互相转换的数据格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl DataFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataFormat::Json => "JSON",
+            DataFormat::Yaml => "YAML",
+            DataFormat::Toml => "TOML",
+        }
+    }
+}
+
+/// 解析YAML文本，多个文档（以 --- 分隔）合并为数组；YAML中的注释不会保留
+pub(super) fn parse_yaml(text: &str, token: &CancelToken) -> Result<Value, TaskError> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(text) {
+        if token.is_cancelled() {
+            return Err(TaskError::Cancelled);
+        }
+        let value = Value::deserialize(document).map_err(|e| {
+            let message = format!("无效的YAML格式: {}", e);
+            TaskError::Failed(match e.location() {
+                Some(location) => JsonError::at_offset(message, text, location.index()),
+                None => JsonError::new(message),
+            })
+        })?;
+        documents.push(value);
+    }
+    Ok(match documents.len() {
+        0 => Value::Null,
+        1 => documents.remove(0),
+        _ => Value::Array(documents),
+    })
+}
+
+/// 解析TOML文本，日期时间转换为字符串；TOML中的注释不会保留
+pub(super) fn parse_toml(text: &str, token: &CancelToken) -> Result<Value, TaskError> {
+    let table: toml::Table = text.parse().map_err(|e: toml::de::Error| {
+        let message = format!("无效的TOML格式: {}", e.message());
+        TaskError::Failed(match e.span() {
+            Some(span) => JsonError::at_offset(message, text, span.start),
+            None => JsonError::new(message),
+        })
+    })?;
+    if token.is_cancelled() {
+        return Err(TaskError::Cancelled);
+    }
+    let mut pointer = String::new();
+    toml_to_json(toml::Value::Table(table), &mut pointer).map_err(|e| TaskError::Failed(JsonError::new(e)))
+}
+
+fn toml_to_json(value: toml::Value, pointer: &mut String) -> Result<Value, String> {
+    Ok(match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::Number(i.into()),
+        toml::Value::Float(f) => Value::Number(
+            Number::from_f64(f).ok_or_else(|| format!("路径 \"{}\" 的值 {} 无法用JSON表示", pointer, f))?,
+        ),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => {
+            let len = pointer.len();
+            let mut out = Vec::with_capacity(items.len());
+            for (i, item) in items.into_iter().enumerate() {
+                push_pointer_segment(pointer, &i.to_string());
+                out.push(toml_to_json(item, pointer)?);
+                pointer.truncate(len);
+            }
+            Value::Array(out)
+        }
+        toml::Value::Table(table) => {
+            let len = pointer.len();
+            let mut out = Map::new();
+            for (key, item) in table {
+                push_pointer_segment(pointer, &key);
+                out.insert(key, toml_to_json(item, pointer)?);
+                pointer.truncate(len);
+            }
+            Value::Object(out)
+        }
+    })
+}
+
+/// 将注释转换为 # 开头的行，块注释按行拆分
+fn comment_lines(comment: &str) -> Vec<String> {
+    if let Some(line) = comment.strip_prefix("//") {
+        return vec![format!("#{}", line.trim_end())];
+    }
+    let body = comment.strip_prefix("/*").and_then(|c| c.strip_suffix("*/")).unwrap_or(comment);
+    body.lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix('*').map_or(line, str::trim_start);
+            if line.is_empty() {
+                "#".to_string()
+            } else {
+                format!("# {}", line)
+            }
+        })
+        .collect()
+}
+
+/// 写出YAML和TOML都支持的双引号字符串，非BMP字符按 \U 转义
+fn write_quoted(out: &mut String, s: &str, ensure_ascii: bool) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c if ensure_ascii && !c.is_ascii() => {
+                if (c as u32) > 0xFFFF {
+                    out.push_str(&format!("\\U{:08X}", c as u32));
+                } else {
+                    out.push_str(&format!("\\u{:04X}", c as u32));
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// 按给定缩进写出注释行
+fn push_comments(out: &mut String, indent: &str, comments: &[String]) {
+    for comment in comments {
+        for line in comment_lines(comment) {
+            out.push_str(indent);
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+}
+
+fn push_trailing(out: &mut String, node: Option<&NodeComments>) {
+    if let Some(node) = node {
+        for comment in &node.trailing {
+            for line in comment_lines(comment) {
+                out.push(' ');
+                out.push_str(&line);
+            }
+        }
+    }
+}
+
+/// 输出YAML（块样式），JSONC中的注释转换为 # 注释
+pub(super) fn write_yaml(
+    value: &Value,
+    comments: &CommentMap,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<String, TaskError> {
+    // YAML不允许用Tab缩进
+    let width = match options.indent {
+        IndentStyle::Spaces(n) if n >= 2 => n,
+        _ => 2,
+    };
+    let mut writer = YamlWriter {
+        out: String::new(),
+        comments,
+        pointer: String::new(),
+        width,
+        ensure_ascii: options.ensure_ascii,
+        token,
+    };
+    let root = comments.get("");
+    if let Some(node) = root {
+        push_comments(&mut writer.out, "", &node.leading);
+    }
+    match value {
+        Value::Object(map) if !map.is_empty() => writer.mapping(map, 0, false)?,
+        Value::Array(items) if !items.is_empty() => writer.sequence(items, 0, false)?,
+        scalar => {
+            writer.scalar(scalar, 0);
+            push_trailing(&mut writer.out, root);
+            writer.out.push('\n');
+        }
+    }
+    Ok(writer.out)
+}
+
+struct YamlWriter<'a> {
+    out: String,
+    comments: &'a CommentMap,
+    pointer: String,
+    width: usize,
+    ensure_ascii: bool,
+    token: &'a CancelToken,
+}
+
+impl YamlWriter<'_> {
+    fn indent(&self, depth: usize) -> String {
+        " ".repeat(depth * self.width)
+    }
+
+    /// 写出映射；inline为true时第一项紧跟在已输出的 "- " 之后
+    fn mapping(&mut self, map: &Map<String, Value>, depth: usize, inline: bool) -> Result<(), TaskError> {
+        if self.token.is_cancelled() {
+            return Err(TaskError::Cancelled);
+        }
+        let indent = self.indent(depth);
+        let len = self.pointer.len();
+        for (i, (key, value)) in map.iter().enumerate() {
+            push_pointer_segment(&mut self.pointer, key);
+            let node = self.comments.get(&self.pointer);
+            if !(inline && i == 0) {
+                push_comments(&mut self.out, &indent, node.map_or(&[], |node| &node.leading));
+                self.out.push_str(&indent);
+            }
+            self.key(key);
+            self.out.push(':');
+            self.value(value, depth, node)?;
+            self.pointer.truncate(len);
+        }
+        self.dangling(&indent);
+        Ok(())
+    }
+
+    fn sequence(&mut self, items: &[Value], depth: usize, inline: bool) -> Result<(), TaskError> {
+        if self.token.is_cancelled() {
+            return Err(TaskError::Cancelled);
+        }
+        let indent = self.indent(depth);
+        let marker = format!("{:<width$}", "-", width = self.width);
+        let len = self.pointer.len();
+        for (i, item) in items.iter().enumerate() {
+            push_pointer_segment(&mut self.pointer, &i.to_string());
+            let node = self.comments.get(&self.pointer);
+            if !(inline && i == 0) {
+                push_comments(&mut self.out, &indent, node.map_or(&[], |node| &node.leading));
+                self.out.push_str(&indent);
+            }
+            // 非空容器紧凑写在 "- " 之后，首个子项带注释时改为另起一行
+            let first_has_comments = match item {
+                Value::Object(map) => map.keys().next().is_some_and(|k| self.child_has_leading(k)),
+                Value::Array(items) => !items.is_empty() && self.child_has_leading("0"),
+                _ => false,
+            };
+            match item {
+                Value::Object(map) if !map.is_empty() && !first_has_comments && node.is_none() => {
+                    self.out.push_str(&marker);
+                    self.mapping(map, depth + 1, true)?;
+                }
+                Value::Array(items) if !items.is_empty() && !first_has_comments && node.is_none() => {
+                    self.out.push_str(&marker);
+                    self.sequence(items, depth + 1, true)?;
+                }
+                _ => {
+                    self.out.push('-');
+                    self.value(item, depth, node)?;
+                }
+            }
+            self.pointer.truncate(len);
+        }
+        self.dangling(&indent);
+        Ok(())
+    }
+
+    fn child_has_leading(&self, segment: &str) -> bool {
+        let mut pointer = self.pointer.clone();
+        push_pointer_segment(&mut pointer, segment);
+        self.comments.get(&pointer).is_some_and(|node| !node.leading.is_empty())
+    }
+
+    /// 写出 "key:" 或 "-" 之后的值，容器换行后缩进一级
+    fn value(&mut self, value: &Value, depth: usize, node: Option<&NodeComments>) -> Result<(), TaskError> {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                push_trailing(&mut self.out, node);
+                self.out.push('\n');
+                self.mapping(map, depth + 1, false)
+            }
+            Value::Array(items) if !items.is_empty() => {
+                push_trailing(&mut self.out, node);
+                self.out.push('\n');
+                self.sequence(items, depth + 1, false)
+            }
+            scalar => {
+                self.out.push(' ');
+                self.scalar(scalar, depth + 1);
+                push_trailing(&mut self.out, node);
+                self.out.push('\n');
+                Ok(())
+            }
+        }
+    }
+
+    fn dangling(&mut self, indent: &str) {
+        if let Some(node) = self.comments.get(&self.pointer) {
+            push_comments(&mut self.out, indent, &node.dangling);
+        }
+    }
+
+    fn key(&mut self, key: &str) {
+        if is_plain_yaml(key) {
+            self.out.push_str(key);
+        } else {
+            write_quoted(&mut self.out, key, self.ensure_ascii);
+        }
+    }
+
+    fn scalar(&mut self, value: &Value, depth: usize) {
+        match value {
+            Value::Null => self.out.push_str("null"),
+            Value::Bool(b) => self.out.push_str(if *b { "true" } else { "false" }),
+            Value::Number(n) => self.out.push_str(&n.to_string()),
+            Value::String(s) if is_plain_yaml(s) => self.out.push_str(s),
+            Value::String(s) if is_block_literal(s) => {
+                // 多行文本使用字面块，按结尾换行数选择截断方式
+                let body = s.trim_end_matches('\n');
+                let chomp = match s.len() - body.len() {
+                    0 => "-",
+                    1 => "",
+                    _ => "+",
+                };
+                self.out.push('|');
+                self.out.push_str(chomp);
+                let indent = self.indent(depth);
+                for line in s.strip_suffix('\n').unwrap_or(s).split('\n') {
+                    self.out.push('\n');
+                    if !line.is_empty() {
+                        self.out.push_str(&indent);
+                        self.out.push_str(line);
+                    }
+                }
+            }
+            Value::String(s) => write_quoted(&mut self.out, s, self.ensure_ascii),
+            Value::Array(_) => self.out.push_str("[]"),
+            Value::Object(_) => self.out.push_str("{}"),
+        }
+    }
+}
+
+/// 判断字符串能否不加引号写出，且不会被解析为其他类型
+fn is_plain_yaml(s: &str) -> bool {
+    const RESERVED: [&str; 11] = ["null", "~", "true", "false", "yes", "no", "on", "off", "y", "n", "<<"];
+    let Some(first) = s.chars().next() else {
+        return false;
+    };
+    let lower = s.to_ascii_lowercase();
+    !RESERVED.contains(&lower.as_str())
+        && !"-?:,[]{}#&*!|>'\"%@`".contains(first)
+        && !first.is_whitespace()
+        && !s.ends_with(char::is_whitespace)
+        && !s.ends_with(':')
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && !s.chars().any(|c| c.is_control() || (!c.is_ascii() && c.is_whitespace()))
+        && !looks_like_number(&lower)
+}
+
+fn looks_like_number(s: &str) -> bool {
+    let unsigned = s.strip_prefix(['+', '-']).unwrap_or(s);
+    unsigned.starts_with(|c: char| c.is_ascii_digit())
+        || unsigned.starts_with(".inf")
+        || unsigned.starts_with(".nan")
+        || (unsigned.starts_with('.') && unsigned[1..].starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// 多行且各行没有首尾空白问题的文本可以写成字面块
+fn is_block_literal(s: &str) -> bool {
+    s.contains('\n')
+        && !s.starts_with([' ', '\n'])
+        && !s.chars().any(|c| c != '\n' && c != '\t' && c.is_control())
+        && s.split('\n').all(|line| !line.ends_with([' ', '\t']))
+}
+
+/// 输出TOML，JSONC中的注释转换为 # 注释；null等TOML无法表示的值会报错
+pub(super) fn write_toml(
+    value: &Value,
+    comments: &CommentMap,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<String, TaskError> {
+    let Value::Object(map) = value else {
+        return Err(TaskError::Failed(JsonError::new(format!(
+            "无法转换为TOML: 顶层必须是对象，实际为{}",
+            kind_name(value)
+        ))));
+    };
+    let mut writer = TomlWriter {
+        out: String::new(),
+        comments,
+        pointer: String::new(),
+        ensure_ascii: options.ensure_ascii,
+        token,
+    };
+    if let Some(node) = comments.get("") {
+        push_comments(&mut writer.out, "", &node.leading);
+    }
+    writer.table(map, &[])?;
+    Ok(writer.out.trim_start_matches('\n').to_string())
+}
+
+struct TomlWriter<'a> {
+    out: String,
+    comments: &'a CommentMap,
+    pointer: String,
+    ensure_ascii: bool,
+    token: &'a CancelToken,
+}
+
+/// 值在TOML中的写法
+enum TomlKind {
+    Inline,
+    Table,
+    ArrayOfTables,
+}
+
+fn toml_kind(value: &Value) -> TomlKind {
+    match value {
+        Value::Object(map) if !map.is_empty() => TomlKind::Table,
+        Value::Array(items) if !items.is_empty() && items.iter().all(|v| v.is_object()) => TomlKind::ArrayOfTables,
+        _ => TomlKind::Inline,
+    }
+}
+
+fn kind_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "布尔值",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "整数",
+        Value::Number(_) => "浮点数",
+        Value::String(_) => "字符串",
+        Value::Array(_) => "数组",
+        Value::Object(_) => "对象",
+    }
+}
+
+fn toml_error(pointer: &str, message: String) -> TaskError {
+    let path = if pointer.is_empty() { "(根节点)" } else { pointer };
+    TaskError::Failed(JsonError::new(format!("无法转换为TOML: 路径 \"{}\" {}", path, message)))
+}
+
+impl TomlWriter<'_> {
+    /// 先写出普通键值，再写出子表和表数组
+    fn table(&mut self, map: &Map<String, Value>, path: &[String]) -> Result<(), TaskError> {
+        if self.token.is_cancelled() {
+            return Err(TaskError::Cancelled);
+        }
+        let len = self.pointer.len();
+        for (key, value) in map {
+            if !matches!(toml_kind(value), TomlKind::Inline) {
+                continue;
+            }
+            push_pointer_segment(&mut self.pointer, key);
+            let node = self.comments.get(&self.pointer);
+            if let Some(node) = node {
+                push_comments(&mut self.out, "", &node.leading);
+            }
+            self.key(key);
+            self.out.push_str(" = ");
+            self.inline(value)?;
+            push_trailing(&mut self.out, node);
+            self.out.push('\n');
+            self.pointer.truncate(len);
+        }
+        if let Some(node) = self.comments.get(&self.pointer) {
+            push_comments(&mut self.out, "", &node.dangling);
+        }
+
+        for (key, value) in map {
+            let mut child_path = path.to_vec();
+            child_path.push(key.clone());
+            push_pointer_segment(&mut self.pointer, key);
+            match (toml_kind(value), value) {
+                (TomlKind::Table, Value::Object(child)) => {
+                    self.out.push('\n');
+                    self.header(&child_path, false);
+                    self.table(child, &child_path)?;
+                }
+                (TomlKind::ArrayOfTables, Value::Array(items)) => {
+                    let item_len = self.pointer.len();
+                    for (i, item) in items.iter().enumerate() {
+                        push_pointer_segment(&mut self.pointer, &i.to_string());
+                        self.out.push('\n');
+                        self.header(&child_path, true);
+                        if let Value::Object(child) = item {
+                            self.table(child, &child_path)?;
+                        }
+                        self.pointer.truncate(item_len);
+                    }
+                }
+                _ => {}
+            }
+            self.pointer.truncate(len);
+        }
+        Ok(())
+    }
+
+    /// 写出 [a.b] 或 [[a.b]] 表头，节点的注释写在表头前后
+    fn header(&mut self, path: &[String], array: bool) {
+        let node = self.comments.get(&self.pointer);
+        if let Some(node) = node {
+            push_comments(&mut self.out, "", &node.leading);
+        }
+        self.out.push_str(if array { "[[" } else { "[" });
+        for (i, key) in path.iter().enumerate() {
+            if i > 0 {
+                self.out.push('.');
+            }
+            self.key(key);
+        }
+        self.out.push_str(if array { "]]" } else { "]" });
+        push_trailing(&mut self.out, node);
+        self.out.push('\n');
+    }
+
+    fn key(&mut self, key: &str) {
+        if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            self.out.push_str(key);
+        } else {
+            write_quoted(&mut self.out, key, self.ensure_ascii);
+        }
+    }
+
+    fn inline(&mut self, value: &Value) -> Result<(), TaskError> {
+        match value {
+            Value::Null => return Err(toml_error(&self.pointer, "的值为null，TOML不支持null".to_string())),
+            Value::Bool(b) => self.out.push_str(if *b { "true" } else { "false" }),
+            Value::Number(n) => {
+                let text = n.to_string();
+                let is_float = text.contains(['.', 'e', 'E']);
+                if !is_float && n.as_i64().is_none() {
+                    return Err(toml_error(&self.pointer, format!("的整数 {} 超出TOML的64位有符号整数范围", text)));
+                }
+                self.out.push_str(&text);
+            }
+            Value::String(s) => write_quoted(&mut self.out, s, self.ensure_ascii),
+            Value::Array(items) => {
+                // TOML 1.0 允许数组混合不同类型的元素
+                self.out.push('[');
+                let len = self.pointer.len();
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    push_pointer_segment(&mut self.pointer, &i.to_string());
+                    self.inline(item)?;
+                    self.pointer.truncate(len);
+                }
+                self.out.push(']');
+            }
+            Value::Object(map) => {
+                if map.is_empty() {
+                    self.out.push_str("{}");
+                    return Ok(());
+                }
+                self.out.push_str("{ ");
+                let len = self.pointer.len();
+                for (i, (key, item)) in map.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    push_pointer_segment(&mut self.pointer, key);
+                    self.key(key);
+                    self.out.push_str(" = ");
+                    self.inline(item)?;
+                    self.pointer.truncate(len);
+                }
+                self.out.push_str(" }");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn writes_mixed_arrays_to_toml() {
+        let value = json!({"a": [1, 2.5], "b": [1, "x", true, [2]]});
+        let toml = write_toml(&value, &CommentMap::default(), &FormatOptions::default(), &CancelToken::new()).ok().unwrap();
+        assert_eq!(toml, "a = [1, 2.5]\nb = [1, \"x\", true, [2]]\n");
+        assert_eq!(parse_toml(&toml, &CancelToken::new()).ok(), Some(value));
+    }
+}
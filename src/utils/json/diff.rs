@@ -0,0 +1,493 @@
+use super::jsonpath::json_equal;
+use super::relaxed::push_pointer_segment;
+use super::{write_json, CancelToken, FormatOptions, JsonError};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// 有序数组按LCS对齐时允许的最大比较次数，超出后按下标逐一比较
+const LCS_MAX_CELLS: usize = 1_000_000;
+/// 差异列表中值预览的最大字符数
+const PREVIEW_MAX_CHARS: usize = 120;
+
+/// 结构化对比选项
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    pub ignore_key_order: bool,
+    pub ignore_array_order: bool,
+    pub ignore_paths: Vec<String>, // 忽略的JSON Pointer，* 匹配任意一层键或下标
+}
+
+impl DiffOptions {
+    /// 从逗号或换行分隔的文本中解析忽略路径
+    pub fn parse_ignore_paths(text: &str) -> Vec<String> {
+        text.split([',', '\n'])
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(|p| if p.starts_with('/') { p.to_string() } else { format!("/{}", p) })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// 一处差异；删除项的路径指向原文档，新增和修改项的路径指向新文档
+#[derive(Debug, Clone)]
+pub struct DiffEntry {
+    pub kind: ChangeKind,
+    pub pointer: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl DiffEntry {
+    pub fn old_preview(&self) -> String {
+        self.old.as_ref().map(preview).unwrap_or_default()
+    }
+
+    pub fn new_preview(&self) -> String {
+        self.new.as_ref().map(preview).unwrap_or_default()
+    }
+}
+
+/// 对比结果：差异列表及等价的RFC 6902 JSON Patch
+#[derive(Debug, Clone)]
+pub struct JsonDiff {
+    pub entries: Vec<DiffEntry>,
+    pub patch: Value,
+}
+
+impl JsonDiff {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 按格式化选项输出JSON Patch文本
+    pub fn patch_text(&self, options: &FormatOptions) -> String {
+        write_json(&self.patch, options, true, &CancelToken::new()).unwrap_or_default()
+    }
+}
+
+/// 对比失败时指明是哪一侧的文档出错
+#[derive(Debug, Clone)]
+pub enum DiffError {
+    Left(JsonError),
+    Right(JsonError),
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffError::Left(e) => write!(f, "原JSON: {}", e),
+            DiffError::Right(e) => write!(f, "新JSON: {}", e),
+        }
+    }
+}
+
+/// 结构化对比两个JSON值，生成差异列表和JSON Patch；任务被取消时返回None
+pub fn diff_values(left: &Value, right: &Value, options: &DiffOptions, token: &CancelToken) -> Option<JsonDiff> {
+    let mut differ = Differ {
+        options,
+        ignore: options
+            .ignore_paths
+            .iter()
+            .map(|p| p.split('/').skip(1).map(str::to_string).collect())
+            .collect(),
+        token,
+        entries: Vec::new(),
+        patch: Vec::new(),
+    };
+    differ.diff(left, right, &mut String::new(), &mut String::new())?;
+    Some(JsonDiff {
+        entries: differ.entries,
+        patch: Value::Array(differ.patch),
+    })
+}
+
+/// 有序数组对齐后的编辑步骤
+enum Step {
+    Keep,
+    Delete(usize),
+    Insert(usize),
+}
+
+struct Differ<'a> {
+    options: &'a DiffOptions,
+    ignore: Vec<Vec<String>>,
+    token: &'a CancelToken,
+    entries: Vec<DiffEntry>,
+    patch: Vec<Value>,
+}
+
+impl Differ<'_> {
+    fn is_ignored(&self, pointer: &str) -> bool {
+        if self.ignore.is_empty() {
+            return false;
+        }
+        let segments: Vec<&str> = pointer.split('/').skip(1).collect();
+        self.ignore.iter().any(|pattern| {
+            pattern.len() == segments.len()
+                && pattern.iter().zip(&segments).all(|(p, s)| p == "*" || p == s)
+        })
+    }
+
+    /// 在忽略选项下判断两个值是否相等，pointer为左侧值的位置
+    fn equal(&self, a: &Value, b: &Value, pointer: &mut String) -> bool {
+        if self.is_ignored(pointer) {
+            return true;
+        }
+        let len = pointer.len();
+        match (a, b) {
+            (Value::Object(x), Value::Object(y)) => {
+                let visible = |map: &serde_json::Map<String, Value>, pointer: &mut String| -> Vec<String> {
+                    map.keys()
+                        .filter(|k| {
+                            push_pointer_segment(pointer, k);
+                            let keep = !self.is_ignored(pointer);
+                            pointer.truncate(len);
+                            keep
+                        })
+                        .cloned()
+                        .collect()
+                };
+                let (xk, yk) = (visible(x, pointer), visible(y, pointer));
+                let same_keys = if self.options.ignore_key_order {
+                    xk.len() == yk.len() && xk.iter().all(|k| y.contains_key(k))
+                } else {
+                    xk == yk
+                };
+                same_keys
+                    && xk.iter().all(|k| {
+                        push_pointer_segment(pointer, k);
+                        let eq = self.equal(&x[k.as_str()], &y[k.as_str()], pointer);
+                        pointer.truncate(len);
+                        eq
+                    })
+            }
+            (Value::Array(x), Value::Array(y)) if x.len() == y.len() => {
+                if self.options.ignore_array_order {
+                    self.match_unordered(x, y, pointer).0.iter().all(Option::is_some)
+                } else {
+                    x.iter().zip(y).enumerate().all(|(i, (p, q))| {
+                        push_pointer_segment(pointer, &i.to_string());
+                        let eq = self.equal(p, q, pointer);
+                        pointer.truncate(len);
+                        eq
+                    })
+                }
+            }
+            (Value::Array(_), Value::Array(_)) => false,
+            _ => json_equal(a, b),
+        }
+    }
+
+    /// 无序匹配数组元素，返回左侧每个元素匹配到的右侧下标，以及右侧各元素是否已被匹配
+    fn match_unordered(&self, x: &[Value], y: &[Value], pointer: &mut String) -> (Vec<Option<usize>>, Vec<bool>) {
+        let len = pointer.len();
+        let mut used = vec![false; y.len()];
+        let matches = x
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                // 两两比较的代价是 O(n²)，比较每一对之前都检查是否已取消，由调用方丢弃结果
+                push_pointer_segment(pointer, &i.to_string());
+                let found = (0..y.len())
+                    .take_while(|_| !self.token.is_cancelled())
+                    .find(|&j| !used[j] && self.equal(p, &y[j], pointer));
+                pointer.truncate(len);
+                if let Some(j) = found {
+                    used[j] = true;
+                }
+                found
+            })
+            .collect();
+        (matches, used)
+    }
+
+    fn record(&mut self, kind: ChangeKind, pointer: &str, patch_path: &str, old: Option<&Value>, new: Option<&Value>) {
+        self.entries.push(DiffEntry {
+            kind,
+            pointer: pointer.to_string(),
+            old: old.cloned(),
+            new: new.cloned(),
+        });
+        self.patch.push(match kind {
+            ChangeKind::Added => json!({ "op": "add", "path": patch_path, "value": new }),
+            ChangeKind::Removed => json!({ "op": "remove", "path": patch_path }),
+            ChangeKind::Changed => json!({ "op": "replace", "path": patch_path, "value": new }),
+        });
+    }
+
+    /// 递归对比；left_ptr为原文档中的位置，right_ptr为新文档中的位置（同时也是补丁路径）
+    fn diff(&mut self, left: &Value, right: &Value, left_ptr: &mut String, right_ptr: &mut String) -> Option<()> {
+        if self.token.is_cancelled() {
+            return None;
+        }
+        if self.is_ignored(left_ptr) {
+            return Some(());
+        }
+        match (left, right) {
+            (Value::Object(x), Value::Object(y)) => {
+                if !self.options.ignore_key_order && !self.same_key_order(x, y, left_ptr) {
+                    // JSON Patch无法表达键的重新排序，整体替换该对象
+                    let r = right_ptr.clone();
+                    self.record(ChangeKind::Changed, &r, &r, Some(left), Some(right));
+                    return Some(());
+                }
+                let (llen, rlen) = (left_ptr.len(), right_ptr.len());
+                for (k, lv) in x {
+                    push_pointer_segment(left_ptr, k);
+                    push_pointer_segment(right_ptr, k);
+                    if !self.is_ignored(left_ptr) {
+                        match y.get(k) {
+                            Some(rv) => self.diff(lv, rv, left_ptr, right_ptr)?,
+                            None => {
+                                let (l, r) = (left_ptr.clone(), right_ptr.clone());
+                                self.record(ChangeKind::Removed, &l, &r, Some(lv), None);
+                            }
+                        }
+                    }
+                    left_ptr.truncate(llen);
+                    right_ptr.truncate(rlen);
+                }
+                for (k, rv) in y {
+                    if x.contains_key(k) {
+                        continue;
+                    }
+                    push_pointer_segment(left_ptr, k);
+                    push_pointer_segment(right_ptr, k);
+                    if !self.is_ignored(left_ptr) {
+                        let r = right_ptr.clone();
+                        self.record(ChangeKind::Added, &r, &r, None, Some(rv));
+                    }
+                    left_ptr.truncate(llen);
+                    right_ptr.truncate(rlen);
+                }
+                Some(())
+            }
+            (Value::Array(x), Value::Array(y)) => {
+                if self.options.ignore_array_order {
+                    self.diff_unordered(x, y, left_ptr, right_ptr)
+                } else {
+                    self.diff_ordered(x, y, left_ptr, right_ptr)
+                }
+            }
+            _ => {
+                if !self.equal(left, right, left_ptr) {
+                    let r = right_ptr.clone();
+                    self.record(ChangeKind::Changed, &r, &r, Some(left), Some(right));
+                }
+                Some(())
+            }
+        }
+    }
+
+    /// 两侧共有键的相对顺序是否一致
+    fn same_key_order(&self, x: &serde_json::Map<String, Value>, y: &serde_json::Map<String, Value>, pointer: &mut String) -> bool {
+        let len = pointer.len();
+        let mut common = |a: &serde_json::Map<String, Value>, b: &serde_json::Map<String, Value>| -> Vec<String> {
+            a.keys()
+                .filter(|k| {
+                    push_pointer_segment(pointer, k);
+                    let keep = b.contains_key(k.as_str()) && !self.is_ignored(pointer);
+                    pointer.truncate(len);
+                    keep
+                })
+                .cloned()
+                .collect()
+        };
+        common(x, y) == common(y, x)
+    }
+
+    /// 忽略顺序对比数组：未匹配的元素按删除和追加处理
+    fn diff_unordered(&mut self, x: &[Value], y: &[Value], left_ptr: &mut String, right_ptr: &mut String) -> Option<()> {
+        let (matches, used) = self.match_unordered(x, y, left_ptr);
+        if self.token.is_cancelled() {
+            return None;
+        }
+        let (llen, rlen) = (left_ptr.len(), right_ptr.len());
+        // 从后往前删除，保证补丁中的下标有效
+        for i in (0..x.len()).rev().filter(|&i| matches[i].is_none()) {
+            push_pointer_segment(left_ptr, &i.to_string());
+            push_pointer_segment(right_ptr, &i.to_string());
+            let (l, r) = (left_ptr.clone(), right_ptr.clone());
+            self.record(ChangeKind::Removed, &l, &r, Some(&x[i]), None);
+            left_ptr.truncate(llen);
+            right_ptr.truncate(rlen);
+        }
+        for j in (0..y.len()).filter(|&j| !used[j]) {
+            push_pointer_segment(right_ptr, &j.to_string());
+            let shown = right_ptr.clone();
+            right_ptr.truncate(rlen);
+            push_pointer_segment(right_ptr, "-");
+            let r = right_ptr.clone();
+            right_ptr.truncate(rlen);
+            self.record(ChangeKind::Added, &shown, &r, None, Some(&y[j]));
+        }
+        Some(())
+    }
+
+    /// 按顺序对比数组：用LCS对齐相同元素，相邻的删除和插入按位置配对后递归对比
+    fn diff_ordered(&mut self, x: &[Value], y: &[Value], left_ptr: &mut String, right_ptr: &mut String) -> Option<()> {
+        let steps = self.align(x, y, left_ptr)?;
+        let (llen, rlen) = (left_ptr.len(), right_ptr.len());
+        let mut deleted = Vec::new();
+        let mut inserted = Vec::new();
+        let mut cur = 0; // 补丁应用过程中当前元素在数组中的位置
+        for step in steps.into_iter().map(Some).chain(std::iter::once(None)) {
+            match step {
+                Some(Step::Delete(i)) => deleted.push(i),
+                Some(Step::Insert(j)) => inserted.push(j),
+                keep => {
+                    let paired = deleted.len().min(inserted.len());
+                    for (&i, &j) in deleted.iter().zip(&inserted) {
+                        push_pointer_segment(left_ptr, &i.to_string());
+                        push_pointer_segment(right_ptr, &cur.to_string());
+                        self.diff(&x[i], &y[j], left_ptr, right_ptr)?;
+                        left_ptr.truncate(llen);
+                        right_ptr.truncate(rlen);
+                        cur += 1;
+                    }
+                    for &i in &deleted[paired..] {
+                        push_pointer_segment(left_ptr, &i.to_string());
+                        push_pointer_segment(right_ptr, &cur.to_string());
+                        let (l, r) = (left_ptr.clone(), right_ptr.clone());
+                        self.record(ChangeKind::Removed, &l, &r, Some(&x[i]), None);
+                        left_ptr.truncate(llen);
+                        right_ptr.truncate(rlen);
+                    }
+                    for &j in &inserted[paired..] {
+                        push_pointer_segment(right_ptr, &cur.to_string());
+                        let r = right_ptr.clone();
+                        self.record(ChangeKind::Added, &r, &r, None, Some(&y[j]));
+                        right_ptr.truncate(rlen);
+                        cur += 1;
+                    }
+                    deleted.clear();
+                    inserted.clear();
+                    if keep.is_some() {
+                        cur += 1;
+                    }
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// 计算两个数组的编辑步骤，过大的数组退化为按下标对齐
+    fn align(&self, x: &[Value], y: &[Value], pointer: &mut String) -> Option<Vec<Step>> {
+        let len = pointer.len();
+        let mut eq = |i: usize, j: usize| {
+            push_pointer_segment(pointer, &i.to_string());
+            let result = self.equal(&x[i], &y[j], pointer);
+            pointer.truncate(len);
+            result
+        };
+
+        // 先去掉相同的首尾，缩小LCS规模
+        let mut prefix = 0;
+        while prefix < x.len() && prefix < y.len() && eq(prefix, prefix) {
+            prefix += 1;
+        }
+        let mut suffix = 0;
+        while suffix < x.len() - prefix && suffix < y.len() - prefix && eq(x.len() - 1 - suffix, y.len() - 1 - suffix) {
+            suffix += 1;
+        }
+        let (n, m) = (x.len() - prefix - suffix, y.len() - prefix - suffix);
+
+        let mut steps: Vec<Step> = (0..prefix).map(|_| Step::Keep).collect();
+        if n * m > LCS_MAX_CELLS {
+            for k in 0..n.max(m) {
+                match (k < n, k < m) {
+                    (true, true) => {
+                        steps.push(Step::Delete(prefix + k));
+                        steps.push(Step::Insert(prefix + k));
+                    }
+                    (true, false) => steps.push(Step::Delete(prefix + k)),
+                    _ => steps.push(Step::Insert(prefix + k)),
+                }
+            }
+        } else {
+            // lcs[i][j] 为 x[i..] 与 y[j..] 的最长公共子序列长度
+            let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+            for i in (0..n).rev() {
+                if self.token.is_cancelled() {
+                    return None;
+                }
+                for j in (0..m).rev() {
+                    lcs[i][j] = if eq(prefix + i, prefix + j) {
+                        lcs[i + 1][j + 1] + 1
+                    } else {
+                        lcs[i + 1][j].max(lcs[i][j + 1])
+                    };
+                }
+            }
+            let (mut i, mut j) = (0, 0);
+            while i < n || j < m {
+                if i < n && j < m && lcs[i][j] == lcs[i + 1][j + 1] + 1 && eq(prefix + i, prefix + j) {
+                    steps.push(Step::Keep);
+                    i += 1;
+                    j += 1;
+                } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+                    steps.push(Step::Insert(prefix + j));
+                    j += 1;
+                } else {
+                    steps.push(Step::Delete(prefix + i));
+                    i += 1;
+                }
+            }
+        }
+        steps.extend((0..suffix).map(|_| Step::Keep));
+        Some(steps)
+    }
+}
+
+/// 生成值的单行预览，过长时截断
+fn preview(value: &Value) -> String {
+    let text = write_json(value, &FormatOptions::default(), false, &CancelToken::new()).unwrap_or_default();
+    if text.chars().count() > PREVIEW_MAX_CHARS {
+        let truncated: String = text.chars().take(PREVIEW_MAX_CHARS).collect();
+        format!("{}...", truncated)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn unordered_arrays_pair_equal_elements() {
+        let left = json!([{"a": 1}, [1, 2], 3]);
+        let right = json!([3, [2, 1], {"a": 1}, 4]);
+        let options = DiffOptions { ignore_array_order: true, ..Default::default() };
+        let diff = diff_values(&left, &right, &options, &CancelToken::new()).unwrap();
+        assert_eq!(diff.patch, json!([{"op": "add", "path": "/-", "value": 4}]));
+    }
+
+    #[test]
+    fn unordered_matching_stops_when_cancelled() {
+        let left = Value::Array((0..30_000).map(Value::from).collect());
+        let right = Value::Array((30_000..60_000).map(Value::from).collect());
+        let options = DiffOptions { ignore_array_order: true, ..Default::default() };
+        let token = CancelToken::new();
+        let canceller = {
+            let token = token.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                token.cancel();
+            })
+        };
+        let start = Instant::now();
+        assert!(diff_values(&left, &right, &options, &token).is_none());
+        assert!(start.elapsed() < Duration::from_secs(5), "取消后仍在比较: {:?}", start.elapsed());
+        canceller.join().unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+use serde_json::error::Category;
+use std::fmt;
+
+// 错误片段中单行最多显示的字符数
+const EXCERPT_WIDTH: usize = 80;
+
+/// JSON处理错误，解析错误附带出错位置
+#[derive(Debug, Clone)]
+pub struct JsonError {
+    /// 面向用户的错误说明
+    pub message: String,
+    /// 出错位置，非解析类错误为None
+    pub location: Option<ErrorLocation>,
+}
+
+/// 解析错误在原文中的位置
+#[derive(Debug, Clone)]
+pub struct ErrorLocation {
+    /// 行号，从1开始
+    pub line: usize,
+    /// 列号（按字符计），从1开始
+    pub column: usize,
+    /// 出错字符在原文中的字节偏移
+    pub offset: usize,
+    /// 出错字符的字节长度，位于文本末尾时为0
+    pub len: usize,
+    /// 出错行的上下文片段，下一行用 ^ 指示出错列
+    pub excerpt: String,
+}
+
+impl JsonError {
+    /// 创建不带位置信息的错误
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    /// 根据serde_json的错误和原文构造带位置的错误
+    pub fn from_serde(err: &serde_json::Error, source: &str) -> Self {
+        if err.line() == 0 {
+            return Self::new(format!("{}: {}", super::ERR_INVALID_JSON, err));
+        }
+
+        let message = format!("{}: {}", super::ERR_INVALID_JSON, explain(err));
+        if err.classify() == Category::Eof {
+            return Self::at_offset(message, source, source.len());
+        }
+
+        // serde报告的是读到出错字符之后的列号（按字节计），列号为0表示出错字符是上一行的换行符
+        let line_start = source
+            .split_inclusive('\n')
+            .take(err.line() - 1)
+            .map(str::len)
+            .sum::<usize>();
+        let offset = (line_start + err.column()).saturating_sub(1);
+        Self::at_offset(message, source, offset)
+    }
+
+    /// 在原文指定字节偏移处构造带位置的错误
+    pub fn at_offset(message: impl Into<String>, source: &str, offset: usize) -> Self {
+        Self {
+            message: message.into(),
+            location: Some(ErrorLocation::new(source, offset)),
+        }
+    }
+}
+
+impl ErrorLocation {
+    fn new(source: &str, offset: usize) -> Self {
+        let offset = floor_char_boundary(source, offset.min(source.len()));
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line = source[..line_start].matches('\n').count() + 1;
+        let line_text = source[line_start..].split('\n').next().unwrap_or("");
+        let line_text = line_text.strip_suffix('\r').unwrap_or(line_text);
+
+        let column = source[line_start..offset].chars().count() + 1;
+        let len = source[offset..].chars().next().map_or(0, char::len_utf8);
+
+        Self {
+            line,
+            column,
+            offset,
+            len,
+            excerpt: build_excerpt(line, line_text, column),
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(loc) = &self.location {
+            write!(
+                f,
+                "\n位置: 第{}行第{}列（字节偏移 {}）\n\n{}",
+                loc.line, loc.column, loc.offset, loc.excerpt
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// 生成带行号和 ^ 指示符的错误片段，过长的行只截取出错列附近的内容
+fn build_excerpt(line: usize, line_text: &str, column: usize) -> String {
+    let chars: Vec<char> = line_text.chars().collect();
+    let start = column.saturating_sub(EXCERPT_WIDTH / 2 + 1).min(chars.len());
+    let end = (start + EXCERPT_WIDTH).min(chars.len());
+
+    let mut snippet: String = chars[start..end]
+        .iter()
+        .map(|&c| if c == '\t' { ' ' } else { c })
+        .collect();
+    let mut caret_pos = column - 1 - start;
+    if start > 0 {
+        snippet.insert_str(0, "...");
+        caret_pos += 3;
+    }
+    if end < chars.len() {
+        snippet.push_str("...");
+    }
+
+    let gutter = format!("{} | ", line);
+    format!(
+        "{}{}\n{}^",
+        gutter,
+        snippet,
+        " ".repeat(gutter.chars().count() + caret_pos)
+    )
+}
+
+/// 根据serde_json的错误给出中文说明
+fn explain(err: &serde_json::Error) -> String {
+    let raw = err.to_string();
+    let detail = raw.split(" at line ").next().unwrap_or(&raw);
+
+    let hint = match err.classify() {
+        Category::Eof => "内容不完整，可能缺少右括号、右引号或值",
+        _ if detail.contains("trailing comma") => "最后一个元素后面有多余的逗号",
+        _ if detail.contains("key must be a string") => "对象的键必须是双引号包裹的字符串",
+        _ if detail.contains("expected `,` or `}`") => "缺少逗号或右花括号 }",
+        _ if detail.contains("expected `,` or `]`") => "缺少逗号或右方括号 ]",
+        _ if detail.contains("expected `:`") => "键后面缺少冒号 :",
+        _ if detail.contains("control character") => "字符串中包含未转义的控制字符（如换行或制表符）",
+        _ if detail.contains("escape") => "字符串中包含无效的转义序列",
+        _ if detail.contains("trailing characters") => "JSON已结束，但后面还有多余内容",
+        _ if detail.contains("expected ident") => "无法识别的字面量，应为 true、false 或 null",
+        _ if detail.contains("expected value") => "此处需要一个合法的值（对象、数组、字符串、数字、true、false或null）",
+        _ if detail.contains("number") => "数字格式无效",
+        _ => "",
+    };
+
+    if hint.is_empty() {
+        detail.to_string()
+    } else {
+        format!("{}（{}）", hint, detail)
+    }
+}
+
+fn floor_char_boundary(s: &str, mut index: usize) -> usize {
+    while index > 0 && !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
@@ -0,0 +1,585 @@
+use super::shape::{Shape as Observed, StringShape};
+use super::{write_json, CancelToken, FormatOptions};
+use chrono::DateTime;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{Map, Number, Value};
+
+/// 单次最多生成的文档数
+pub const FAKE_MAX_COUNT: usize = 100_000;
+/// 生成时的最大嵌套深度，防止递归Schema无限展开
+const MAX_DEPTH: usize = 16;
+/// Schema中minItems/maxItems的上限，防止生成超大数组
+const MAX_ARRAY_LEN: usize = 100;
+/// Schema中minLength/maxLength的上限，防止生成超长字符串
+const MAX_STRING_LEN: usize = 1000;
+
+const FIRST_NAMES: [&str; 16] = [
+    "James", "Mary", "John", "Linda", "Robert", "Emma", "Michael", "Olivia", "David", "Sophia", "Daniel", "Grace",
+    "Thomas", "Chloe", "Lucas", "Alice",
+];
+const LAST_NAMES: [&str; 16] = [
+    "Smith", "Johnson", "Brown", "Taylor", "Miller", "Wilson", "Moore", "Clark", "Lewis", "Walker", "Hall", "Young",
+    "King", "Wright", "Scott", "Green",
+];
+const CJK_SURNAMES: [&str; 12] = ["王", "李", "张", "刘", "陈", "杨", "赵", "黄", "周", "吴", "徐", "孙"];
+const CJK_GIVEN_NAMES: [&str; 12] = ["伟", "芳", "娜", "敏", "静", "磊", "洋", "勇", "艳", "杰", "涛", "婷"];
+const WORDS: [&str; 24] = [
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do", "eiusmod", "tempor",
+    "incididunt", "ut", "labore", "et", "dolore", "magna", "aliqua", "enim", "minim", "veniam", "quis", "nostrud",
+];
+const DOMAINS: [&str; 5] = ["example.com", "example.org", "mail.test", "demo.net", "sample.io"];
+
+/// 假数据生成选项
+#[derive(Debug, Clone)]
+pub struct FakeOptions {
+    pub count: usize,
+    pub seed: u64,
+    /// 输出NDJSON，否则输出JSON数组
+    pub ndjson: bool,
+    /// 日期时间字符串的输出格式（strftime）
+    pub time_format: String,
+}
+
+/// 字符串的语义类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextKind {
+    Email,
+    Uuid,
+    DateTime,
+    Date,
+    Url,
+    Ipv4,
+    FullName,
+    FirstName,
+    LastName,
+    Username,
+    Phone,
+    Words,
+}
+
+#[derive(Debug, Clone)]
+struct TextShape {
+    kind: TextKind,
+    min_len: usize,
+    max_len: usize,
+    /// 日期时间的取值范围（秒）
+    time_range: Option<(i64, i64)>,
+    cjk: bool,
+}
+
+/// 同一位置上值的形状，来自样本或Schema
+#[derive(Debug, Clone)]
+enum Shape {
+    Null,
+    Bool,
+    Integer { min: i64, max: i64 },
+    Number { min: f64, max: f64, decimals: usize },
+    Text(TextShape),
+    Array { item: Option<Box<Shape>>, min_len: usize, max_len: usize },
+    Object { fields: Vec<Field> },
+    Choice(Vec<Value>),
+    Any(Vec<Shape>),
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    name: String,
+    shape: Shape,
+    /// 出现概率
+    presence: f64,
+}
+
+/// 输入是否为JSON Schema：带$schema，或顶层type配合properties/items
+fn looks_like_schema(value: &Value) -> bool {
+    let Value::Object(map) = value else {
+        return false;
+    };
+    map.contains_key("$schema")
+        || (map.get("type").is_some_and(|t| t.is_string() || t.is_array())
+            && (map.contains_key("properties") || map.contains_key("items")))
+}
+
+/// 按样本或Schema生成假数据；样本为数组时以其元素为样本。任务被取消时返回Ok(None)
+pub fn generate_fake(
+    input: &Value,
+    fake: &FakeOptions,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<Option<String>, String> {
+    if fake.count == 0 || fake.count > FAKE_MAX_COUNT {
+        return Err(format!("生成数量须在1到{}之间", FAKE_MAX_COUNT));
+    }
+    let shape = if looks_like_schema(input) {
+        SchemaReader { root: input }.read(input, None, 0)
+    } else {
+        let samples: &[Value] = match input {
+            Value::Array(items) if !items.is_empty() => items,
+            other => std::slice::from_ref(other),
+        };
+        let mut observed = Observed::default();
+        for sample in samples {
+            if token.is_cancelled() {
+                return Ok(None);
+            }
+            observed.add(sample);
+        }
+        from_observed(&observed, None)
+    };
+
+    let mut generator = Generator {
+        rng: StdRng::seed_from_u64(fake.seed),
+        time_format: &fake.time_format,
+    };
+    let line_options = FormatOptions {
+        trailing_newline: false,
+        ..options.clone()
+    };
+    let mut documents = Vec::with_capacity(fake.count);
+    for _ in 0..fake.count {
+        if token.is_cancelled() {
+            return Ok(None);
+        }
+        documents.push(generator.value(&shape, 0));
+    }
+
+    let mut output = if fake.ndjson {
+        let mut lines = Vec::with_capacity(documents.len());
+        for document in &documents {
+            let Some(line) = write_json(document, &line_options, false, token) else {
+                return Ok(None);
+            };
+            lines.push(line);
+        }
+        lines.join("\n")
+    } else {
+        match write_json(&Value::Array(documents), &line_options, true, token) {
+            Some(text) => text,
+            None => return Ok(None),
+        }
+    };
+    if options.trailing_newline {
+        output.push('\n');
+    }
+    Ok(Some(output))
+}
+
+// ---------- 从样本统计归纳形状 ----------
+
+/// 将样本统计转换为生成用的形状，同一位置出现多种类型时随机选择其一
+fn from_observed(observed: &Observed, key: Option<&str>) -> Shape {
+    let mut shapes = Vec::new();
+    if let Some(object) = &observed.objects {
+        let fields = object
+            .keys
+            .iter()
+            .map(|name| {
+                let (count, child) = &object.props[name];
+                Field {
+                    name: name.clone(),
+                    shape: from_observed(child, Some(name)),
+                    presence: *count as f64 / object.count as f64,
+                }
+            })
+            .collect();
+        shapes.push(Shape::Object { fields });
+    }
+    if let Some(array) = &observed.arrays {
+        shapes.push(Shape::Array {
+            item: array.has_items.then(|| Box::new(from_observed(&array.items, key))),
+            min_len: array.min_len,
+            max_len: array.max_len,
+        });
+    }
+    if let Some(strings) = &observed.strings {
+        shapes.push(text_from_observed(strings, key));
+    }
+    if let Some((min, max)) = observed.range {
+        shapes.push(if observed.numbers > 0 {
+            Shape::Number { min, max, decimals: observed.decimals }
+        } else {
+            Shape::Integer { min: min as i64, max: max as i64 }
+        });
+    }
+    if observed.booleans > 0 {
+        shapes.push(Shape::Bool);
+    }
+    if observed.nulls > 0 {
+        shapes.push(Shape::Null);
+    }
+    match shapes.len() {
+        0 => Shape::Null,
+        1 => shapes.swap_remove(0),
+        _ => Shape::Any(shapes),
+    }
+}
+
+fn text_from_observed(strings: &StringShape, key: Option<&str>) -> Shape {
+    let has = |format: &str| strings.formats.contains(&format);
+    let kind = if has("uuid") {
+        TextKind::Uuid
+    } else if has("email") {
+        TextKind::Email
+    } else if strings.times.is_some() {
+        if has("date") { TextKind::Date } else { TextKind::DateTime }
+    } else if has("uri") {
+        TextKind::Url
+    } else if has("ipv4") {
+        TextKind::Ipv4
+    } else {
+        kind_from_key(key)
+    };
+    // 普通文本取值种类少且有重复时，从样本取值中随机选择
+    if kind == TextKind::Words && strings.is_enum() {
+        return Shape::Choice(strings.order.iter().cloned().map(Value::String).collect());
+    }
+    Shape::Text(TextShape {
+        kind,
+        min_len: strings.min_len,
+        max_len: strings.max_len,
+        time_range: strings.times,
+        cjk: strings.cjk,
+    })
+}
+
+/// 按键名识别字符串的语义类型
+fn kind_from_key(key: Option<&str>) -> TextKind {
+    let key = key.unwrap_or_default().to_ascii_lowercase().replace(['-', ' '], "_");
+    match key.as_str() {
+        "first_name" | "firstname" | "given_name" => TextKind::FirstName,
+        "last_name" | "lastname" | "surname" | "family_name" => TextKind::LastName,
+        "name" | "full_name" | "fullname" | "author" | "nickname" => TextKind::FullName,
+        "username" | "user_name" | "login" | "account" => TextKind::Username,
+        k if k.contains("phone") || k.contains("mobile") || k == "tel" => TextKind::Phone,
+        k if k.contains("email") => TextKind::Email,
+        _ => TextKind::Words,
+    }
+}
+
+// ---------- 从Schema读取形状 ----------
+
+struct SchemaReader<'a> {
+    root: &'a Value,
+}
+
+impl SchemaReader<'_> {
+    fn read(&self, schema: &Value, key: Option<&str>, depth: usize) -> Shape {
+        let Value::Object(map) = schema else {
+            // true或空Schema：生成普通文本
+            return text_shape(TextKind::Words, key, 3, 12);
+        };
+        if depth > MAX_DEPTH {
+            return Shape::Null;
+        }
+        if let Some(target) = map.get("$ref").and_then(Value::as_str).and_then(|r| self.resolve(r)) {
+            return self.read(target, key, depth + 1);
+        }
+        if let Some(Value::Array(values)) = map.get("enum") {
+            return Shape::Choice(values.clone());
+        }
+        if let Some(value) = map.get("const") {
+            return Shape::Choice(vec![value.clone()]);
+        }
+        for combinator in ["oneOf", "anyOf"] {
+            if let Some(Value::Array(options)) = map.get(combinator) {
+                return Shape::Any(options.iter().map(|s| self.read(s, key, depth + 1)).collect());
+            }
+        }
+        if let Some(Value::Array(parts)) = map.get("allOf") {
+            if let Some(first) = parts.first() {
+                return self.read(first, key, depth + 1);
+            }
+        }
+
+        match map.get("type") {
+            Some(Value::Array(types)) => Shape::Any(
+                types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|t| self.read_typed(map, t, key, depth))
+                    .collect(),
+            ),
+            Some(Value::String(t)) => self.read_typed(map, t, key, depth),
+            _ if map.contains_key("properties") => self.read_typed(map, "object", key, depth),
+            _ if map.contains_key("items") => self.read_typed(map, "array", key, depth),
+            _ => text_shape(TextKind::Words, key, 3, 12),
+        }
+    }
+
+    fn read_typed(&self, map: &Map<String, Value>, kind: &str, key: Option<&str>, depth: usize) -> Shape {
+        let number = |name: &str| map.get(name).and_then(Value::as_f64);
+        let size = |name: &str, limit: usize| map.get(name).and_then(Value::as_u64).map(|n| n.min(limit as u64) as usize);
+        match kind {
+            "null" => Shape::Null,
+            "boolean" => Shape::Bool,
+            "integer" => {
+                let min = number("minimum").or(number("exclusiveMinimum").map(|v| v + 1.0)).unwrap_or(0.0);
+                let max = number("maximum").or(number("exclusiveMaximum").map(|v| v - 1.0)).unwrap_or(min.max(0.0) + 1000.0);
+                Shape::Integer { min: min as i64, max: (max as i64).max(min as i64) }
+            }
+            "number" => {
+                let min = number("minimum").or(number("exclusiveMinimum")).unwrap_or(0.0);
+                let max = number("maximum").or(number("exclusiveMaximum")).unwrap_or(min.max(0.0) + 1000.0);
+                Shape::Number { min, max: max.max(min), decimals: 2 }
+            }
+            "string" => {
+                let kind = match map.get("format").and_then(Value::as_str) {
+                    Some("email" | "idn-email") => TextKind::Email,
+                    Some("uuid") => TextKind::Uuid,
+                    Some("date-time") => TextKind::DateTime,
+                    Some("date") => TextKind::Date,
+                    Some("uri" | "url" | "iri") => TextKind::Url,
+                    Some("ipv4") => TextKind::Ipv4,
+                    _ => kind_from_key(key),
+                };
+                let min_len = size("minLength", MAX_STRING_LEN).unwrap_or(3);
+                let max_len = size("maxLength", MAX_STRING_LEN).unwrap_or(min_len.max(12)).max(min_len);
+                text_shape(kind, key, min_len, max_len)
+            }
+            "array" => {
+                let min_len = size("minItems", MAX_ARRAY_LEN).unwrap_or(1);
+                let max_len = size("maxItems", MAX_ARRAY_LEN).unwrap_or(min_len.max(5)).max(min_len);
+                let item = map.get("items").map(|items| Box::new(self.read(items, key, depth + 1)));
+                Shape::Array { item, min_len, max_len }
+            }
+            _ => {
+                let required: Vec<&str> = map
+                    .get("required")
+                    .and_then(Value::as_array)
+                    .map(|names| names.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+                let fields = map
+                    .get("properties")
+                    .and_then(Value::as_object)
+                    .map(|properties| {
+                        properties
+                            .iter()
+                            .map(|(name, schema)| Field {
+                                name: name.clone(),
+                                shape: self.read(schema, Some(name), depth + 1),
+                                // 非必需的属性以一定概率缺省
+                                presence: if required.contains(&name.as_str()) { 1.0 } else { 0.8 },
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Shape::Object { fields }
+            }
+        }
+    }
+
+    /// 解析文档内引用，如 #/$defs/user
+    fn resolve(&self, reference: &str) -> Option<&Value> {
+        let pointer = reference.strip_prefix('#')?;
+        self.root.pointer(pointer)
+    }
+}
+
+fn text_shape(kind: TextKind, key: Option<&str>, min_len: usize, max_len: usize) -> Shape {
+    // Schema未指定格式时仍按键名识别
+    let kind = if kind == TextKind::Words { kind_from_key(key) } else { kind };
+    Shape::Text(TextShape {
+        kind,
+        min_len,
+        max_len,
+        time_range: None,
+        cjk: false,
+    })
+}
+
+// ---------- 生成 ----------
+
+struct Generator<'a> {
+    rng: StdRng,
+    time_format: &'a str,
+}
+
+impl Generator<'_> {
+    fn value(&mut self, shape: &Shape, depth: usize) -> Value {
+        if depth > MAX_DEPTH {
+            return Value::Null;
+        }
+        match shape {
+            Shape::Null => Value::Null,
+            Shape::Bool => Value::Bool(self.rng.gen_bool(0.5)),
+            Shape::Integer { min, max } => {
+                let (min, max) = widen_integer(*min, *max);
+                Value::from(self.rng.gen_range(min..=max))
+            }
+            Shape::Number { min, max, decimals } => {
+                let (min, max) = widen_number(*min, *max);
+                let v = if min < max && (max - min).is_finite() {
+                    self.rng.gen_range(min..=max)
+                } else if min < max {
+                    // 跨度超出f64范围时gen_range会panic，改为按比例插值
+                    let t: f64 = self.rng.gen();
+                    min * (1.0 - t) + max * t
+                } else {
+                    min
+                };
+                let scale = 10f64.powi(*decimals as i32);
+                let rounded = (v * scale).round() / scale;
+                Number::from_f64(if rounded.is_finite() { rounded } else { v }).map_or(Value::Null, Value::Number)
+            }
+            Shape::Text(text) => Value::String(self.text(text)),
+            Shape::Choice(values) => values.get(self.rng.gen_range(0..values.len().max(1))).cloned().unwrap_or(Value::Null),
+            Shape::Any(shapes) if shapes.is_empty() => Value::Null,
+            Shape::Any(shapes) => {
+                let shape = &shapes[self.rng.gen_range(0..shapes.len())];
+                self.value(shape, depth)
+            }
+            Shape::Array { item, min_len, max_len } => {
+                let Some(item) = item else {
+                    return Value::Array(Vec::new());
+                };
+                let min_len = (*min_len).min(*max_len);
+                let len = self.rng.gen_range(min_len..=*max_len);
+                Value::Array((0..len).map(|_| self.value(item, depth + 1)).collect())
+            }
+            Shape::Object { fields, .. } => {
+                let mut map = Map::new();
+                for field in fields {
+                    if field.presence >= 1.0 || self.rng.gen_bool(field.presence.clamp(0.0, 1.0)) {
+                        map.insert(field.name.clone(), self.value(&field.shape, depth + 1));
+                    }
+                }
+                Value::Object(map)
+            }
+        }
+    }
+
+    fn text(&mut self, text: &TextShape) -> String {
+        match text.kind {
+            TextKind::Email => {
+                let first = self.pick(&FIRST_NAMES).to_lowercase();
+                let last = self.pick(&LAST_NAMES).to_lowercase();
+                let domain = self.pick(&DOMAINS);
+                format!("{}.{}{}@{}", first, last, self.rng.gen_range(1..100), domain)
+            }
+            TextKind::Uuid => {
+                // 随机UUID v4
+                let bytes: [u8; 16] = self.rng.gen();
+                let hex: String = bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| match i {
+                        6 => format!("{:02x}", (b & 0x0f) | 0x40),
+                        8 => format!("{:02x}", (b & 0x3f) | 0x80),
+                        _ => format!("{:02x}", b),
+                    })
+                    .collect();
+                format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+            }
+            TextKind::DateTime | TextKind::Date => {
+                // 没有观察范围时取最近一年
+                let (min, max) = text.time_range.unwrap_or((1_700_000_000, 1_731_536_000));
+                let (min, max) = if min == max { (min - 30 * 86_400, max + 30 * 86_400) } else { (min, max) };
+                let t = DateTime::from_timestamp(self.rng.gen_range(min..=max), 0).unwrap_or_default();
+                let format = if text.kind == TextKind::Date { "%Y-%m-%d" } else { self.time_format };
+                t.naive_utc().format(format).to_string()
+            }
+            TextKind::Url => {
+                let word = self.pick(&WORDS);
+                format!("https://{}/{}/{}", self.pick(&DOMAINS), word, self.rng.gen_range(1..10_000))
+            }
+            TextKind::Ipv4 => format!(
+                "{}.{}.{}.{}",
+                self.rng.gen_range(1..=223),
+                self.rng.gen_range(0..=255),
+                self.rng.gen_range(0..=255),
+                self.rng.gen_range(1..=254)
+            ),
+            TextKind::FullName if text.cjk => format!("{}{}", self.pick(&CJK_SURNAMES), self.pick(&CJK_GIVEN_NAMES)),
+            TextKind::FullName => format!("{} {}", self.pick(&FIRST_NAMES), self.pick(&LAST_NAMES)),
+            TextKind::FirstName if text.cjk => self.pick(&CJK_GIVEN_NAMES).to_string(),
+            TextKind::FirstName => self.pick(&FIRST_NAMES).to_string(),
+            TextKind::LastName if text.cjk => self.pick(&CJK_SURNAMES).to_string(),
+            TextKind::LastName => self.pick(&LAST_NAMES).to_string(),
+            TextKind::Username => format!("{}{}", self.pick(&FIRST_NAMES).to_lowercase(), self.rng.gen_range(1..1000)),
+            TextKind::Phone => format!("1{}{:09}", self.rng.gen_range(3..=9), self.rng.gen_range(0..1_000_000_000u32)),
+            TextKind::Words => self.words(text.min_len, text.max_len),
+        }
+    }
+
+    /// 生成长度（字符数）在范围内的文本
+    fn words(&mut self, min_len: usize, max_len: usize) -> String {
+        let min_len = min_len.min(max_len);
+        let target = self.rng.gen_range(min_len..=max_len);
+        let mut out = String::new();
+        while out.len() < target {
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            out.push_str(self.pick(&WORDS));
+        }
+        out.truncate(target);
+        out.trim_end().to_string()
+    }
+
+    fn pick<'w>(&mut self, items: &[&'w str]) -> &'w str {
+        items[self.rng.gen_range(0..items.len())]
+    }
+}
+
+/// 只观察到单个取值时适当扩大范围，非负数保持非负
+fn widen_integer(min: i64, max: i64) -> (i64, i64) {
+    if min != max {
+        return (min, max);
+    }
+    let spread = (min.unsigned_abs() / 2).max(10) as i64;
+    let low = min.saturating_sub(spread);
+    (if min >= 0 { low.max(0) } else { low }, max.saturating_add(spread))
+}
+
+fn widen_number(min: f64, max: f64) -> (f64, f64) {
+    if min != max {
+        return (min, max);
+    }
+    let spread = (min.abs() / 2.0).max(1.0);
+    let low = if min >= 0.0 { (min - spread).max(0.0) } else { (min - spread).max(f64::MIN) };
+    (low, (max + spread).min(f64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn generate(input: Value, count: usize) -> Vec<Value> {
+        let fake = FakeOptions {
+            count,
+            seed: 42,
+            ndjson: true,
+            time_format: "%Y-%m-%d %H:%M:%S".to_string(),
+        };
+        let output = generate_fake(&input, &fake, &FormatOptions::default(), &CancelToken::new()).unwrap().unwrap();
+        output.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn handles_ranges_wider_than_f64() {
+        for input in [json!([-1.7e308, 1.7e308, 0.5]), json!([-1.7e308]), json!([1.7e308])] {
+            for value in generate(input, 50) {
+                assert!(value.as_f64().is_some_and(f64::is_finite), "{}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn follows_observed_samples() {
+        let samples = json!([
+            {"id": "0b6f3a3e-1c2d-4e5f-8a9b-0c1d2e3f4a5b", "created": "2024-01-01 10:00:00", "status": "open", "n": 5},
+            {"id": "1b6f3a3e-1c2d-4e5f-8a9b-0c1d2e3f4a5b", "created": "2024-03-01 12:00:00", "status": "open", "n": 50},
+            {"id": "2b6f3a3e-1c2d-4e5f-8a9b-0c1d2e3f4a5b", "created": "2024-02-01 12:00:00", "status": "closed", "n": 7},
+            {"id": "3b6f3a3e-1c2d-4e5f-8a9b-0c1d2e3f4a5b", "created": "2024-02-05 12:00:00", "status": "closed", "n": 9}
+        ]);
+        for value in generate(samples, 20) {
+            let id = value["id"].as_str().unwrap();
+            assert!(super::super::schema::check_format("uuid", id), "{}", id);
+            let created = value["created"].as_str().unwrap();
+            assert!(("2024-01-01 10:00:00"..="2024-03-01 12:00:00").contains(&created), "{}", created);
+            assert!(["open", "closed"].contains(&value["status"].as_str().unwrap()));
+            assert!((5..=50).contains(&value["n"].as_i64().unwrap()));
+        }
+    }
+}
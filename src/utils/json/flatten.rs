@@ -0,0 +1,319 @@
+use super::format::write_escaped;
+use super::relaxed::push_pointer_segment;
+use super::CancelToken;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// 扁平化键的风格
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlattenStyle {
+    /// 点号路径，如 a.b[0].c；含点号、方括号、引号的键或空键写作 ["a.b"]
+    Dotted,
+    /// 环境变量风格，如 A__B__0__C：键转为大写，非字母数字字符替换为下划线
+    Env { separator: String },
+}
+
+impl FlattenStyle {
+    fn check(&self) -> Result<(), String> {
+        match self {
+            FlattenStyle::Env { separator } if separator.is_empty() => Err("分隔符不能为空".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 路径中的一段
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// 将对象或数组展开为一层的键值映射，空对象和空数组作为叶子保留；
+/// 不同路径映射为同一个键时报错。任务被取消时返回Ok(None)
+pub fn flatten(value: &Value, style: &FlattenStyle, token: &CancelToken) -> Result<Option<Map<String, Value>>, String> {
+    style.check()?;
+    if !matches!(value, Value::Object(_) | Value::Array(_)) {
+        return Err("只能扁平化对象或数组".to_string());
+    }
+    let mut flattener = Flattener {
+        style,
+        token,
+        path: Vec::new(),
+        out: Map::new(),
+        sources: HashMap::new(),
+    };
+    if !flattener.visit(value)? {
+        return Ok(None);
+    }
+    Ok(Some(flattener.out))
+}
+
+struct Flattener<'a> {
+    style: &'a FlattenStyle,
+    token: &'a CancelToken,
+    path: Vec<Segment>,
+    out: Map<String, Value>,
+    sources: HashMap<String, String>, // 扁平键 -> 来源JSON Pointer，用于冲突提示
+}
+
+impl Flattener<'_> {
+    /// 被取消时返回Ok(false)
+    fn visit(&mut self, value: &Value) -> Result<bool, String> {
+        if self.token.is_cancelled() {
+            return Ok(false);
+        }
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, item) in map {
+                    self.path.push(Segment::Key(key.clone()));
+                    let done = self.visit(item)?;
+                    self.path.pop();
+                    if !done {
+                        return Ok(false);
+                    }
+                }
+            }
+            Value::Array(items) if !items.is_empty() => {
+                for (i, item) in items.iter().enumerate() {
+                    self.path.push(Segment::Index(i));
+                    let done = self.visit(item)?;
+                    self.path.pop();
+                    if !done {
+                        return Ok(false);
+                    }
+                }
+            }
+            leaf => self.insert(leaf.clone())?,
+        }
+        Ok(true)
+    }
+
+    fn insert(&mut self, value: Value) -> Result<(), String> {
+        let key = match self.style {
+            FlattenStyle::Dotted => dotted_key(&self.path),
+            FlattenStyle::Env { separator } => env_key(&self.path, separator),
+        };
+        let source = pointer_of(&self.path);
+        if let Some(existing) = self.sources.get(&key) {
+            return Err(format!(
+                "键冲突: 路径 \"{}\" 与 \"{}\" 都对应扁平键 \"{}\"",
+                existing, source, key
+            ));
+        }
+        self.sources.insert(key.clone(), source);
+        self.out.insert(key, value);
+        Ok(())
+    }
+}
+
+fn dotted_key(path: &[Segment]) -> String {
+    let mut key = String::new();
+    for segment in path {
+        match segment {
+            Segment::Index(i) => key.push_str(&format!("[{}]", i)),
+            Segment::Key(name) if is_plain_key(name) => {
+                if !key.is_empty() {
+                    key.push('.');
+                }
+                key.push_str(name);
+            }
+            Segment::Key(name) => {
+                key.push('[');
+                write_escaped(&mut key, name, false);
+                key.push(']');
+            }
+        }
+    }
+    key
+}
+
+fn env_key(path: &[Segment], separator: &str) -> String {
+    path.iter()
+        .map(|segment| match segment {
+            Segment::Index(i) => i.to_string(),
+            Segment::Key(name) => name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+                .collect(),
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// 点号路径中可以直接书写的键：非空，且不含点号、方括号和引号
+fn is_plain_key(key: &str) -> bool {
+    !key.is_empty() && !key.contains(['.', '[', ']', '"'])
+}
+
+fn pointer_of(path: &[Segment]) -> String {
+    let mut pointer = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(name) => push_pointer_segment(&mut pointer, name),
+            Segment::Index(i) => push_pointer_segment(&mut pointer, &i.to_string()),
+        }
+    }
+    pointer
+}
+
+/// 解析扁平键为路径，数组下标不得超过max_index
+fn parse_key(key: &str, style: &FlattenStyle, max_index: usize) -> Result<Vec<Segment>, String> {
+    match style {
+        FlattenStyle::Dotted => parse_dotted_key(key, max_index),
+        // 环境变量风格中纯数字的段视为数组下标
+        FlattenStyle::Env { separator } => key
+            .split(separator.as_str())
+            .map(|segment| {
+                if !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()) {
+                    parse_index(key, segment, max_index).map(Segment::Index)
+                } else {
+                    Ok(Segment::Key(segment.to_string()))
+                }
+            })
+            .collect(),
+    }
+}
+
+/// 解析数组下标。扁平化结果中的下标不会超过键的总数，过大的下标视为输入错误，避免分配超大数组
+fn parse_index(key: &str, text: &str, max_index: usize) -> Result<usize, String> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("无效的扁平键 \"{}\"", key));
+    }
+    match text.parse::<usize>() {
+        Ok(index) if index <= max_index => Ok(index),
+        _ => Err(format!("无效的扁平键 \"{}\"：数组下标 {} 过大", key, text)),
+    }
+}
+
+fn parse_dotted_key(key: &str, max_index: usize) -> Result<Vec<Segment>, String> {
+    let invalid = || format!("无效的扁平键 \"{}\"", key);
+    let mut segments = Vec::new();
+    let mut rest = key;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            if after.starts_with('"') {
+                // ["带特殊字符的键"]，按JSON字符串解析
+                let mut de = serde_json::Deserializer::from_str(after).into_iter::<String>();
+                let name = de.next().and_then(Result::ok).ok_or_else(invalid)?;
+                let consumed = de.byte_offset();
+                segments.push(Segment::Key(name));
+                rest = after[consumed..].strip_prefix(']').ok_or_else(invalid)?;
+            } else {
+                let end = after.find(']').ok_or_else(invalid)?;
+                let index = parse_index(key, &after[..end], max_index)?;
+                segments.push(Segment::Index(index));
+                rest = &after[end + 1..];
+            }
+        } else {
+            let name = rest.strip_prefix('.').unwrap_or(rest);
+            // 首段不能以点号开头，之后的键必须以点号分隔
+            if rest.starts_with('.') == segments.is_empty() {
+                return Err(invalid());
+            }
+            let end = name.find(['.', '[']).unwrap_or(name.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            segments.push(Segment::Key(name[..end].to_string()));
+            rest = &name[end..];
+        }
+    }
+    if segments.is_empty() {
+        return Err(invalid());
+    }
+    Ok(segments)
+}
+
+/// 还原过程中的节点，数组下标可能乱序或不连续
+enum Node {
+    Empty,
+    Leaf(Value),
+    Object(Vec<(String, Node)>, HashMap<String, usize>),
+    Array(BTreeMap<usize, Node>),
+}
+
+impl Node {
+    fn into_value(self) -> Value {
+        match self {
+            Node::Empty => Value::Null,
+            Node::Leaf(value) => value,
+            Node::Object(entries, _) => Value::Object(entries.into_iter().map(|(k, v)| (k, v.into_value())).collect()),
+            // 缺失的下标以null填充
+            Node::Array(items) => {
+                let len = items.keys().next_back().map_or(0, |last| last + 1);
+                let mut values = vec![Value::Null; len];
+                for (i, node) in items {
+                    values[i] = node.into_value();
+                }
+                Value::Array(values)
+            }
+        }
+    }
+}
+
+/// 将扁平的键值映射还原为嵌套结构，数字下标的段还原为数组，缺失的下标以null填充；
+/// 同一位置既是值又是容器，或既是对象又是数组时报错。任务被取消时返回Ok(None)
+pub fn unflatten(map: &Map<String, Value>, style: &FlattenStyle, token: &CancelToken) -> Result<Option<Value>, String> {
+    style.check()?;
+    let mut root = Node::Empty;
+    // 每个位置由哪个扁平键创建，用于冲突提示
+    let mut owners: HashMap<String, String> = HashMap::new();
+    for (key, value) in map {
+        if token.is_cancelled() {
+            return Ok(None);
+        }
+        let path = parse_key(key, style, map.len())?;
+        insert_path(&mut root, &path, value.clone(), key, &mut owners)?;
+    }
+    Ok(Some(match root {
+        Node::Empty => Value::Object(Map::new()),
+        root => root.into_value(),
+    }))
+}
+
+fn insert_path(
+    root: &mut Node,
+    path: &[Segment],
+    value: Value,
+    key: &str,
+    owners: &mut HashMap<String, String>,
+) -> Result<(), String> {
+    let conflict = |owners: &HashMap<String, String>, pointer: &str| {
+        let other = owners.get(pointer).map_or("", String::as_str);
+        let location = if pointer.is_empty() { "(根节点)" } else { pointer };
+        format!("键冲突: \"{}\" 与 \"{}\" 在 {} 处结构不一致", other, key, location)
+    };
+
+    let mut node = root;
+    let mut pointer = String::new();
+    for segment in path {
+        if let Node::Empty = node {
+            *node = match segment {
+                Segment::Key(_) => Node::Object(Vec::new(), HashMap::new()),
+                Segment::Index(_) => Node::Array(BTreeMap::new()),
+            };
+            owners.insert(pointer.clone(), key.to_string());
+        }
+        node = match (node, segment) {
+            (Node::Object(entries, index), Segment::Key(name)) => {
+                let position = *index.entry(name.clone()).or_insert_with(|| {
+                    entries.push((name.clone(), Node::Empty));
+                    entries.len() - 1
+                });
+                push_pointer_segment(&mut pointer, name);
+                &mut entries[position].1
+            }
+            (Node::Array(items), Segment::Index(i)) => {
+                push_pointer_segment(&mut pointer, &i.to_string());
+                items.entry(*i).or_insert(Node::Empty)
+            }
+            _ => return Err(conflict(owners, &pointer)),
+        };
+    }
+    if !matches!(node, Node::Empty) {
+        return Err(conflict(owners, &pointer));
+    }
+    owners.insert(pointer, key.to_string());
+    *node = Node::Leaf(value);
+    Ok(())
+}
@@ -0,0 +1,333 @@
+use super::relaxed::{push_pointer_segment, CommentMap, NodeComments};
+use super::CancelToken;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 缩进方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndentStyle {
+    Spaces(usize),
+    Tab,
+}
+
+/// JSON格式化选项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatOptions {
+    /// 缩进方式
+    pub indent: IndentStyle,
+    /// 是否按键名排序
+    pub sort_keys: bool,
+    /// 单行宽度不超过该值的短数组/对象保持在一行，0表示不启用
+    pub inline_width: usize,
+    /// 是否将非ASCII字符转义为 \uXXXX
+    pub ensure_ascii: bool,
+    /// 格式化结果末尾是否追加换行符
+    pub trailing_newline: bool,
+    /// 宽松模式：接受JSON5/JSONC语法（注释、尾随逗号、单引号等）
+    pub relaxed: bool,
+    /// 宽松模式下格式化时保留注释，输出为JSONC
+    pub keep_comments: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: IndentStyle::Spaces(2),
+            sort_keys: false,
+            inline_width: 0,
+            ensure_ascii: false,
+            trailing_newline: false,
+            relaxed: false,
+            keep_comments: false,
+        }
+    }
+}
+
+/// 按选项输出JSON文本，pretty为false时输出压缩格式；任务被取消时返回None
+pub fn write_json(
+    value: &Value,
+    options: &FormatOptions,
+    pretty: bool,
+    token: &CancelToken,
+) -> Option<String> {
+    write_json_with_comments(value, &CommentMap::default(), options, pretty, token)
+}
+
+/// 按选项输出JSON文本并将注释写回原位置，注释只在格式化输出中保留
+pub fn write_json_with_comments(
+    value: &Value,
+    comments: &CommentMap,
+    options: &FormatOptions,
+    pretty: bool,
+    token: &CancelToken,
+) -> Option<String> {
+    let empty = CommentMap::default();
+    let mut writer = JsonWriter {
+        out: String::new(),
+        options,
+        pretty,
+        token,
+        comments: if pretty { comments } else { &empty },
+        path: String::new(),
+    };
+
+    let root = writer.comments.get("");
+    if let Some(root) = root {
+        for comment in &root.leading {
+            writer.out.push_str(comment);
+            writer.out.push('\n');
+        }
+    }
+    writer.write_value(value, 0)?;
+    if let Some(root) = root {
+        writer.write_trailing(root);
+    }
+
+    if pretty && options.trailing_newline {
+        writer.out.push('\n');
+    }
+    Some(writer.out)
+}
+
+struct JsonWriter<'a> {
+    out: String,
+    options: &'a FormatOptions,
+    pretty: bool,
+    token: &'a CancelToken,
+    comments: &'a CommentMap,
+    // 当前节点的JSON Pointer，仅在存在注释时维护
+    path: String,
+}
+
+impl<'a> JsonWriter<'a> {
+    fn write_value(&mut self, value: &Value, depth: usize) -> Option<()> {
+        match value {
+            Value::Null => self.out.push_str("null"),
+            Value::Bool(b) => self.out.push_str(if *b { "true" } else { "false" }),
+            Value::Number(n) => self.out.push_str(&n.to_string()),
+            Value::String(s) => write_escaped(&mut self.out, s, self.options.ensure_ascii),
+            Value::Array(items) => {
+                if self.token.is_cancelled() {
+                    return None;
+                }
+                if items.is_empty() && !self.has_dangling() {
+                    self.out.push_str("[]");
+                } else if !self.pretty || self.fits_inline(value) {
+                    self.write_inline(value);
+                } else {
+                    self.out.push('[');
+                    for (i, item) in items.iter().enumerate() {
+                        let len = self.enter(|| i.to_string());
+                        let node = self.write_leading(depth + 1);
+                        self.write_value(item, depth + 1)?;
+                        self.write_separator(i + 1 < items.len(), node);
+                        self.path.truncate(len);
+                    }
+                    self.write_dangling(depth + 1);
+                    self.newline(depth);
+                    self.out.push(']');
+                }
+            }
+            Value::Object(map) => {
+                if self.token.is_cancelled() {
+                    return None;
+                }
+                if map.is_empty() && !self.has_dangling() {
+                    self.out.push_str("{}");
+                } else if !self.pretty || self.fits_inline(value) {
+                    self.write_inline(value);
+                } else {
+                    self.out.push('{');
+                    for (i, (key, item)) in map.iter().enumerate() {
+                        let len = self.enter(|| key.clone());
+                        let node = self.write_leading(depth + 1);
+                        write_escaped(&mut self.out, key, self.options.ensure_ascii);
+                        self.out.push_str(": ");
+                        self.write_value(item, depth + 1)?;
+                        self.write_separator(i + 1 < map.len(), node);
+                        self.path.truncate(len);
+                    }
+                    self.write_dangling(depth + 1);
+                    self.newline(depth);
+                    self.out.push('}');
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// 单行输出，格式化模式下在分隔符后保留空格
+    fn write_inline(&mut self, value: &Value) {
+        let (comma, colon) = if self.pretty { (", ", ": ") } else { (",", ":") };
+        match value {
+            Value::Array(items) => {
+                self.out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(comma);
+                    }
+                    self.write_inline(item);
+                }
+                self.out.push(']');
+            }
+            Value::Object(map) => {
+                self.out.push('{');
+                for (i, (key, item)) in map.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(comma);
+                    }
+                    write_escaped(&mut self.out, key, self.options.ensure_ascii);
+                    self.out.push_str(colon);
+                    self.write_inline(item);
+                }
+                self.out.push('}');
+            }
+            scalar => {
+                let _ = self.write_value(scalar, 0);
+            }
+        }
+    }
+
+    fn fits_inline(&self, value: &Value) -> bool {
+        let limit = self.options.inline_width;
+        limit > 0
+            && !self.comments.has_inner(&self.path)
+            && inline_len(value, limit, self.options.ensure_ascii).is_some()
+    }
+
+    /// 进入子节点，返回进入前的路径长度用于恢复
+    fn enter(&mut self, segment: impl FnOnce() -> String) -> usize {
+        let len = self.path.len();
+        if !self.comments.is_empty() {
+            push_pointer_segment(&mut self.path, &segment());
+        }
+        len
+    }
+
+    /// 换行并写出元素之前的注释，返回当前元素的注释
+    fn write_leading(&mut self, depth: usize) -> Option<&'a NodeComments> {
+        let node = self.comments.get(&self.path);
+        if let Some(node) = node {
+            for comment in &node.leading {
+                self.newline(depth);
+                self.out.push_str(comment);
+            }
+        }
+        self.newline(depth);
+        node
+    }
+
+    /// 写出元素之间的逗号，以及元素同一行的注释
+    fn write_separator(&mut self, has_next: bool, node: Option<&NodeComments>) {
+        if has_next {
+            self.out.push(',');
+        }
+        if let Some(node) = node {
+            self.write_trailing(node);
+        }
+    }
+
+    fn write_trailing(&mut self, node: &NodeComments) {
+        for comment in &node.trailing {
+            self.out.push(' ');
+            self.out.push_str(comment);
+        }
+    }
+
+    fn has_dangling(&self) -> bool {
+        self.comments
+            .get(&self.path)
+            .is_some_and(|node| !node.dangling.is_empty())
+    }
+
+    /// 写出容器右括号之前的注释
+    fn write_dangling(&mut self, depth: usize) {
+        if let Some(node) = self.comments.get(&self.path) {
+            for comment in &node.dangling {
+                self.newline(depth);
+                self.out.push_str(comment);
+            }
+        }
+    }
+
+    fn newline(&mut self, depth: usize) {
+        self.out.push('\n');
+        match self.options.indent {
+            IndentStyle::Spaces(width) => {
+                self.out.extend(std::iter::repeat_n(' ', width * depth));
+            }
+            IndentStyle::Tab => self.out.extend(std::iter::repeat_n('\t', depth)),
+        }
+    }
+}
+
+/// 计算值的单行输出长度，超过limit时提前返回None
+fn inline_len(value: &Value, limit: usize, ensure_ascii: bool) -> Option<usize> {
+    let len = match value {
+        Value::Null => 4,
+        Value::Bool(b) => if *b { 4 } else { 5 },
+        Value::Number(n) => n.to_string().len(),
+        Value::String(s) => escaped_len(s, ensure_ascii),
+        Value::Array(items) => {
+            let mut total = 2 + items.len().saturating_sub(1) * 2;
+            for item in items {
+                if total > limit {
+                    return None;
+                }
+                total += inline_len(item, limit - total, ensure_ascii)?;
+            }
+            total
+        }
+        Value::Object(map) => {
+            let mut total = 2 + map.len().saturating_sub(1) * 2 + map.len() * 2;
+            for (key, item) in map {
+                total += escaped_len(key, ensure_ascii);
+                if total > limit {
+                    return None;
+                }
+                total += inline_len(item, limit - total, ensure_ascii)?;
+            }
+            total
+        }
+    };
+    (len <= limit).then_some(len)
+}
+
+/// 转义后字符串（含引号）的字符数
+fn escaped_len(s: &str, ensure_ascii: bool) -> usize {
+    2 + s
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' | '\n' | '\r' | '\t' | '\u{08}' | '\u{0C}' => 2,
+            c if (c as u32) < 0x20 => 6,
+            c if ensure_ascii && !c.is_ascii() => 6 * c.len_utf16(),
+            _ => 1,
+        })
+        .sum::<usize>()
+}
+
+/// 按JSON规则转义字符串并写入输出
+pub(crate) fn write_escaped(out: &mut String, s: &str, ensure_ascii: bool) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0C}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c if ensure_ascii && !c.is_ascii() => {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    out.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
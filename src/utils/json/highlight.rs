@@ -0,0 +1,210 @@
+use std::ops::Range;
+
+/// 语法高亮的记号类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Plain,
+    Key,
+    String,
+    Number,
+    Boolean,
+    Null,
+    Bracket,
+    Punctuation,
+    Comment,
+}
+
+/// 行内记号，range为该行内的字节范围
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightToken {
+    pub range: Range<usize>,
+    pub kind: TokenKind,
+}
+
+/// 将一行文本切分为记号，所有记号首尾相接覆盖整行。
+/// 以JSON为主，同时兼顾YAML/TOML输出中的裸键、`=` 和 `#` 注释
+pub fn tokenize_line(line: &str) -> Vec<HighlightToken> {
+    let bytes = line.as_bytes();
+    let mut tokens: Vec<HighlightToken> = Vec::new();
+    let push = |tokens: &mut Vec<HighlightToken>, range: Range<usize>, kind: TokenKind| {
+        // 相邻的同类记号合并，减少界面上的文本片段
+        match tokens.last_mut() {
+            Some(last) if last.kind == kind && last.range.end == range.start => last.range.end = range.end,
+            _ => tokens.push(HighlightToken { range, kind }),
+        }
+    };
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let b = bytes[i];
+        let after_space = i == 0 || bytes[i - 1].is_ascii_whitespace();
+        if b.is_ascii_whitespace() {
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            push(&mut tokens, start..i, TokenKind::Plain);
+        } else if b == b'"' {
+            i = string_end(bytes, i);
+            let kind = if is_key_suffix(&bytes[i..], false) { TokenKind::Key } else { TokenKind::String };
+            push(&mut tokens, start..i, kind);
+        } else if after_space && (b == b'#' || bytes[i..].starts_with(b"//")) {
+            push(&mut tokens, start..bytes.len(), TokenKind::Comment);
+            break;
+        } else if bytes[i..].starts_with(b"/*") {
+            i = line[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+            push(&mut tokens, start..i, TokenKind::Comment);
+        } else if matches!(b, b'{' | b'}' | b'[' | b']') {
+            i += 1;
+            push(&mut tokens, start..i, TokenKind::Bracket);
+        } else if matches!(b, b',' | b':' | b'=') {
+            i += 1;
+            push(&mut tokens, start..i, TokenKind::Punctuation);
+        } else {
+            while i < bytes.len() && !is_word_end(bytes, i) {
+                i += 1;
+            }
+            let kind = classify_word(&line[start..i], &bytes[i..]);
+            push(&mut tokens, start..i, kind);
+        }
+    }
+    tokens
+}
+
+/// 从起始引号开始，返回字符串结束引号之后的位置；未闭合时到行尾
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// 后面紧跟 `:` 或 `=`（中间可有空白）时视为键；
+/// bare为裸词时冒号后须为空白或行尾（YAML），避免把 http://… 之类的值当作键
+fn is_key_suffix(rest: &[u8], bare: bool) -> bool {
+    let rest = rest.trim_ascii_start();
+    match rest.first() {
+        Some(b'=') => true,
+        Some(b':') => !bare || is_separator_colon(rest, 0),
+        _ => false,
+    }
+}
+
+fn is_separator_colon(bytes: &[u8], i: usize) -> bool {
+    bytes.get(i + 1).is_none_or(|b| b.is_ascii_whitespace())
+}
+
+/// 裸词在空白、结构字符或作为键分隔符的冒号处结束
+fn is_word_end(bytes: &[u8], i: usize) -> bool {
+    match bytes[i] {
+        b if b.is_ascii_whitespace() => true,
+        b'{' | b'}' | b'[' | b']' | b',' | b'"' | b'=' => true,
+        b':' => is_separator_colon(bytes, i),
+        _ => false,
+    }
+}
+
+fn classify_word(word: &str, rest: &[u8]) -> TokenKind {
+    match word {
+        "true" | "false" => TokenKind::Boolean,
+        "null" | "~" => TokenKind::Null,
+        "-" => TokenKind::Punctuation,
+        _ if is_key_suffix(rest, true) => TokenKind::Key,
+        _ if is_number(word) => TokenKind::Number,
+        _ => TokenKind::Plain,
+    }
+}
+
+fn is_number(word: &str) -> bool {
+    let digits = word.strip_prefix(['-', '+']).unwrap_or(word);
+    digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-' | '_'))
+}
+
+/// 文本的行索引：各行的起始位置及括号所在行的配对行，用于按需渲染可见行
+#[derive(Debug, Clone, Default)]
+pub struct LineIndex {
+    starts: Vec<usize>,
+    len: usize,
+    /// 各行中跨行配对的括号所对应的行，u32::MAX表示无
+    matches: Vec<u32>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(text.bytes().enumerate().filter(|(_, b)| *b == b'\n').map(|(i, _)| i + 1));
+        let mut index = Self {
+            matches: vec![u32::MAX; starts.len()],
+            starts,
+            len: text.len(),
+        };
+        index.match_brackets(text);
+        index
+    }
+
+    /// 按记号扫描全文配对括号，只记录开闭不在同一行的括号
+    fn match_brackets(&mut self, text: &str) {
+        let mut stack: Vec<(u8, usize)> = Vec::new();
+        for line in 0..self.line_count() {
+            let content = &text[self.line_range(line)];
+            for token in tokenize_line(content) {
+                if token.kind != TokenKind::Bracket {
+                    continue;
+                }
+                match content.as_bytes()[token.range.start] {
+                    open @ (b'{' | b'[') => stack.push((open, line)),
+                    close => {
+                        let expected = if close == b'}' { b'{' } else { b'[' };
+                        // 括号不匹配时丢弃，继续按外层配对
+                        while let Some((open, open_line)) = stack.pop() {
+                            if open != expected {
+                                continue;
+                            }
+                            if open_line != line {
+                                self.set_match(open_line, line);
+                                self.set_match(line, open_line);
+                            }
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_match(&mut self, line: usize, other: usize) {
+        if self.matches[line] == u32::MAX {
+            self.matches[line] = other as u32;
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// 第line行（从0开始）的字节范围，不含换行符
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        let start = self.starts[line];
+        let end = self.starts.get(line + 1).map_or(self.len, |next| next - 1);
+        start..end
+    }
+
+    /// 该行括号所配对的行
+    pub fn matching_line(&self, line: usize) -> Option<usize> {
+        self.matches
+            .get(line)
+            .filter(|m| **m != u32::MAX)
+            .map(|m| *m as usize)
+    }
+
+    /// 字节偏移所在的行
+    pub fn line_of_offset(&self, offset: usize) -> usize {
+        self.starts.partition_point(|start| *start <= offset).saturating_sub(1)
+    }
+}
@@ -0,0 +1,100 @@
+use super::shape::Shape;
+use super::CancelToken;
+use serde_json::{json, Map, Value};
+
+/// 推断Schema时输出的字符串格式，按优先级排列
+const INFERRED_FORMATS: [&str; 3] = ["date-time", "uuid", "email"];
+
+/// 从一个或多个样本推断Draft 2020-12 Schema；任务被取消时返回None
+pub fn infer_schema(samples: &[Value], token: &CancelToken) -> Option<Value> {
+    let mut shape = Shape::default();
+    for sample in samples {
+        if token.is_cancelled() {
+            return None;
+        }
+        shape.add(sample);
+    }
+
+    let mut schema = Map::new();
+    schema.insert(
+        "$schema".to_string(),
+        Value::String("https://json-schema.org/draft/2020-12/schema".to_string()),
+    );
+    if let Value::Object(body) = to_schema(&shape) {
+        schema.extend(body);
+    }
+    Some(Value::Object(schema))
+}
+
+/// 生成该位置的Schema，多种类型时合并为类型列表
+fn to_schema(shape: &Shape) -> Value {
+    let mut types = Vec::new();
+    let mut schema = Map::new();
+    let mut enum_values = None;
+
+    if let Some(object) = &shape.objects {
+        types.push("object");
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for key in &object.keys {
+            let (count, shape) = &object.props[key];
+            properties.insert(key.clone(), to_schema(shape));
+            // 每个对象样本中都出现的键视为必需
+            if *count == object.count {
+                required.push(Value::String(key.clone()));
+            }
+        }
+        schema.insert("properties".to_string(), Value::Object(properties));
+        if !required.is_empty() {
+            schema.insert("required".to_string(), Value::Array(required));
+        }
+    }
+    if let Some(array) = &shape.arrays {
+        types.push("array");
+        if array.has_items {
+            schema.insert("items".to_string(), to_schema(&array.items));
+        }
+    }
+    if let Some(strings) = &shape.strings {
+        types.push("string");
+        if let Some(format) = strings.formats.iter().find(|f| INFERRED_FORMATS.contains(f)) {
+            schema.insert("format".to_string(), Value::String(format.to_string()));
+        } else if strings.is_enum() {
+            enum_values = Some(strings.order.iter().cloned().map(Value::String).collect::<Vec<_>>());
+        }
+    }
+    if shape.numbers > 0 {
+        types.push("number");
+    } else if shape.integers > 0 {
+        types.push("integer");
+    }
+    if shape.booleans > 0 {
+        types.push("boolean");
+    }
+    if shape.nulls > 0 {
+        types.push("null");
+    }
+
+    // enum 会约束所有类型，因此只在值仅为字符串（或null）时生成
+    if let Some(mut values) = enum_values {
+        match types.as_slice() {
+            ["string"] => {
+                schema.insert("enum".to_string(), Value::Array(values));
+            }
+            ["string", "null"] => {
+                values.push(Value::Null);
+                schema.insert("enum".to_string(), Value::Array(values));
+            }
+            _ => {}
+        }
+    }
+    let kind = match types.as_slice() {
+        [] => return json!({}),
+        [single] => json!(single),
+        many => json!(many),
+    };
+    let mut result = Map::new();
+    result.insert("type".to_string(), kind);
+    result.extend(schema);
+    Value::Object(result)
+}
@@ -0,0 +1,1310 @@
+use super::jsonpath::{json_equal, number_cmp};
+use super::CancelToken;
+use regex::Regex;
+use serde_json::{Map, Number, Value};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// range 一次最多生成的值数量，防止耗尽内存
+const MAX_RANGE_LEN: usize = 1_000_000;
+
+/// 执行jq风格的查询，返回所有输出值；任务被取消时返回Ok(None)
+///
+/// 支持常用子集：路径访问（.a、.[0]、.[1:3]、.[]、..、?）、管道 |、逗号 ,、
+/// 数组/对象构造、比较与算术运算、and/or/not、//、if-then-else，
+/// 以及 select、map、length、keys、has、sort_by 等常用函数
+pub fn query(program: &str, input: &Value, token: &CancelToken) -> Result<Option<Vec<Value>>, String> {
+    let filter = Parser::new(program).parse_program()?;
+    let eval = Evaluator {
+        token,
+        regexes: RefCell::new(HashMap::new()),
+    };
+    match eval.eval(&filter, input) {
+        Ok(values) => Ok(Some(values)),
+        Err(_) if token.is_cancelled() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+enum Filter {
+    Identity,
+    Recurse,
+    Literal(Value),
+    Field(Box<Filter>, String),
+    Index(Box<Filter>, Box<Filter>),
+    Slice(Box<Filter>, Option<Box<Filter>>, Option<Box<Filter>>),
+    Iterate(Box<Filter>),
+    Optional(Box<Filter>),
+    Pipe(Box<Filter>, Box<Filter>),
+    Comma(Box<Filter>, Box<Filter>),
+    Binary(BinOp, Box<Filter>, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Alternative(Box<Filter>, Box<Filter>),
+    Neg(Box<Filter>),
+    Array(Option<Box<Filter>>),
+    Object(Vec<(Filter, Filter)>),
+    If(Vec<(Filter, Filter)>, Option<Box<Filter>>),
+    Call(String, Vec<Filter>),
+    Template(Vec<TemplatePart<Filter>>),
+}
+
+/// 带插值的字符串 "a\(.b)c" 的组成部分
+#[derive(Debug, Clone, PartialEq)]
+enum TemplatePart<T> {
+    Text(String),
+    Expr(T),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    DotDot,
+    Ident(String),
+    Field(String),
+    Str(String),
+    Template(Vec<TemplatePart<String>>),
+    Num(Number),
+    Op(&'static str),
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    len: usize,
+    error: Option<String>,
+}
+
+impl Parser {
+    fn new(src: &str) -> Self {
+        match tokenize(src) {
+            Ok(tokens) => Self { tokens, pos: 0, len: src.len(), error: None },
+            Err(e) => Self { tokens: Vec::new(), pos: 0, len: src.len(), error: Some(e) },
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        let at = self.tokens.get(self.pos).map_or(self.len, |(_, offset)| *offset);
+        format!("jq语法错误（位置 {}）: {}", at + 1, message)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_ident(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(w)) if w == word) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_op(&mut self, op: &str) -> Result<(), String> {
+        if self.eat_op(op) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("此处应为 '{}'", op)))
+        }
+    }
+
+    fn parse_program(&mut self) -> Result<Filter, String> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        if self.tokens.is_empty() {
+            return Ok(Filter::Identity);
+        }
+        let filter = self.parse_pipe()?;
+        if self.pos < self.tokens.len() {
+            return Err(self.error("无法识别的内容"));
+        }
+        Ok(filter)
+    }
+
+    fn parse_pipe(&mut self) -> Result<Filter, String> {
+        let left = self.parse_comma()?;
+        if self.eat_op("|") {
+            let right = self.parse_pipe()?;
+            return Ok(Filter::Pipe(Box::new(left), Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn parse_comma(&mut self) -> Result<Filter, String> {
+        let mut left = self.parse_alternative()?;
+        while self.eat_op(",") {
+            let right = self.parse_alternative()?;
+            left = Filter::Comma(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_alternative(&mut self) -> Result<Filter, String> {
+        let left = self.parse_or()?;
+        if self.eat_op("//") {
+            let right = self.parse_alternative()?;
+            return Ok(Filter::Alternative(Box::new(left), Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, String> {
+        let mut left = self.parse_and()?;
+        while self.eat_ident("or") {
+            let right = self.parse_and()?;
+            left = Filter::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, String> {
+        let mut left = self.parse_compare()?;
+        while self.eat_ident("and") {
+            let right = self.parse_compare()?;
+            left = Filter::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_compare(&mut self) -> Result<Filter, String> {
+        let left = self.parse_additive()?;
+        let ops = [
+            ("==", BinOp::Eq),
+            ("!=", BinOp::Ne),
+            ("<=", BinOp::Le),
+            (">=", BinOp::Ge),
+            ("<", BinOp::Lt),
+            (">", BinOp::Gt),
+        ];
+        for (text, op) in ops {
+            if self.eat_op(text) {
+                let right = self.parse_additive()?;
+                return Ok(Filter::Binary(op, Box::new(left), Box::new(right)));
+            }
+        }
+        Ok(left)
+    }
+
+    fn parse_additive(&mut self) -> Result<Filter, String> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = if self.eat_op("+") {
+                BinOp::Add
+            } else if self.eat_op("-") {
+                BinOp::Sub
+            } else {
+                return Ok(left);
+            };
+            let right = self.parse_multiplicative()?;
+            left = Filter::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Filter, String> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = if self.eat_op("*") {
+                BinOp::Mul
+            } else if self.eat_op("/") {
+                BinOp::Div
+            } else if self.eat_op("%") {
+                BinOp::Rem
+            } else {
+                return Ok(left);
+            };
+            let right = self.parse_unary()?;
+            left = Filter::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, String> {
+        if self.eat_op("-") {
+            return Ok(Filter::Neg(Box::new(self.parse_postfix()?)));
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Filter, String> {
+        let mut term = self.parse_term()?;
+        loop {
+            match self.peek() {
+                Some(Token::Field(name)) => {
+                    let name = name.clone();
+                    self.pos += 1;
+                    term = Filter::Field(Box::new(term), name);
+                }
+                Some(Token::Dot) if matches!(self.tokens.get(self.pos + 1), Some((Token::Str(_), _))) => {
+                    self.pos += 1;
+                    let Some(Token::Str(name)) = self.peek().cloned() else { unreachable!() };
+                    self.pos += 1;
+                    term = Filter::Field(Box::new(term), name);
+                }
+                Some(Token::Dot) if matches!(self.tokens.get(self.pos + 1), Some((Token::Op("["), _))) => {
+                    self.pos += 1;
+                }
+                Some(Token::Op("[")) => {
+                    self.pos += 1;
+                    term = self.parse_bracket_suffix(term)?;
+                }
+                Some(Token::Op("?")) => {
+                    self.pos += 1;
+                    term = Filter::Optional(Box::new(term));
+                }
+                _ => return Ok(term),
+            }
+        }
+    }
+
+    fn parse_bracket_suffix(&mut self, target: Filter) -> Result<Filter, String> {
+        if self.eat_op("]") {
+            return Ok(Filter::Iterate(Box::new(target)));
+        }
+        let start = if matches!(self.peek(), Some(Token::Op(":"))) {
+            None
+        } else {
+            Some(Box::new(self.parse_pipe()?))
+        };
+        if self.eat_op(":") {
+            let end = if matches!(self.peek(), Some(Token::Op("]"))) {
+                None
+            } else {
+                Some(Box::new(self.parse_pipe()?))
+            };
+            self.expect_op("]")?;
+            return Ok(Filter::Slice(Box::new(target), start, end));
+        }
+        self.expect_op("]")?;
+        let index = start.ok_or_else(|| self.error("缺少索引"))?;
+        Ok(Filter::Index(Box::new(target), index))
+    }
+
+    fn parse_term(&mut self) -> Result<Filter, String> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.error("表达式不完整"));
+        };
+        self.pos += 1;
+        match token {
+            Token::Dot => {
+                if let Some(Token::Str(name)) = self.peek().cloned() {
+                    self.pos += 1;
+                    return Ok(Filter::Field(Box::new(Filter::Identity), name));
+                }
+                Ok(Filter::Identity)
+            }
+            Token::DotDot => Ok(Filter::Recurse),
+            Token::Field(name) => Ok(Filter::Field(Box::new(Filter::Identity), name)),
+            Token::Str(s) => Ok(Filter::Literal(Value::String(s))),
+            Token::Template(parts) => {
+                let mut filters = Vec::with_capacity(parts.len());
+                for part in parts {
+                    filters.push(match part {
+                        TemplatePart::Text(text) => TemplatePart::Text(text),
+                        TemplatePart::Expr(src) => TemplatePart::Expr(Parser::new(&src).parse_program()?),
+                    });
+                }
+                Ok(Filter::Template(filters))
+            }
+            Token::Num(n) => Ok(Filter::Literal(Value::Number(n))),
+            Token::Op("(") => {
+                let inner = self.parse_pipe()?;
+                self.expect_op(")")?;
+                Ok(inner)
+            }
+            Token::Op("[") => {
+                if self.eat_op("]") {
+                    return Ok(Filter::Array(None));
+                }
+                let inner = self.parse_pipe()?;
+                self.expect_op("]")?;
+                Ok(Filter::Array(Some(Box::new(inner))))
+            }
+            Token::Op("{") => self.parse_object(),
+            Token::Ident(word) => match word.as_str() {
+                "true" => Ok(Filter::Literal(Value::Bool(true))),
+                "false" => Ok(Filter::Literal(Value::Bool(false))),
+                "null" => Ok(Filter::Literal(Value::Null)),
+                "if" => self.parse_if(),
+                _ => {
+                    let mut args = Vec::new();
+                    if self.eat_op("(") {
+                        loop {
+                            args.push(self.parse_pipe()?);
+                            if self.eat_op(")") {
+                                break;
+                            }
+                            self.expect_op(";")?;
+                        }
+                    }
+                    Ok(Filter::Call(word, args))
+                }
+            },
+            _ => {
+                self.pos -= 1;
+                Err(self.error("此处应为表达式"))
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Filter, String> {
+        let mut entries = Vec::new();
+        if self.eat_op("}") {
+            return Ok(Filter::Object(entries));
+        }
+        loop {
+            let key_token = self.peek().cloned();
+            self.pos += 1;
+            let key_name = match key_token {
+                Some(Token::Ident(name)) | Some(Token::Str(name)) => name,
+                Some(Token::Op("(")) => {
+                    let key = self.parse_pipe()?;
+                    self.expect_op(")")?;
+                    self.expect_op(":")?;
+                    entries.push((key, self.parse_alternative()?));
+                    if self.eat_op("}") {
+                        return Ok(Filter::Object(entries));
+                    }
+                    self.expect_op(",")?;
+                    continue;
+                }
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("此处应为对象的键"));
+                }
+            };
+            // {a} 是 {a: .a} 的简写
+            let value = if self.eat_op(":") {
+                self.parse_alternative()?
+            } else {
+                Filter::Field(Box::new(Filter::Identity), key_name.clone())
+            };
+            entries.push((Filter::Literal(Value::String(key_name)), value));
+            if self.eat_op("}") {
+                return Ok(Filter::Object(entries));
+            }
+            self.expect_op(",")?;
+        }
+    }
+
+    fn parse_if(&mut self) -> Result<Filter, String> {
+        let mut branches = Vec::new();
+        loop {
+            let cond = self.parse_pipe()?;
+            if !self.eat_ident("then") {
+                return Err(self.error("if 缺少 then"));
+            }
+            let body = self.parse_pipe()?;
+            branches.push((cond, body));
+            if self.eat_ident("elif") {
+                continue;
+            }
+            let otherwise = if self.eat_ident("else") {
+                Some(Box::new(self.parse_pipe()?))
+            } else {
+                None
+            };
+            if !self.eat_ident("end") {
+                return Err(self.error("if 缺少 end"));
+            }
+            return Ok(Filter::If(branches, otherwise));
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, String> {
+    const OPS: [&str; 22] = [
+        "==", "!=", "<=", ">=", "//", "|", ",", "<", ">", "+", "-", "*", "/", "%", "(", ")", "[",
+        "]", "{", "}", ":", ";",
+    ];
+    let mut tokens = Vec::new();
+    let mut chars = src.char_indices().peekable();
+    while let Some(&(i, c)) = chars.peek() {
+        let rest = &src[i..];
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '#' {
+            // 注释到行尾
+            while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+        } else if rest.starts_with("..") {
+            chars.next();
+            chars.next();
+            tokens.push((Token::DotDot, i));
+        } else if c == '.' {
+            chars.next();
+            let name_start = chars.peek().map_or(src.len(), |&(j, _)| j);
+            let mut end = name_start;
+            while let Some(&(j, c)) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' {
+                    end = j + c.len_utf8();
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            if end > name_start {
+                tokens.push((Token::Field(src[name_start..end].to_string()), i));
+            } else {
+                tokens.push((Token::Dot, i));
+            }
+        } else if c == '"' {
+            chars.next();
+            let unterminated = || format!("jq语法错误（位置 {}）: 字符串缺少结束引号", i + 1);
+            let mut parts = Vec::new();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    None => return Err(unterminated()),
+                    Some((_, '"')) => break,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => s.push('\n'),
+                        Some((_, 't')) => s.push('\t'),
+                        Some((_, 'r')) => s.push('\r'),
+                        Some((_, '(')) => {
+                            // 字符串插值，截取到匹配的右括号为止
+                            let mut depth = 1;
+                            let mut in_string = false;
+                            let mut expr = String::new();
+                            loop {
+                                let (_, c) = chars.next().ok_or_else(unterminated)?;
+                                match c {
+                                    '"' => in_string = !in_string,
+                                    '(' if !in_string => depth += 1,
+                                    ')' if !in_string => {
+                                        depth -= 1;
+                                        if depth == 0 {
+                                            break;
+                                        }
+                                    }
+                                    _ => {}
+                                }
+                                expr.push(c);
+                            }
+                            parts.push(TemplatePart::Text(std::mem::take(&mut s)));
+                            parts.push(TemplatePart::Expr(expr));
+                        }
+                        Some((_, c)) => s.push(c),
+                        None => return Err(unterminated()),
+                    },
+                    Some((_, c)) => s.push(c),
+                }
+            }
+            if parts.is_empty() {
+                tokens.push((Token::Str(s), i));
+            } else {
+                parts.push(TemplatePart::Text(s));
+                tokens.push((Token::Template(parts), i));
+            }
+        } else if c.is_ascii_digit() {
+            let mut end = i;
+            while let Some(&(j, c)) = chars.peek() {
+                if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' {
+                    end = j + 1;
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let n = serde_json::from_str::<Number>(&src[i..end])
+                .map_err(|_| format!("jq语法错误（位置 {}）: 无效的数字", i + 1))?;
+            tokens.push((Token::Num(n), i));
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let mut end = i;
+            while let Some(&(j, c)) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' || c == '$' {
+                    end = j + c.len_utf8();
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push((Token::Ident(src[i..end].to_string()), i));
+        } else if c == '?' {
+            chars.next();
+            tokens.push((Token::Op("?"), i));
+        } else if let Some(op) = OPS.iter().find(|op| rest.starts_with(**op)) {
+            for _ in 0..op.len() {
+                chars.next();
+            }
+            tokens.push((Token::Op(op), i));
+        } else {
+            return Err(format!("jq语法错误（位置 {}）: 无法识别的字符 '{}'", i + 1, c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Evaluator<'t> {
+    token: &'t CancelToken,
+    /// 已编译的正则缓存，避免 test 对每个输入重复编译
+    regexes: RefCell<HashMap<String, Result<Regex, String>>>,
+}
+
+type Outputs = Result<Vec<Value>, String>;
+
+impl Evaluator<'_> {
+    fn regex(&self, pattern: &str) -> Result<Regex, String> {
+        self.regexes
+            .borrow_mut()
+            .entry(pattern.to_string())
+            .or_insert_with(|| Regex::new(pattern).map_err(|e| e.to_string()))
+            .clone()
+    }
+
+    /// 出错时返回空结果（`?` 和 `//` 的语义），但取消不能被吞掉，继续向上传递
+    fn eval_or_empty(&self, filter: &Filter, input: &Value) -> Outputs {
+        match self.eval(filter, input) {
+            Err(_) if self.token.is_cancelled() => Err(super::ERR_CANCELLED.to_string()),
+            result => Ok(result.unwrap_or_default()),
+        }
+    }
+
+    fn eval(&self, filter: &Filter, input: &Value) -> Outputs {
+        if self.token.is_cancelled() {
+            return Err(super::ERR_CANCELLED.to_string());
+        }
+        match filter {
+            Filter::Identity => Ok(vec![input.clone()]),
+            Filter::Recurse => {
+                let mut out = Vec::new();
+                let mut stack = vec![input];
+                while let Some(v) = stack.pop() {
+                    out.push(v.clone());
+                    match v {
+                        Value::Array(items) => stack.extend(items.iter().rev()),
+                        Value::Object(map) => stack.extend(map.values().rev()),
+                        _ => {}
+                    }
+                }
+                Ok(out)
+            }
+            Filter::Literal(v) => Ok(vec![v.clone()]),
+            Filter::Field(target, name) => self.flat_map(target, input, |v| match v {
+                Value::Object(map) => Ok(vec![map.get(name).cloned().unwrap_or(Value::Null)]),
+                Value::Null => Ok(vec![Value::Null]),
+                other => Err(format!("无法用字符串 \"{}\" 索引 {}", name, type_name(&other))),
+            }),
+            Filter::Index(target, index) => self.flat_map(target, input, |v| {
+                self.eval(index, input)?
+                    .into_iter()
+                    .map(|idx| index_value(&v, &idx))
+                    .collect()
+            }),
+            Filter::Slice(target, start, end) => self.flat_map(target, input, |v| {
+                let bound = |f: &Option<Box<Filter>>| -> Result<Option<i64>, String> {
+                    match f {
+                        None => Ok(None),
+                        Some(f) => match self.eval(f, input)?.first() {
+                            Some(Value::Number(n)) => Ok(n.as_f64().map(|x| x.floor() as i64)),
+                            Some(Value::Null) | None => Ok(None),
+                            Some(other) => Err(format!("切片下标必须是数字，而不是 {}", type_name(other))),
+                        },
+                    }
+                };
+                slice_value(&v, bound(start)?, bound(end)?).map(|s| vec![s])
+            }),
+            Filter::Iterate(target) => self.flat_map(target, input, |v| match v {
+                Value::Array(items) => Ok(items),
+                Value::Object(map) => Ok(map.into_iter().map(|(_, v)| v).collect()),
+                other => Err(format!("无法遍历 {}", type_name(&other))),
+            }),
+            Filter::Optional(inner) => self.eval_or_empty(inner, input),
+            Filter::Pipe(left, right) => {
+                let mut out = Vec::new();
+                for v in self.eval(left, input)? {
+                    out.extend(self.eval(right, &v)?);
+                }
+                Ok(out)
+            }
+            Filter::Comma(left, right) => {
+                let mut out = self.eval(left, input)?;
+                out.extend(self.eval(right, input)?);
+                Ok(out)
+            }
+            Filter::Binary(op, left, right) => {
+                let mut out = Vec::new();
+                for r in self.eval(right, input)? {
+                    for l in self.eval(left, input)? {
+                        out.push(binary(*op, &l, &r)?);
+                    }
+                }
+                Ok(out)
+            }
+            Filter::And(left, right) => {
+                let mut out = Vec::new();
+                for l in self.eval(left, input)? {
+                    if !truthy(&l) {
+                        out.push(Value::Bool(false));
+                        continue;
+                    }
+                    for r in self.eval(right, input)? {
+                        out.push(Value::Bool(truthy(&r)));
+                    }
+                }
+                Ok(out)
+            }
+            Filter::Or(left, right) => {
+                let mut out = Vec::new();
+                for l in self.eval(left, input)? {
+                    if truthy(&l) {
+                        out.push(Value::Bool(true));
+                        continue;
+                    }
+                    for r in self.eval(right, input)? {
+                        out.push(Value::Bool(truthy(&r)));
+                    }
+                }
+                Ok(out)
+            }
+            Filter::Alternative(left, right) => {
+                let values: Vec<Value> = self
+                    .eval_or_empty(left, input)?
+                    .into_iter()
+                    .filter(truthy)
+                    .collect();
+                if values.is_empty() {
+                    self.eval(right, input)
+                } else {
+                    Ok(values)
+                }
+            }
+            Filter::Neg(inner) => self
+                .eval(inner, input)?
+                .into_iter()
+                .map(|v| match v {
+                    Value::Number(n) => Ok(number_value(-n.as_f64().unwrap_or(0.0))),
+                    other => Err(format!("{} 不能取负", type_name(&other))),
+                })
+                .collect(),
+            Filter::Array(inner) => Ok(vec![Value::Array(match inner {
+                Some(inner) => self.eval(inner, input)?,
+                None => Vec::new(),
+            })]),
+            Filter::Object(entries) => {
+                let mut results = vec![Map::new()];
+                for (key, value) in entries {
+                    let mut next = Vec::new();
+                    for k in self.eval(key, input)? {
+                        let Value::String(k) = k else {
+                            return Err(format!("对象的键必须是字符串，而不是 {}", type_name(&k)));
+                        };
+                        for v in self.eval(value, input)? {
+                            for partial in &results {
+                                let mut map = partial.clone();
+                                map.insert(k.clone(), v.clone());
+                                next.push(map);
+                            }
+                        }
+                    }
+                    results = next;
+                }
+                Ok(results.into_iter().map(Value::Object).collect())
+            }
+            Filter::If(branches, otherwise) => self.eval_if(branches, otherwise.as_deref(), input),
+            Filter::Call(name, args) => self.call(name, args, input),
+            Filter::Template(parts) => {
+                let mut results = vec![String::new()];
+                for part in parts {
+                    match part {
+                        TemplatePart::Text(text) => results.iter_mut().for_each(|r| r.push_str(text)),
+                        TemplatePart::Expr(f) => {
+                            let values = self.eval(f, input)?;
+                            results = values
+                                .iter()
+                                .flat_map(|v| results.iter().map(move |r| format!("{}{}", r, value_to_text(v))))
+                                .collect();
+                        }
+                    }
+                }
+                Ok(results.into_iter().map(Value::String).collect())
+            }
+        }
+    }
+
+    fn eval_if(&self, branches: &[(Filter, Filter)], otherwise: Option<&Filter>, input: &Value) -> Outputs {
+        let Some(((cond, body), rest)) = branches.split_first() else {
+            return match otherwise {
+                Some(f) => self.eval(f, input),
+                None => Ok(vec![input.clone()]),
+            };
+        };
+        let mut out = Vec::new();
+        for c in self.eval(cond, input)? {
+            if truthy(&c) {
+                out.extend(self.eval(body, input)?);
+            } else {
+                out.extend(self.eval_if(rest, otherwise, input)?);
+            }
+        }
+        Ok(out)
+    }
+
+    fn flat_map<F>(&self, target: &Filter, input: &Value, mut f: F) -> Outputs
+    where
+        F: FnMut(Value) -> Outputs,
+    {
+        let mut out = Vec::new();
+        for v in self.eval(target, input)? {
+            out.extend(f(v)?);
+        }
+        Ok(out)
+    }
+
+    /// 对参数求值并取第一个输出
+    fn arg(&self, args: &[Filter], i: usize, input: &Value) -> Result<Value, String> {
+        Ok(self.eval(&args[i], input)?.into_iter().next().unwrap_or(Value::Null))
+    }
+
+    fn call(&self, name: &str, args: &[Filter], input: &Value) -> Outputs {
+        let arity = args.len();
+        let one = |v: Value| Ok(vec![v]);
+        match (name, arity) {
+            ("empty", 0) => Ok(Vec::new()),
+            ("not", 0) => one(Value::Bool(!truthy(input))),
+            ("select", 1) => {
+                let keep = self.eval(&args[0], input)?.iter().any(truthy);
+                Ok(if keep { vec![input.clone()] } else { Vec::new() })
+            }
+            ("map", 1) => match input {
+                Value::Array(items) => {
+                    let mut out = Vec::new();
+                    for item in items {
+                        out.extend(self.eval(&args[0], item)?);
+                    }
+                    one(Value::Array(out))
+                }
+                other => Err(format!("map 需要数组，而不是 {}", type_name(other))),
+            },
+            ("map_values", 1) => match input {
+                Value::Object(map) => {
+                    let mut out = Map::new();
+                    for (k, v) in map {
+                        if let Some(first) = self.eval(&args[0], v)?.into_iter().next() {
+                            out.insert(k.clone(), first);
+                        }
+                    }
+                    one(Value::Object(out))
+                }
+                _ => self.call("map", args, input),
+            },
+            ("length", 0) => one(match input {
+                Value::Null => Value::from(0),
+                Value::Bool(_) => return Err("布尔值没有长度".to_string()),
+                Value::Number(n) => number_value(n.as_f64().unwrap_or(0.0).abs()),
+                Value::String(s) => Value::from(s.chars().count()),
+                Value::Array(items) => Value::from(items.len()),
+                Value::Object(map) => Value::from(map.len()),
+            }),
+            ("keys", 0) | ("keys_unsorted", 0) => match input {
+                Value::Object(map) => {
+                    let mut keys: Vec<Value> = map.keys().map(|k| Value::String(k.clone())).collect();
+                    if name == "keys" {
+                        keys.sort_by(jq_cmp);
+                    }
+                    one(Value::Array(keys))
+                }
+                Value::Array(items) => one(Value::Array((0..items.len()).map(Value::from).collect())),
+                other => Err(format!("{} 没有键", type_name(other))),
+            },
+            ("values", 0) => Ok(if input.is_null() { Vec::new() } else { vec![input.clone()] }),
+            ("has", 1) => {
+                let key = self.arg(args, 0, input)?;
+                one(Value::Bool(match (input, &key) {
+                    (Value::Object(map), Value::String(k)) => map.contains_key(k),
+                    (Value::Array(items), Value::Number(n)) => {
+                        n.as_f64().is_some_and(|i| i >= 0.0 && (i as usize) < items.len())
+                    }
+                    _ => return Err(format!("无法检查 {} 是否有 {} 类型的键", type_name(input), type_name(&key))),
+                }))
+            }
+            ("type", 0) => one(Value::String(type_name(input).to_string())),
+            ("first", 0) => Ok(index_value(input, &Value::from(0)).into_iter().collect()),
+            ("last", 0) => Ok(index_value(input, &Value::from(-1)).into_iter().collect()),
+            ("first", 1) => Ok(self.eval(&args[0], input)?.into_iter().take(1).collect()),
+            ("reverse", 0) => match input {
+                Value::Array(items) => one(Value::Array(items.iter().rev().cloned().collect())),
+                Value::String(s) => one(Value::String(s.chars().rev().collect())),
+                Value::Null => one(Value::Array(Vec::new())),
+                other => Err(format!("无法反转 {}", type_name(other))),
+            },
+            ("sort", 0) | ("unique", 0) | ("min", 0) | ("max", 0) => {
+                let mut items = as_array(input, name)?.clone();
+                items.sort_by(jq_cmp);
+                match name {
+                    "unique" => {
+                        items.dedup_by(|a, b| jq_cmp(a, b) == Ordering::Equal);
+                        one(Value::Array(items))
+                    }
+                    "min" => one(items.first().cloned().unwrap_or(Value::Null)),
+                    "max" => one(items.last().cloned().unwrap_or(Value::Null)),
+                    _ => one(Value::Array(items)),
+                }
+            }
+            ("sort_by", 1) | ("unique_by", 1) | ("group_by", 1) | ("min_by", 1) | ("max_by", 1) => {
+                let mut keyed = Vec::new();
+                for item in as_array(input, name)? {
+                    keyed.push((Value::Array(self.eval(&args[0], item)?), item.clone()));
+                }
+                keyed.sort_by(|a, b| jq_cmp(&a.0, &b.0));
+                match name {
+                    "sort_by" => one(Value::Array(keyed.into_iter().map(|(_, v)| v).collect())),
+                    "min_by" => one(keyed.into_iter().next().map_or(Value::Null, |(_, v)| v)),
+                    "max_by" => one(keyed.into_iter().last().map_or(Value::Null, |(_, v)| v)),
+                    _ => {
+                        let mut groups: Vec<(Value, Vec<Value>)> = Vec::new();
+                        for (key, item) in keyed {
+                            match groups.last_mut() {
+                                Some((k, group)) if jq_cmp(k, &key) == Ordering::Equal => group.push(item),
+                                _ => groups.push((key, vec![item])),
+                            }
+                        }
+                        if name == "unique_by" {
+                            one(Value::Array(groups.into_iter().filter_map(|(_, mut g)| g.drain(..).next()).collect()))
+                        } else {
+                            one(Value::Array(groups.into_iter().map(|(_, g)| Value::Array(g)).collect()))
+                        }
+                    }
+                }
+            }
+            ("add", 0) => {
+                let mut acc = Value::Null;
+                for item in as_array(input, name)? {
+                    acc = binary(BinOp::Add, &acc, item)?;
+                }
+                one(acc)
+            }
+            ("to_entries", 0) => match input {
+                Value::Object(map) => one(Value::Array(
+                    map.iter()
+                        .map(|(k, v)| {
+                            let mut entry = Map::new();
+                            entry.insert("key".to_string(), Value::String(k.clone()));
+                            entry.insert("value".to_string(), v.clone());
+                            Value::Object(entry)
+                        })
+                        .collect(),
+                )),
+                other => Err(format!("to_entries 需要对象，而不是 {}", type_name(other))),
+            },
+            ("from_entries", 0) => {
+                let mut map = Map::new();
+                for entry in as_array(input, name)? {
+                    let key = ["key", "k", "name", "Name", "Key"]
+                        .iter()
+                        .find_map(|k| entry.get(k).filter(|v| !v.is_null()));
+                    let key = match key {
+                        Some(Value::String(s)) => s.clone(),
+                        Some(other) => value_to_text(other),
+                        None => return Err("from_entries 的元素缺少 key".to_string()),
+                    };
+                    let value = ["value", "v", "Value"]
+                        .iter()
+                        .find_map(|k| entry.get(k))
+                        .cloned()
+                        .unwrap_or(Value::Null);
+                    map.insert(key, value);
+                }
+                one(Value::Object(map))
+            }
+            ("tostring", 0) => one(Value::String(value_to_text(input))),
+            ("tojson", 0) => one(Value::String(input.to_string())),
+            ("fromjson", 0) => match input {
+                Value::String(s) => serde_json::from_str(s).map(|v| vec![v]).map_err(|e| e.to_string()),
+                other => Err(format!("fromjson 需要字符串，而不是 {}", type_name(other))),
+            },
+            ("tonumber", 0) => match input {
+                Value::Number(_) => one(input.clone()),
+                Value::String(s) => serde_json::from_str::<Number>(s.trim())
+                    .map(|n| vec![Value::Number(n)])
+                    .map_err(|_| format!("无法将 \"{}\" 转换为数字", s)),
+                other => Err(format!("无法将 {} 转换为数字", type_name(other))),
+            },
+            ("ascii_downcase", 0) | ("ascii_upcase", 0) => match input {
+                Value::String(s) => one(Value::String(if name == "ascii_downcase" {
+                    s.to_ascii_lowercase()
+                } else {
+                    s.to_ascii_uppercase()
+                })),
+                other => Err(format!("{} 需要字符串，而不是 {}", name, type_name(other))),
+            },
+            ("test", 1) | ("startswith", 1) | ("endswith", 1) | ("split", 1) | ("ltrimstr", 1) | ("rtrimstr", 1) => {
+                let Value::String(s) = input else {
+                    return Err(format!("{} 需要字符串，而不是 {}", name, type_name(input)));
+                };
+                let Value::String(arg) = self.arg(args, 0, input)? else {
+                    return Err(format!("{} 的参数必须是字符串", name));
+                };
+                one(match name {
+                    "test" => Value::Bool(self.regex(&arg)?.is_match(s)),
+                    "startswith" => Value::Bool(s.starts_with(&arg)),
+                    "endswith" => Value::Bool(s.ends_with(&arg)),
+                    "ltrimstr" => Value::String(s.strip_prefix(&arg).unwrap_or(s).to_string()),
+                    "rtrimstr" => Value::String(s.strip_suffix(&arg).unwrap_or(s).to_string()),
+                    _ => Value::Array(s.split(arg.as_str()).map(|p| Value::String(p.to_string())).collect()),
+                })
+            }
+            ("join", 1) => {
+                let sep = value_to_text(&self.arg(args, 0, input)?);
+                let parts: Vec<String> = as_array(input, name)?
+                    .iter()
+                    .map(|v| if v.is_null() { String::new() } else { value_to_text(v) })
+                    .collect();
+                one(Value::String(parts.join(&sep)))
+            }
+            ("contains", 1) => {
+                let other = self.arg(args, 0, input)?;
+                one(Value::Bool(contains(input, &other)))
+            }
+            ("flatten", 0) => {
+                let mut out = Vec::new();
+                flatten_into(as_array(input, name)?, &mut out);
+                one(Value::Array(out))
+            }
+            ("range", 1) => {
+                let n = self.arg(args, 0, input)?.as_f64().unwrap_or(0.0).max(0.0).ceil();
+                if n > MAX_RANGE_LEN as f64 {
+                    return Err(format!("range 最多生成 {} 个值", MAX_RANGE_LEN));
+                }
+                let mut out = Vec::with_capacity(n as usize);
+                for i in 0..n as usize {
+                    if i % 4096 == 0 && self.token.is_cancelled() {
+                        return Err(super::ERR_CANCELLED.to_string());
+                    }
+                    out.push(Value::from(i));
+                }
+                Ok(out)
+            }
+            ("paths", 0) => {
+                let mut out = Vec::new();
+                collect_paths(input, &mut Vec::new(), &mut out);
+                Ok(out)
+            }
+            ("numbers", 0) | ("strings", 0) | ("booleans", 0) | ("nulls", 0) | ("arrays", 0) | ("objects", 0)
+            | ("iterables", 0) | ("scalars", 0) => {
+                let keep = match name {
+                    "iterables" => input.is_array() || input.is_object(),
+                    "scalars" => !(input.is_array() || input.is_object()),
+                    _ => name.trim_end_matches('s') == type_name(input),
+                };
+                Ok(if keep { vec![input.clone()] } else { Vec::new() })
+            }
+            ("error", 1) => Err(value_to_text(&self.arg(args, 0, input)?)),
+            _ => Err(format!("未知函数 {}/{}", name, arity)),
+        }
+    }
+}
+
+fn as_array<'a>(value: &'a Value, func: &str) -> Result<&'a Vec<Value>, String> {
+    match value {
+        Value::Array(items) => Ok(items),
+        other => Err(format!("{} 需要数组，而不是 {}", func, type_name(other))),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// 将f64转换为JSON数字，整数值输出为整数形式
+fn number_value(x: f64) -> Value {
+    if x.fract() == 0.0 && x.abs() < 9_007_199_254_740_992.0 {
+        Value::from(x as i64)
+    } else {
+        Number::from_f64(x).map_or(Value::Null, Value::Number)
+    }
+}
+
+fn index_value(value: &Value, index: &Value) -> Result<Value, String> {
+    match (value, index) {
+        (Value::Object(map), Value::String(k)) => Ok(map.get(k).cloned().unwrap_or(Value::Null)),
+        (Value::Array(items), Value::Number(n)) => {
+            let i = n.as_f64().unwrap_or(0.0).floor() as i64;
+            let i = if i < 0 { items.len() as i64 + i } else { i };
+            Ok(usize::try_from(i).ok().and_then(|i| items.get(i)).cloned().unwrap_or(Value::Null))
+        }
+        (Value::Null, Value::String(_) | Value::Number(_)) => Ok(Value::Null),
+        (v, i) => Err(format!("无法用 {} 索引 {}", type_name(i), type_name(v))),
+    }
+}
+
+fn slice_value(value: &Value, start: Option<i64>, end: Option<i64>) -> Result<Value, String> {
+    let clamp = |i: i64, len: i64| if i < 0 { (len + i).max(0) } else { i.min(len) };
+    match value {
+        Value::Array(items) => {
+            let len = items.len() as i64;
+            let (s, e) = (clamp(start.unwrap_or(0), len), clamp(end.unwrap_or(len), len));
+            Ok(Value::Array(if s < e { items[s as usize..e as usize].to_vec() } else { Vec::new() }))
+        }
+        Value::String(text) => {
+            let chars: Vec<char> = text.chars().collect();
+            let len = chars.len() as i64;
+            let (s, e) = (clamp(start.unwrap_or(0), len), clamp(end.unwrap_or(len), len));
+            Ok(Value::String(if s < e { chars[s as usize..e as usize].iter().collect() } else { String::new() }))
+        }
+        Value::Null => Ok(Value::Null),
+        other => Err(format!("无法对 {} 切片", type_name(other))),
+    }
+}
+
+fn binary(op: BinOp, l: &Value, r: &Value) -> Result<Value, String> {
+    let num = |v: &Value| v.as_f64().unwrap_or(0.0);
+    match op {
+        BinOp::Eq => return Ok(Value::Bool(json_equal(l, r))),
+        BinOp::Ne => return Ok(Value::Bool(!json_equal(l, r))),
+        BinOp::Lt => return Ok(Value::Bool(jq_cmp(l, r) == Ordering::Less)),
+        BinOp::Le => return Ok(Value::Bool(jq_cmp(l, r) != Ordering::Greater)),
+        BinOp::Gt => return Ok(Value::Bool(jq_cmp(l, r) == Ordering::Greater)),
+        BinOp::Ge => return Ok(Value::Bool(jq_cmp(l, r) != Ordering::Less)),
+        _ => {}
+    }
+    match (op, l, r) {
+        (BinOp::Add, Value::Null, x) | (BinOp::Add, x, Value::Null) => Ok(x.clone()),
+        (BinOp::Add, Value::Number(a), Value::Number(b)) => {
+            match (a.as_i64(), b.as_i64()) {
+                (Some(x), Some(y)) if x.checked_add(y).is_some() => Ok(Value::from(x + y)),
+                _ => Ok(number_value(num(l) + num(r))),
+            }
+        }
+        (BinOp::Add, Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
+        (BinOp::Add, Value::Array(a), Value::Array(b)) => Ok(Value::Array(a.iter().chain(b).cloned().collect())),
+        (BinOp::Add, Value::Object(a), Value::Object(b)) => {
+            let mut map = a.clone();
+            map.extend(b.clone());
+            Ok(Value::Object(map))
+        }
+        (BinOp::Sub, Value::Number(_), Value::Number(_)) => Ok(number_value(num(l) - num(r))),
+        (BinOp::Sub, Value::Array(a), Value::Array(b)) => Ok(Value::Array(
+            a.iter().filter(|x| !b.iter().any(|y| json_equal(x, y))).cloned().collect(),
+        )),
+        (BinOp::Mul, Value::Number(_), Value::Number(_)) => Ok(number_value(num(l) * num(r))),
+        (BinOp::Div, Value::Number(_), Value::Number(_)) => {
+            if num(r) == 0.0 {
+                Err("除数不能为零".to_string())
+            } else {
+                Ok(number_value(num(l) / num(r)))
+            }
+        }
+        (BinOp::Div, Value::String(a), Value::String(b)) => {
+            Ok(Value::Array(a.split(b.as_str()).map(|p| Value::String(p.to_string())).collect()))
+        }
+        (BinOp::Rem, Value::Number(_), Value::Number(_)) => {
+            let divisor = num(r) as i64;
+            if divisor == 0 {
+                return Err("除数不能为零".to_string());
+            }
+            // i64::MIN % -1 溢出
+            let dividend = num(l) as i64;
+            dividend
+                .checked_rem(divisor)
+                .map(Value::from)
+                .ok_or_else(|| format!("{} % {} 超出整数范围", dividend, divisor))
+        }
+        _ => Err(format!("{} 和 {} 不能进行该运算", type_name(l), type_name(r))),
+    }
+}
+
+/// jq的排序规则：null < false < true < 数字 < 字符串 < 数组 < 对象
+fn jq_cmp(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => number_cmp(x, y).unwrap_or(Ordering::Equal),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => {
+            for (p, q) in x.iter().zip(y) {
+                let ord = jq_cmp(p, q);
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            x.len().cmp(&y.len())
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            let mut xk: Vec<&String> = x.keys().collect();
+            let mut yk: Vec<&String> = y.keys().collect();
+            xk.sort();
+            yk.sort();
+            xk.cmp(&yk).then_with(|| {
+                xk.iter()
+                    .map(|k| jq_cmp(&x[k.as_str()], &y[k.as_str()]))
+                    .find(|o| *o != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn contains(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(x), Value::String(y)) => x.contains(y.as_str()),
+        (Value::Array(x), Value::Array(y)) => y.iter().all(|yv| x.iter().any(|xv| contains(xv, yv))),
+        (Value::Object(x), Value::Object(y)) => {
+            y.iter().all(|(k, yv)| x.get(k).is_some_and(|xv| contains(xv, yv)))
+        }
+        _ => json_equal(a, b),
+    }
+}
+
+fn flatten_into(items: &[Value], out: &mut Vec<Value>) {
+    for item in items {
+        match item {
+            Value::Array(inner) => flatten_into(inner, out),
+            other => out.push(other.clone()),
+        }
+    }
+}
+
+fn collect_paths(value: &Value, prefix: &mut Vec<Value>, out: &mut Vec<Value>) {
+    let children: Vec<(Value, &Value)> = match value {
+        Value::Array(items) => items.iter().enumerate().map(|(i, v)| (Value::from(i), v)).collect(),
+        Value::Object(map) => map.iter().map(|(k, v)| (Value::String(k.clone()), v)).collect(),
+        _ => return,
+    };
+    for (key, child) in children {
+        prefix.push(key);
+        out.push(Value::Array(prefix.clone()));
+        collect_paths(child, prefix, out);
+        prefix.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(program: &str, input: &str) -> Result<Vec<Value>, String> {
+        let input: Value = serde_json::from_str(input).unwrap();
+        query(program, &input, &CancelToken::new()).map(Option::unwrap)
+    }
+
+    fn outputs(program: &str, input: &str) -> String {
+        match run(program, input) {
+            Ok(values) => values.iter().map(Value::to_string).collect::<Vec<_>>().join(" "),
+            Err(e) => panic!("{} 执行失败: {}", program, e),
+        }
+    }
+
+    #[test]
+    fn evaluates_programs() {
+        let doc = r#"{"a":{"b":[1,2,3]},"s":"Hello","n":null,"xs":[{"k":"x","v":2},{"k":"y","v":1}]}"#;
+        let cases = [
+            (".a.b", "[1,2,3]"),
+            (".a.b[0], .a.b[-1]", "1 3"),
+            (".a.b[]", "1 2 3"),
+            (".a.b[1:]", "[2,3]"),
+            (".a.b[:-1]", "[1,2]"),
+            (".a.b[5:]", "[]"),
+            (".s[1:3]", r#""el""#),
+            (".missing.deeper", "null"),
+            (".n[0]", "null"),
+            ("[.. | numbers]", "[1,2,3,2,1]"),
+            (".xs | map(.v) | add", "3"),
+            (".xs | sort_by(.v) | .[0].k", r#""y""#),
+            ("[.xs[] | select(.v > 1) | .k]", r#"["x"]"#),
+            ("{k: .s, len: (.s | length)}", r#"{"k":"Hello","len":5}"#),
+            (".missing // \"d\"", r#""d""#),
+            ("if .n then 1 elif .s then 2 else 3 end", "2"),
+            (r#""\(.s)!""#, r#""Hello!""#),
+            ("1, 2 | . * 3", "3 6"),
+            ("-(.a.b[0]) + 10 / 4 - 7 % 4", "-1.5"),
+            ("[.s | test(\"^H\"), startswith(\"x\")]", "[true,false]"),
+            (".a | keys", r#"["b"]"#),
+            ("[range(3)], [range(-1)]", "[0,1,2] []"),
+            ("[.xs[] | .k] | join(\",\")", r#""x,y""#),
+            (".s | .x?", ""),
+            ("[.[] | .b?]", "[[1,2,3],null]"),
+        ];
+        for (program, expected) in cases {
+            assert_eq!(outputs(program, doc), expected, "{}", program);
+        }
+    }
+
+    #[test]
+    fn reports_errors() {
+        let cases = [
+            (".[", "jq语法错误"),
+            (".a |", "jq语法错误"),
+            ("{a:}", "jq语法错误"),
+            ("nosuch(1)", "未知函数 nosuch/1"),
+            (".s.x", "无法用字符串 \"x\" 索引 string"),
+            (".s | map(.)", "map 需要数组"),
+            ("1 / 0", "除数不能为零"),
+            ("1 % 0", "除数不能为零"),
+            ("{} - 1", "object 和 number 不能进行该运算"),
+            (".s | test(\"(\")", "regex parse error"),
+            ("range(1e12)", "range 最多生成"),
+            ("error(\"boom\")", "boom"),
+        ];
+        for (program, expected) in cases {
+            let error = run(program, r#"{"s":"x"}"#).expect_err(program);
+            assert!(error.contains(expected), "{} => {}", program, error);
+        }
+    }
+
+    #[test]
+    fn optional_does_not_swallow_cancellation() {
+        let token = CancelToken::new();
+        let eval = Evaluator {
+            token: &token,
+            regexes: RefCell::new(HashMap::new()),
+        };
+        let filter = Parser::new(".a").parse_program().unwrap();
+        assert_eq!(eval.eval_or_empty(&filter, &Value::from(1)), Ok(Vec::new()));
+        token.cancel();
+        assert!(eval.eval_or_empty(&filter, &Value::Null).is_err());
+        assert!(query(".a? // 1", &Value::Null, &token).unwrap().is_none());
+    }
+}
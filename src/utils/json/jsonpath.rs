@@ -0,0 +1,876 @@
+use super::relaxed::push_pointer_segment;
+use super::CancelToken;
+use regex::Regex;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+/// 解析并执行JSONPath查询（RFC 9535），返回匹配的节点列表；任务被取消时返回Ok(None)
+///
+/// 支持名称、通配符、索引、切片、联合选择器，后代段 `..`，
+/// 以及带比较、逻辑运算和 length/count/match/search/value 函数的过滤器 `?()`
+pub fn query<'v>(
+    path: &str,
+    root: &'v Value,
+    token: &CancelToken,
+) -> Result<Option<Vec<&'v Value>>, String> {
+    let query = Parser::new(path).parse_root()?;
+    let eval = Evaluator {
+        root,
+        token,
+        regexes: RefCell::new(HashMap::new()),
+    };
+    let nodes = eval.select(&query, root);
+    if token.is_cancelled() {
+        return Ok(None);
+    }
+    Ok(Some(nodes))
+}
+
+/// 执行JSONPath查询，按文档顺序返回匹配节点的JSON Pointer；任务被取消时返回Ok(None)
+pub(crate) fn query_pointers(path: &str, root: &Value, token: &CancelToken) -> Result<Option<Vec<String>>, String> {
+    let Some(nodes) = query(path, root, token)? else {
+        return Ok(None);
+    };
+    // 查询结果都是文档内节点的引用，按地址反查其位置
+    let targets: HashSet<*const Value> = nodes.into_iter().map(|node| node as *const Value).collect();
+    let mut pointers = Vec::new();
+    collect_pointers(root, &targets, &mut String::new(), &mut pointers);
+    Ok(Some(pointers))
+}
+
+fn collect_pointers(value: &Value, targets: &HashSet<*const Value>, pointer: &mut String, out: &mut Vec<String>) {
+    if targets.contains(&(value as *const Value)) {
+        out.push(pointer.clone());
+    }
+    let len = pointer.len();
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                push_pointer_segment(pointer, k);
+                collect_pointers(v, targets, pointer, out);
+                pointer.truncate(len);
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                push_pointer_segment(pointer, &i.to_string());
+                collect_pointers(v, targets, pointer, out);
+                pointer.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+struct Query {
+    absolute: bool,
+    segments: Vec<Segment>,
+}
+
+struct Segment {
+    descendant: bool,
+    selectors: Vec<Selector>,
+}
+
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Filter(Expr),
+}
+
+enum Expr {
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Not(Box<Expr>),
+    Compare(Comparable, CmpOp, Comparable),
+    Exists(Query),
+    Func(FuncCall),
+}
+
+enum Comparable {
+    Literal(Value),
+    Query(Query),
+    Func(FuncCall),
+}
+
+#[derive(Clone, Copy)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+struct FuncCall {
+    name: String,
+    args: Vec<FuncArg>,
+}
+
+enum FuncArg {
+    Literal(Value),
+    Query(Query),
+    Func(FuncCall),
+}
+
+/// 函数的返回值类型
+enum FuncResult {
+    Value(Option<Value>),
+    Logical(bool),
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("JSONPath语法错误（位置 {}）: {}", self.pos + 1, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), String> {
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("此处应为 '{}'", s)))
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.bump();
+        }
+    }
+
+    fn parse_root(&mut self) -> Result<Query, String> {
+        self.skip_blank();
+        if !self.eat("$") {
+            return Err(self.error("JSONPath必须以 $ 开头"));
+        }
+        let segments = self.parse_segments()?;
+        self.skip_blank();
+        if self.pos < self.src.len() {
+            return Err(self.error("无法识别的内容"));
+        }
+        Ok(Query {
+            absolute: true,
+            segments,
+        })
+    }
+
+    fn parse_segments(&mut self) -> Result<Vec<Segment>, String> {
+        let mut segments = Vec::new();
+        loop {
+            let save = self.pos;
+            self.skip_blank();
+            if self.eat("..") {
+                let selectors = if self.peek() == Some('[') {
+                    self.parse_bracket()?
+                } else if self.eat("*") {
+                    vec![Selector::Wildcard]
+                } else {
+                    vec![Selector::Name(self.parse_member_name()?)]
+                };
+                segments.push(Segment {
+                    descendant: true,
+                    selectors,
+                });
+            } else if self.eat(".") {
+                let selector = if self.eat("*") {
+                    Selector::Wildcard
+                } else {
+                    Selector::Name(self.parse_member_name()?)
+                };
+                segments.push(Segment {
+                    descendant: false,
+                    selectors: vec![selector],
+                });
+            } else if self.peek() == Some('[') {
+                segments.push(Segment {
+                    descendant: false,
+                    selectors: self.parse_bracket()?,
+                });
+            } else {
+                self.pos = save;
+                return Ok(segments);
+            }
+        }
+    }
+
+    fn parse_member_name(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' || !c.is_ascii() => {}
+            _ => return Err(self.error("此处应为成员名")),
+        }
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        Ok(self.src[start..self.pos].to_string())
+    }
+
+    fn parse_bracket(&mut self) -> Result<Vec<Selector>, String> {
+        self.expect("[")?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_blank();
+            selectors.push(self.parse_selector()?);
+            self.skip_blank();
+            if self.eat("]") {
+                return Ok(selectors);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn parse_selector(&mut self) -> Result<Selector, String> {
+        match self.peek() {
+            Some('\'' | '"') => Ok(Selector::Name(self.parse_string()?)),
+            Some('*') => {
+                self.bump();
+                Ok(Selector::Wildcard)
+            }
+            Some('?') => {
+                self.bump();
+                self.skip_blank();
+                Ok(Selector::Filter(self.parse_or()?))
+            }
+            Some(c) if c == '-' || c == ':' || c.is_ascii_digit() => {
+                let start = if self.peek() == Some(':') { None } else { Some(self.parse_int()?) };
+                self.skip_blank();
+                if !self.eat(":") {
+                    return start
+                        .map(Selector::Index)
+                        .ok_or_else(|| self.error("此处应为索引"));
+                }
+                self.skip_blank();
+                let end = self.parse_optional_int()?;
+                self.skip_blank();
+                let step = if self.eat(":") {
+                    self.skip_blank();
+                    self.parse_optional_int()?
+                } else {
+                    None
+                };
+                Ok(Selector::Slice(start, end, step))
+            }
+            _ => Err(self.error("无效的选择器")),
+        }
+    }
+
+    fn parse_optional_int(&mut self) -> Result<Option<i64>, String> {
+        match self.peek() {
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_int().map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn parse_int(&mut self) -> Result<i64, String> {
+        let start = self.pos;
+        self.eat("-");
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        let text = &self.src[start..self.pos];
+        let digits = text.trim_start_matches('-');
+        if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) || text == "-0" {
+            return Err(self.error("无效的整数"));
+        }
+        text.parse().map_err(|_| self.error("整数超出范围"))
+    }
+
+    /// 读取 \u 后恰好4位的十六进制数字
+    fn parse_hex4(&mut self) -> Result<u32, String> {
+        let code = self
+            .src
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("无效的 \\u 转义"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        let quote = self.bump().unwrap_or('"');
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("字符串缺少结束引号")),
+                Some(c) if c == quote => return Ok(out),
+                Some('\\') => match self.bump() {
+                    Some('b') => out.push('\u{08}'),
+                    Some('f') => out.push('\u{0C}'),
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('u') => {
+                        let code = self.parse_hex4()?;
+                        let code = if (0xD800..0xDC00).contains(&code) {
+                            // 高代理项后必须紧跟低代理项
+                            let low = if self.eat("\\u") { self.parse_hex4()? } else { 0 };
+                            if !(0xDC00..0xE000).contains(&low) {
+                                return Err(self.error("无效的 \\u 转义：高代理项后缺少低代理项"));
+                            }
+                            0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00)
+                        } else {
+                            code
+                        };
+                        out.push(char::from_u32(code).ok_or_else(|| self.error("无效的 \\u 转义"))?);
+                    }
+                    Some(c @ ('\\' | '/' | '\'' | '"')) => out.push(c),
+                    _ => return Err(self.error("无效的转义序列")),
+                },
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut items = vec![self.parse_and()?];
+        loop {
+            self.skip_blank();
+            if !self.eat("||") {
+                break;
+            }
+            self.skip_blank();
+            items.push(self.parse_and()?);
+        }
+        Ok(if items.len() == 1 { items.pop().unwrap() } else { Expr::Or(items) })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut items = vec![self.parse_basic()?];
+        loop {
+            self.skip_blank();
+            if !self.eat("&&") {
+                break;
+            }
+            self.skip_blank();
+            items.push(self.parse_basic()?);
+        }
+        Ok(if items.len() == 1 { items.pop().unwrap() } else { Expr::And(items) })
+    }
+
+    fn parse_basic(&mut self) -> Result<Expr, String> {
+        self.skip_blank();
+        if self.eat("!") {
+            self.skip_blank();
+            if self.eat("(") {
+                let inner = self.parse_paren_rest()?;
+                return Ok(Expr::Not(Box::new(inner)));
+            }
+            return Ok(Expr::Not(Box::new(self.parse_test()?)));
+        }
+        if self.eat("(") {
+            return self.parse_paren_rest();
+        }
+
+        let left = self.parse_comparable_or_test()?;
+        self.skip_blank();
+        let op = if self.eat("==") {
+            CmpOp::Eq
+        } else if self.eat("!=") {
+            CmpOp::Ne
+        } else if self.eat("<=") {
+            CmpOp::Le
+        } else if self.eat(">=") {
+            CmpOp::Ge
+        } else if self.eat("<") {
+            CmpOp::Lt
+        } else if self.eat(">") {
+            CmpOp::Gt
+        } else {
+            return match left {
+                Comparable::Query(q) => Ok(Expr::Exists(q)),
+                Comparable::Func(f) => Ok(Expr::Func(f)),
+                Comparable::Literal(_) => Err(self.error("字面量不能单独作为过滤条件")),
+            };
+        };
+        self.skip_blank();
+        let right = self.parse_comparable_or_test()?;
+        for side in [&left, &right] {
+            if let Comparable::Query(q) = side {
+                if !is_singular(q) {
+                    return Err(self.error("比较运算只能使用单值查询"));
+                }
+            }
+        }
+        Ok(Expr::Compare(left, op, right))
+    }
+
+    fn parse_paren_rest(&mut self) -> Result<Expr, String> {
+        let inner = self.parse_or()?;
+        self.skip_blank();
+        self.expect(")")?;
+        Ok(inner)
+    }
+
+    fn parse_test(&mut self) -> Result<Expr, String> {
+        match self.parse_comparable_or_test()? {
+            Comparable::Query(q) => Ok(Expr::Exists(q)),
+            Comparable::Func(f) => Ok(Expr::Func(f)),
+            Comparable::Literal(_) => Err(self.error("字面量不能单独作为过滤条件")),
+        }
+    }
+
+    fn parse_comparable_or_test(&mut self) -> Result<Comparable, String> {
+        match self.peek() {
+            Some('@') | Some('$') => {
+                let absolute = self.bump() == Some('$');
+                let segments = self.parse_segments()?;
+                Ok(Comparable::Query(Query { absolute, segments }))
+            }
+            Some('\'' | '"') => Ok(Comparable::Literal(Value::String(self.parse_string()?))),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number().map(Comparable::Literal),
+            Some(c) if c.is_ascii_lowercase() => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+                    self.bump();
+                }
+                let name = &self.src[start..self.pos];
+                match name {
+                    "true" => Ok(Comparable::Literal(Value::Bool(true))),
+                    "false" => Ok(Comparable::Literal(Value::Bool(false))),
+                    "null" => Ok(Comparable::Literal(Value::Null)),
+                    _ if self.peek() == Some('(') => self.parse_function(name.to_string()).map(Comparable::Func),
+                    _ => {
+                        self.pos = start;
+                        Err(self.error("无法识别的标识符"))
+                    }
+                }
+            }
+            _ => Err(self.error("此处应为查询、字面量或函数")),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        self.eat("-");
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+        {
+            self.bump();
+        }
+        serde_json::from_str::<serde_json::Number>(&self.src[start..self.pos])
+            .map(Value::Number)
+            .map_err(|_| self.error("无效的数字"))
+    }
+
+    fn parse_function(&mut self, name: String) -> Result<FuncCall, String> {
+        let arity = match name.as_str() {
+            "length" | "count" | "value" => 1,
+            "match" | "search" => 2,
+            _ => return Err(self.error(&format!("未知函数 {}()", name))),
+        };
+        self.expect("(")?;
+        let mut args = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.eat(")") {
+                break;
+            }
+            if !args.is_empty() {
+                self.expect(",")?;
+                self.skip_blank();
+            }
+            args.push(self.parse_func_arg()?);
+        }
+        if args.len() != arity {
+            return Err(self.error(&format!("函数 {}() 需要 {} 个参数", name, arity)));
+        }
+        Ok(FuncCall { name, args })
+    }
+
+    fn parse_func_arg(&mut self) -> Result<FuncArg, String> {
+        let save = self.pos;
+        // 内置函数的参数均为值类型，逻辑表达式作为参数属于类型错误
+        let value = self.parse_comparable_or_test();
+        self.skip_blank();
+        if let Ok(value) = value {
+            if matches!(self.peek(), Some(',' | ')')) {
+                return Ok(match value {
+                    Comparable::Literal(v) => FuncArg::Literal(v),
+                    Comparable::Query(q) => FuncArg::Query(q),
+                    Comparable::Func(f) => FuncArg::Func(f),
+                });
+            }
+        }
+        self.pos = save;
+        Err(self.error("函数参数必须是值或查询，不能是逻辑表达式"))
+    }
+}
+
+/// 单值查询：仅由名称和索引选择器组成
+fn is_singular(query: &Query) -> bool {
+    query.segments.iter().all(|seg| {
+        !seg.descendant
+            && seg.selectors.len() == 1
+            && matches!(seg.selectors[0], Selector::Name(_) | Selector::Index(_))
+    })
+}
+
+struct Evaluator<'v, 't> {
+    root: &'v Value,
+    token: &'t CancelToken,
+    /// 已编译的正则缓存，避免 match()/search() 对每个节点重复编译
+    regexes: RefCell<HashMap<String, Option<Regex>>>,
+}
+
+impl<'v> Evaluator<'v, '_> {
+    fn regex(&self, pattern: &str) -> Option<Regex> {
+        self.regexes
+            .borrow_mut()
+            .entry(pattern.to_string())
+            .or_insert_with(|| Regex::new(pattern).ok())
+            .clone()
+    }
+
+    fn select(&self, query: &Query, current: &'v Value) -> Vec<&'v Value> {
+        let start = if query.absolute { self.root } else { current };
+        let mut nodes = vec![start];
+        for segment in &query.segments {
+            if self.token.is_cancelled() {
+                return Vec::new();
+            }
+            let mut next = Vec::new();
+            for node in nodes {
+                if segment.descendant {
+                    let mut stack = vec![node];
+                    let mut descendants = Vec::new();
+                    while let Some(n) = stack.pop() {
+                        descendants.push(n);
+                        match n {
+                            Value::Array(items) => stack.extend(items.iter().rev()),
+                            Value::Object(map) => stack.extend(map.values().rev()),
+                            _ => {}
+                        }
+                    }
+                    for d in descendants {
+                        self.apply_selectors(&segment.selectors, d, &mut next);
+                    }
+                } else {
+                    self.apply_selectors(&segment.selectors, node, &mut next);
+                }
+            }
+            nodes = next;
+        }
+        nodes
+    }
+
+    fn apply_selectors(&self, selectors: &[Selector], node: &'v Value, out: &mut Vec<&'v Value>) {
+        for selector in selectors {
+            match (selector, node) {
+                (Selector::Name(name), Value::Object(map)) => out.extend(map.get(name)),
+                (Selector::Wildcard, Value::Array(items)) => out.extend(items.iter()),
+                (Selector::Wildcard, Value::Object(map)) => out.extend(map.values()),
+                (Selector::Index(i), Value::Array(items)) => {
+                    let len = items.len() as i64;
+                    let idx = if *i < 0 { len + i } else { *i };
+                    if (0..len).contains(&idx) {
+                        out.push(&items[idx as usize]);
+                    }
+                }
+                (Selector::Slice(start, end, step), Value::Array(items)) => {
+                    for idx in slice_indices(items.len() as i64, *start, *end, step.unwrap_or(1)) {
+                        out.push(&items[idx]);
+                    }
+                }
+                (Selector::Filter(expr), Value::Array(items)) => {
+                    out.extend(items.iter().filter(|item| self.test(expr, item)));
+                }
+                (Selector::Filter(expr), Value::Object(map)) => {
+                    out.extend(map.values().filter(|item| self.test(expr, item)));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn test(&self, expr: &Expr, current: &'v Value) -> bool {
+        match expr {
+            Expr::Or(items) => items.iter().any(|e| self.test(e, current)),
+            Expr::And(items) => items.iter().all(|e| self.test(e, current)),
+            Expr::Not(inner) => !self.test(inner, current),
+            Expr::Exists(query) => !self.select(query, current).is_empty(),
+            Expr::Func(func) => match self.call(func, current) {
+                FuncResult::Logical(b) => b,
+                FuncResult::Value(_) => false,
+            },
+            Expr::Compare(left, op, right) => {
+                let left = self.comparable(left, current);
+                let right = self.comparable(right, current);
+                compare(left.as_ref(), *op, right.as_ref())
+            }
+        }
+    }
+
+    fn comparable(&self, comparable: &Comparable, current: &'v Value) -> Option<Value> {
+        match comparable {
+            Comparable::Literal(v) => Some(v.clone()),
+            Comparable::Query(query) => {
+                let nodes = self.select(query, current);
+                (nodes.len() == 1).then(|| nodes[0].clone())
+            }
+            Comparable::Func(func) => match self.call(func, current) {
+                FuncResult::Value(v) => v,
+                _ => None,
+            },
+        }
+    }
+
+    fn arg_value(&self, arg: &FuncArg, current: &'v Value) -> Option<Value> {
+        match arg {
+            FuncArg::Literal(v) => Some(v.clone()),
+            FuncArg::Query(query) => {
+                let nodes = self.select(query, current);
+                (nodes.len() == 1).then(|| nodes[0].clone())
+            }
+            FuncArg::Func(func) => match self.call(func, current) {
+                FuncResult::Value(v) => v,
+                _ => None,
+            },
+        }
+    }
+
+    fn call(&self, func: &FuncCall, current: &'v Value) -> FuncResult {
+        match func.name.as_str() {
+            "length" => FuncResult::Value(match self.arg_value(&func.args[0], current) {
+                Some(Value::String(s)) => Some(Value::from(s.chars().count())),
+                Some(Value::Array(items)) => Some(Value::from(items.len())),
+                Some(Value::Object(map)) => Some(Value::from(map.len())),
+                _ => None,
+            }),
+            "count" => FuncResult::Value(match &func.args[0] {
+                FuncArg::Query(query) => Some(Value::from(self.select(query, current).len())),
+                _ => None,
+            }),
+            "value" => FuncResult::Value(match &func.args[0] {
+                FuncArg::Query(query) => {
+                    let nodes = self.select(query, current);
+                    (nodes.len() == 1).then(|| nodes[0].clone())
+                }
+                _ => None,
+            }),
+            name => {
+                let text = self.arg_value(&func.args[0], current);
+                let pattern = self.arg_value(&func.args[1], current);
+                let (Some(Value::String(text)), Some(Value::String(pattern))) = (text, pattern) else {
+                    return FuncResult::Logical(false);
+                };
+                // match() 要求整体匹配，search() 只需包含匹配
+                let pattern = if name == "match" {
+                    format!("^(?:{})$", pattern)
+                } else {
+                    pattern
+                };
+                FuncResult::Logical(self.regex(&pattern).is_some_and(|re| re.is_match(&text)))
+            }
+        }
+    }
+}
+
+/// 按RFC 9535计算切片选中的下标
+fn slice_indices(len: i64, start: Option<i64>, end: Option<i64>, step: i64) -> Vec<usize> {
+    let normalize = |i: i64| if i >= 0 { i } else { len + i };
+    let mut indices = Vec::new();
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        let mut i = lower;
+        while i < upper {
+            indices.push(i as usize);
+            // 步长很大时下一个下标会溢出，此时已越过上界
+            let Some(next) = i.checked_add(step) else {
+                break;
+            };
+            i = next;
+        }
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = end.map_or(-1, |e| normalize(e).clamp(-1, len - 1));
+        let mut i = upper;
+        while lower < i {
+            indices.push(i as usize);
+            let Some(next) = i.checked_add(step) else {
+                break;
+            };
+            i = next;
+        }
+    }
+    indices
+}
+
+/// 按RFC 9535的比较语义比较两个值，None表示“无值”
+fn compare(left: Option<&Value>, op: CmpOp, right: Option<&Value>) -> bool {
+    match op {
+        CmpOp::Eq => values_equal(left, right),
+        CmpOp::Ne => !values_equal(left, right),
+        CmpOp::Lt => less_than(left, right),
+        CmpOp::Gt => less_than(right, left),
+        CmpOp::Le => less_than(left, right) || values_equal(left, right),
+        CmpOp::Ge => less_than(right, left) || values_equal(left, right),
+    }
+}
+
+fn values_equal(left: Option<&Value>, right: Option<&Value>) -> bool {
+    match (left, right) {
+        (None, None) => true,
+        (Some(a), Some(b)) => json_equal(a, b),
+        _ => false,
+    }
+}
+
+/// 深度比较两个JSON值，数字按数值比较
+pub(crate) fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => number_cmp(x, y) == Some(Ordering::Equal),
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(a, b)| json_equal(a, b))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len() && x.iter().all(|(k, v)| y.get(k).is_some_and(|w| json_equal(v, w)))
+        }
+        _ => a == b,
+    }
+}
+
+fn less_than(left: Option<&Value>, right: Option<&Value>) -> bool {
+    match (left, right) {
+        (Some(Value::Number(x)), Some(Value::Number(y))) => number_cmp(x, y) == Some(Ordering::Less),
+        (Some(Value::String(x)), Some(Value::String(y))) => x < y,
+        _ => false,
+    }
+}
+
+/// 比较两个数字；整数优先按整数比较以免丢失精度
+pub(crate) fn number_cmp(x: &serde_json::Number, y: &serde_json::Number) -> Option<Ordering> {
+    if let (Some(a), Some(b)) = (x.as_i64(), y.as_i64()) {
+        return Some(a.cmp(&b));
+    }
+    if let (Some(a), Some(b)) = (x.as_u64(), y.as_u64()) {
+        return Some(a.cmp(&b));
+    }
+    x.as_f64()?.partial_cmp(&y.as_f64()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = r#"{"store":{"book":[
+        {"category":"reference","author":"Nigel Rees","title":"Sayings","price":8.95},
+        {"category":"fiction","author":"Evelyn Waugh","title":"Sword","price":12.99},
+        {"category":"fiction","author":"Herman","title":"Moby","isbn":"0-553","price":8.99}
+    ],"bicycle":{"color":"red","price":399}},"a":[0,1,2,3,4,5],"o":{"x'y":1,"":2}}"#;
+
+    fn run(path: &str) -> Result<String, String> {
+        let root: Value = serde_json::from_str(DOC).unwrap();
+        let nodes = query(path, &root, &CancelToken::new())?.unwrap();
+        Ok(Value::Array(nodes.into_iter().cloned().collect()).to_string())
+    }
+
+    #[test]
+    fn selects_nodes() {
+        let cases = [
+            ("$.store.book[0].title", r#"["Sayings"]"#),
+            ("$['store']['bicycle'].color", r#"["red"]"#),
+            ("$.store.book[-1].title", r#"["Moby"]"#),
+            ("$.store.book[3]", "[]"),
+            ("$.store.book[*].author", r#"["Nigel Rees","Evelyn Waugh","Herman"]"#),
+            ("$..price", "[8.95,12.99,8.99,399]"),
+            ("$.store.book[0,2].price", "[8.95,8.99]"),
+            ("$.store.book[?@.isbn].title", r#"["Moby"]"#),
+            ("$.store.book[?@.price < 9 && @.category == 'fiction'].title", r#"["Moby"]"#),
+            ("$.store.book[?!(@.price < 10)].title", r#"["Sword"]"#),
+            ("$.store.book[?length(@.title) > 5].title", r#"["Sayings"]"#),
+            ("$.store[?count(@.*) == 2]", r#"[{"color":"red","price":399}]"#),
+            ("$.store.book[?match(@.author, 'N.*')].title", r#"["Sayings"]"#),
+            ("$.store.book[?search(@.author, 'augh')].title", r#"["Sword"]"#),
+            ("$.store.book[?search(@.author, '(')]", "[]"),
+            ("$.store.book[?value(@.price) == 399]", "[]"),
+            ("$.o[\"x'y\"]", "[1]"),
+            ("$.o['']", "[2]"),
+            ("$.o['x\\u0027y']", "[1]"),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(run(path).as_deref(), Ok(expected), "{}", path);
+        }
+    }
+
+    #[test]
+    fn slices_arrays() {
+        let cases = [
+            ("$.a[1:3]", "[1,2]"),
+            ("$.a[:2]", "[0,1]"),
+            ("$.a[-2:]", "[4,5]"),
+            ("$.a[::2]", "[0,2,4]"),
+            ("$.a[::-1]", "[5,4,3,2,1,0]"),
+            ("$.a[4:1:-2]", "[4,2]"),
+            ("$.a[10:]", "[]"),
+            ("$.a[3:1]", "[]"),
+            ("$.a[::0]", "[]"),
+            ("$.a[-100:100]", "[0,1,2,3,4,5]"),
+            ("$.a[1:9223372036854775807:9223372036854775807]", "[1]"),
+            ("$.a[-1::-9223372036854775807]", "[5]"),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(run(path).as_deref(), Ok(expected), "{}", path);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_unicode_escapes() {
+        for path in [r"$['\u+123']", r"$['\u12']", r"$['\uD800\u0041']", r"$['\uD800']", r"$['\uDC00']"] {
+            assert!(run(path).is_err(), "{}", path);
+        }
+        let root: Value = serde_json::from_str(r#"{"😀":1}"#).unwrap();
+        let nodes = query(r"$['\uD83D\uDE00']", &root, &CancelToken::new()).unwrap().unwrap();
+        assert_eq!(nodes, [&Value::from(1)]);
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        for path in ["", "store", "$.", "$[", "$['a'", "$[?", "$[?@.a ==]", "$[?match(@.a)]", "$[?foo(@)]", "$[1:2:3:4]", "$.a[01]"] {
+            assert!(run(path).is_err(), "{}", path);
+        }
+    }
+}
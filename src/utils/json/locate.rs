@@ -0,0 +1,125 @@
+use super::relaxed::push_pointer_segment;
+use std::collections::{HashMap, HashSet};
+
+/// 在标准JSON文本中查找指定JSON Pointer对应值的字节范围，找不到的指针不出现在结果中
+pub fn locate_pointers(text: &str, pointers: &HashSet<String>) -> HashMap<String, (usize, usize)> {
+    let mut scanner = Scanner {
+        bytes: text.as_bytes(),
+        text,
+        pos: 0,
+        wanted: pointers,
+        found: HashMap::new(),
+    };
+    if !pointers.is_empty() {
+        let mut pointer = String::new();
+        scanner.value(&mut pointer);
+    }
+    scanner.found
+}
+
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    text: &'a str,
+    pos: usize,
+    wanted: &'a HashSet<String>,
+    found: HashMap<String, (usize, usize)>,
+}
+
+impl Scanner<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    /// 扫描一个值并记录其范围
+    fn value(&mut self, pointer: &mut String) {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            Some(b'{') => self.object(pointer),
+            Some(b'[') => self.array(pointer),
+            Some(b'"') => self.string(),
+            Some(_) => {
+                while self.pos < self.bytes.len() && !b",]} \t\r\n".contains(&self.bytes[self.pos]) {
+                    self.pos += 1;
+                }
+            }
+            None => return,
+        }
+        if self.wanted.contains(pointer.as_str()) {
+            self.found.insert(pointer.clone(), (start, self.pos));
+        }
+    }
+
+    fn object(&mut self, pointer: &mut String) {
+        self.pos += 1;
+        let len = pointer.len();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b'"') => {
+                    let start = self.pos;
+                    self.string();
+                    let key: String = serde_json::from_str(&self.text[start..self.pos]).unwrap_or_default();
+                    self.skip_whitespace();
+                    if self.peek() == Some(b':') {
+                        self.pos += 1;
+                    }
+                    push_pointer_segment(pointer, &key);
+                    self.value(pointer);
+                    pointer.truncate(len);
+                }
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return;
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn array(&mut self, pointer: &mut String) {
+        self.pos += 1;
+        let len = pointer.len();
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b']') => {
+                    self.pos += 1;
+                    return;
+                }
+                Some(b',') => self.pos += 1,
+                Some(_) => {
+                    let before = self.pos;
+                    push_pointer_segment(pointer, &index.to_string());
+                    self.value(pointer);
+                    pointer.truncate(len);
+                    if self.pos == before {
+                        return;
+                    }
+                    index += 1;
+                }
+                None => return,
+            }
+        }
+    }
+
+    fn string(&mut self) {
+        self.pos += 1;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            match b {
+                b'\\' => self.pos += 1,
+                b'"' => return,
+                _ => {}
+            }
+        }
+    }
+}
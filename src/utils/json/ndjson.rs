@@ -0,0 +1,149 @@
+use super::relaxed::parse_relaxed;
+use super::{write_json, CancelToken, FormatOptions, JsonError, TaskError, LARGE_JSON_THRESHOLD};
+use rayon::prelude::*;
+use serde_json::Value;
+
+/// NDJSON（JSON Lines）逐行处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NdjsonAction {
+    Format,
+    Minify,
+    Validate,
+    ToArray,
+}
+
+/// 无效行及其错误，错误位置相对于该行
+#[derive(Debug, Clone)]
+pub struct NdjsonLineError {
+    /// 行号，从1开始
+    pub line: usize,
+    pub error: JsonError,
+}
+
+impl NdjsonLineError {
+    /// 面向用户的单行说明，如 "第3行第5列: 无效的JSON格式: ..."
+    pub fn describe(&self) -> String {
+        match &self.error.location {
+            Some(location) => format!("第{}行第{}列: {}", self.line, location.column, self.error.message),
+            None => format!("第{}行: {}", self.line, self.error.message),
+        }
+    }
+}
+
+/// NDJSON处理结果，无效行不会中断处理
+#[derive(Debug, Clone)]
+pub struct NdjsonReport {
+    pub output: String,
+    /// 非空行数
+    pub lines: usize,
+    pub errors: Vec<NdjsonLineError>,
+}
+
+/// 单行的处理结果：成功时为输出文本（校验模式为空）或解析出的值
+enum LineOutput {
+    Text(String),
+    Value(Value),
+}
+
+/// 逐行处理NDJSON，空行被忽略；输入较大时并行处理各行，任务被取消时返回None
+pub fn process_ndjson(
+    input: &str,
+    action: NdjsonAction,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Option<NdjsonReport> {
+    let lines: Vec<(usize, &str)> = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| (i + 1, line))
+        .collect();
+
+    // 每行单独输出，末尾换行只加在整体结果上
+    let line_options = FormatOptions {
+        trailing_newline: false,
+        ..options.clone()
+    };
+    let process = |&(_, line): &(usize, &str)| process_line(line, action, &line_options, token);
+    let results: Vec<Result<LineOutput, TaskError>> = if input.len() > LARGE_JSON_THRESHOLD {
+        lines.par_iter().map(process).collect()
+    } else {
+        lines.iter().map(process).collect()
+    };
+    if token.is_cancelled() {
+        return None;
+    }
+
+    let mut outputs = Vec::with_capacity(lines.len());
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for (&(number, line), result) in lines.iter().zip(results) {
+        match result {
+            Ok(LineOutput::Text(text)) => outputs.push(text),
+            Ok(LineOutput::Value(value)) => values.push(value),
+            Err(TaskError::Failed(error)) => {
+                errors.push(NdjsonLineError { line: number, error });
+                // 格式化和压缩时原样保留无效行，便于对照修改
+                outputs.push(line.to_string());
+            }
+            Err(TaskError::Cancelled) => return None,
+        }
+    }
+
+    let mut output = match action {
+        NdjsonAction::Format | NdjsonAction::Minify => outputs.join("\n"),
+        NdjsonAction::Validate => errors.iter().map(NdjsonLineError::describe).collect::<Vec<_>>().join("\n"),
+        NdjsonAction::ToArray => write_json(&Value::Array(values), &line_options, true, token)?,
+    };
+    if options.trailing_newline && action != NdjsonAction::Validate {
+        output.push('\n');
+    }
+    Some(NdjsonReport {
+        output,
+        lines: lines.len(),
+        errors,
+    })
+}
+
+fn process_line(
+    line: &str,
+    action: NdjsonAction,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Result<LineOutput, TaskError> {
+    if token.is_cancelled() {
+        return Err(TaskError::Cancelled);
+    }
+    let mut value = if options.relaxed {
+        parse_relaxed(line, token)?.0
+    } else {
+        serde_json::from_str::<Value>(line).map_err(|e| TaskError::Failed(JsonError::from_serde(&e, line)))?
+    };
+    if options.sort_keys {
+        value.sort_all_objects();
+    }
+    let pretty = match action {
+        NdjsonAction::Format => true,
+        NdjsonAction::Minify => false,
+        NdjsonAction::Validate => return Ok(LineOutput::Text(String::new())),
+        NdjsonAction::ToArray => return Ok(LineOutput::Value(value)),
+    };
+    write_json(&value, options, pretty, token)
+        .map(LineOutput::Text)
+        .ok_or(TaskError::Cancelled)
+}
+
+/// 将JSON数组的每个元素压缩为一行，输出NDJSON；任务被取消时返回None
+pub fn array_to_ndjson(items: &[Value], options: &FormatOptions, token: &CancelToken) -> Option<String> {
+    let write = |item: &Value| write_json(item, options, false, token);
+    let lines: Option<Vec<String>> = if items.len() > 1000 {
+        items.par_iter().map(write).collect()
+    } else {
+        items.iter().map(write).collect()
+    };
+    let mut output = lines?.join("\n");
+    if options.trailing_newline {
+        output.push('\n');
+    }
+    Some(output)
+}
@@ -0,0 +1,92 @@
+use super::format::write_escaped;
+use super::{write_json, CancelToken, FormatOptions, JsonError};
+use serde_json::Value;
+
+/// 嵌套展开的最大层数，防止异常输入导致过深递归
+const MAX_NESTING: usize = 16;
+
+/// 递归查找内容为JSON对象或数组的字符串值并原地展开，返回展开的个数；
+/// 多次编码的字符串（如 "\"{\\\"a\\\":1}\""）会逐层解开。任务被取消时返回None
+pub fn expand_nested(value: &mut Value, token: &CancelToken) -> Option<usize> {
+    if token.is_cancelled() {
+        return None;
+    }
+    let mut count = 0;
+    match value {
+        Value::String(s) => {
+            if let Some(mut inner) = decode_nested(s, 0) {
+                count += 1 + expand_nested(&mut inner, token)?;
+                *value = inner;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                count += expand_nested(item, token)?;
+            }
+        }
+        Value::Object(map) => {
+            for (_, item) in map.iter_mut() {
+                count += expand_nested(item, token)?;
+            }
+        }
+        _ => {}
+    }
+    Some(count)
+}
+
+/// 字符串内容是JSON对象或数组（可能经过多次编码）时返回解析结果
+fn decode_nested(s: &str, depth: usize) -> Option<Value> {
+    if depth >= MAX_NESTING {
+        return None;
+    }
+    let trimmed = s.trim();
+    // 只展开容器，避免把 "123"、"true" 之类的普通字符串变成其他类型
+    if !trimmed.starts_with(['{', '[', '"']) {
+        return None;
+    }
+    match serde_json::from_str::<Value>(trimmed).ok()? {
+        Value::String(inner) => decode_nested(&inner, depth + 1),
+        container @ (Value::Object(_) | Value::Array(_)) => Some(container),
+        _ => None,
+    }
+}
+
+/// 将JSON Pointer指向的节点替换为其压缩后的JSON字符串
+pub fn stringify_node(value: &mut Value, pointer: &str, options: &FormatOptions) -> Result<(), String> {
+    let node = value
+        .pointer_mut(pointer)
+        .ok_or_else(|| format!("找不到路径 \"{}\" 对应的节点", pointer))?;
+    let text = write_json(node, options, false, &CancelToken::new()).unwrap_or_default();
+    *node = Value::String(text);
+    Ok(())
+}
+
+/// 将文本转义为JSON字符串字面量的内容，不含两侧引号
+pub fn escape_string(text: &str, ensure_ascii: bool) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    write_escaped(&mut out, text, ensure_ascii);
+    out[1..out.len() - 1].to_string()
+}
+
+/// 还原JSON字符串字面量，两侧引号可有可无；错误位置对应原始输入
+pub fn unescape_string(literal: &str) -> Result<String, JsonError> {
+    let trimmed = literal.trim();
+    let quoted = trimmed.len() >= 2 && trimmed.starts_with('"') && trimmed.ends_with('"');
+    // 未带引号时补上引号后解析；错误位置换算回原始输入
+    let (source, shift) = if quoted {
+        let leading = literal.len() - literal.trim_start().len();
+        (trimmed.to_string(), leading as isize)
+    } else {
+        (format!("\"{}\"", literal), -1)
+    };
+    serde_json::from_str::<String>(&source).map_err(|e| {
+        let error = JsonError::from_serde(&e, &source);
+        match &error.location {
+            Some(location) => {
+                let offset = (location.offset as isize + shift).max(0) as usize;
+                JsonError::at_offset(error.message.clone(), literal, offset)
+            }
+            None => error,
+        }
+    })
+}
@@ -0,0 +1,307 @@
+use super::jsonpath::json_equal;
+use super::{CancelToken, JsonError};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// 补丁类型，应用前根据补丁内容自动判断
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchKind {
+    JsonPatch,  // RFC 6902
+    MergePatch, // RFC 7396
+}
+
+impl PatchKind {
+    /// 判断补丁类型：元素都是对象、或含有带 op 字段的对象的数组视为JSON Patch，其余视为Merge Patch
+    ///
+    /// 个别操作写错时仍按JSON Patch处理并报告出错的操作，而不是把整个文档替换为补丁数组
+    pub fn detect(patch: &Value) -> Self {
+        match patch {
+            Value::Array(ops) if ops.iter().all(Value::is_object) || ops.iter().any(|op| op.get("op").is_some()) => {
+                PatchKind::JsonPatch
+            }
+            _ => PatchKind::MergePatch,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatchKind::JsonPatch => "JSON Patch (RFC 6902)",
+            PatchKind::MergePatch => "JSON Merge Patch (RFC 7396)",
+        }
+    }
+}
+
+/// 应用补丁失败的原因
+#[derive(Debug, Clone)]
+pub enum PatchError {
+    Document(JsonError), // 原文档解析失败
+    Patch(JsonError),    // 补丁解析失败
+    Operation {
+        index: usize,      // 失败操作的序号，从0开始
+        operation: String, // 失败操作的原文
+        path: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::Document(e) => write!(f, "原JSON: {}", e),
+            PatchError::Patch(e) => write!(f, "补丁: {}", e),
+            PatchError::Operation { index, operation, path, message } => write!(
+                f,
+                "第{}个操作失败（路径 \"{}\"）: {}\n操作: {}",
+                index + 1,
+                path,
+                message,
+                operation
+            ),
+        }
+    }
+}
+
+/// 将补丁应用到文档，自动识别补丁类型；JSON Patch中任一操作失败时整体不生效，任务被取消时返回Ok(None)
+pub fn apply_patch(document: Value, patch: &Value, token: &CancelToken) -> Result<Option<(PatchKind, Value)>, PatchError> {
+    match PatchKind::detect(patch) {
+        PatchKind::JsonPatch => {
+            let ops = patch.as_array().map(Vec::as_slice).unwrap_or_default();
+            apply_json_patch(document, ops, token).map(|doc| doc.map(|doc| (PatchKind::JsonPatch, doc)))
+        }
+        PatchKind::MergePatch => {
+            let mut document = document;
+            apply_merge_patch(&mut document, patch);
+            Ok(Some((PatchKind::MergePatch, document)))
+        }
+    }
+}
+
+/// 按RFC 6902依次执行操作，每个操作前检查取消令牌
+pub fn apply_json_patch(mut document: Value, ops: &[Value], token: &CancelToken) -> Result<Option<Value>, PatchError> {
+    for (index, op) in ops.iter().enumerate() {
+        if token.is_cancelled() {
+            return Ok(None);
+        }
+        let path = op.get("path").and_then(Value::as_str).unwrap_or_default().to_string();
+        apply_operation(&mut document, op).map_err(|message| PatchError::Operation {
+            index,
+            operation: op.to_string(),
+            path,
+            message,
+        })?;
+    }
+    Ok(Some(document))
+}
+
+/// 按RFC 7396合并：null删除成员，对象递归合并，其他值直接替换
+pub fn apply_merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    if let Value::Object(map) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                map.shift_remove(key);
+            } else {
+                apply_merge_patch(map.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+fn apply_operation(document: &mut Value, op: &Value) -> Result<(), String> {
+    if !op.is_object() {
+        return Err(format!("操作必须是对象，而不是 {}", op));
+    }
+    let name = member_str(op, "op")?;
+    let path = parse_pointer(member_str(op, "path")?)?;
+    match name {
+        "add" => add(document, &path, member(op, "value")?.clone()),
+        "remove" => remove(document, &path).map(|_| ()),
+        "replace" => {
+            let value = member(op, "value")?.clone();
+            *get_mut(document, &path)? = value;
+            Ok(())
+        }
+        "move" => {
+            let from_text = member_str(op, "from")?;
+            let from = parse_pointer(from_text)?;
+            if path.len() > from.len() && path.starts_with(&from) {
+                return Err(format!("不能将 \"{}\" 移动到其子节点中", from_text));
+            }
+            let value = remove(document, &from)?;
+            add(document, &path, value)
+        }
+        "copy" => {
+            let from = parse_pointer(member_str(op, "from")?)?;
+            let value = get_mut(document, &from)?.clone();
+            add(document, &path, value)
+        }
+        "test" => {
+            let expected = member(op, "value")?;
+            let actual = get_mut(document, &path)?;
+            if json_equal(actual, expected) {
+                Ok(())
+            } else {
+                Err(format!("测试失败，期望 {}，实际为 {}", expected, actual))
+            }
+        }
+        other => Err(format!("不支持的操作 \"{}\"", other)),
+    }
+}
+
+fn member<'a>(op: &'a Value, name: &str) -> Result<&'a Value, String> {
+    op.get(name).ok_or_else(|| format!("缺少 {} 字段", name))
+}
+
+fn member_str<'a>(op: &'a Value, name: &str) -> Result<&'a str, String> {
+    member(op, name)?.as_str().ok_or_else(|| format!("{} 字段必须是字符串", name))
+}
+
+/// 解析JSON Pointer（RFC 6901）为未转义的片段列表
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, String> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(format!("无效的JSON Pointer \"{}\"，必须以 / 开头", pointer));
+    }
+    pointer[1..]
+        .split('/')
+        .map(|segment| {
+            let mut out = String::new();
+            let mut chars = segment.chars();
+            while let Some(c) = chars.next() {
+                if c != '~' {
+                    out.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('0') => out.push('~'),
+                    Some('1') => out.push('/'),
+                    _ => return Err(format!("无效的JSON Pointer \"{}\"，~ 后只能是 0 或 1", pointer)),
+                }
+            }
+            Ok(out)
+        })
+        .collect()
+}
+
+/// 解析数组下标，不允许前导零和负数
+fn parse_index(token: &str, len: usize, allow_end: bool) -> Result<usize, String> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    let index: usize = if valid {
+        token.parse().map_err(|_| format!("数组下标 \"{}\" 过大", token))?
+    } else {
+        return Err(format!("无效的数组下标 \"{}\"", token));
+    };
+    if index > len || (index == len && !allow_end) {
+        return Err(format!("数组下标 {} 超出范围（长度 {}）", index, len));
+    }
+    Ok(index)
+}
+
+fn get_mut<'a>(document: &'a mut Value, path: &[String]) -> Result<&'a mut Value, String> {
+    let mut current = document;
+    for token in path {
+        current = match current {
+            Value::Object(map) => map.get_mut(token).ok_or_else(|| format!("成员 \"{}\" 不存在", token))?,
+            Value::Array(items) => {
+                let index = parse_index(token, items.len(), false)?;
+                &mut items[index]
+            }
+            _ => return Err(format!("无法在标量值中查找 \"{}\"", token)),
+        };
+    }
+    Ok(current)
+}
+
+fn add(document: &mut Value, path: &[String], value: Value) -> Result<(), String> {
+    let Some((last, parent)) = path.split_last() else {
+        *document = value;
+        return Ok(());
+    };
+    match get_mut(document, parent)? {
+        Value::Object(map) => {
+            map.insert(last.clone(), value);
+            Ok(())
+        }
+        Value::Array(items) => {
+            if last == "-" {
+                items.push(value);
+            } else {
+                let index = parse_index(last, items.len(), true)?;
+                items.insert(index, value);
+            }
+            Ok(())
+        }
+        _ => Err("父节点不是对象或数组".to_string()),
+    }
+}
+
+fn remove(document: &mut Value, path: &[String]) -> Result<Value, String> {
+    let Some((last, parent)) = path.split_last() else {
+        return Err("不能删除根节点".to_string());
+    };
+    match get_mut(document, parent)? {
+        Value::Object(map) => map.shift_remove(last).ok_or_else(|| format!("成员 \"{}\" 不存在", last)),
+        Value::Array(items) => {
+            let index = parse_index(last, items.len(), false)?;
+            Ok(items.remove(index))
+        }
+        _ => Err("父节点不是对象或数组".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn detects_patch_kind() {
+        let cases = [
+            (json!([{"op": "add", "path": "/a", "value": 1}]), PatchKind::JsonPatch),
+            (json!([{"op": "add", "path": "/b", "value": 2}, {"path": "/c", "value": 3}]), PatchKind::JsonPatch),
+            (json!([{"path": "/c", "value": 3}]), PatchKind::JsonPatch),
+            (json!([{"op": "remove", "path": "/a"}, 1]), PatchKind::JsonPatch),
+            (json!([]), PatchKind::JsonPatch),
+            (json!([1, 2]), PatchKind::MergePatch),
+            (json!({"a": null}), PatchKind::MergePatch),
+        ];
+        for (patch, kind) in cases {
+            assert_eq!(PatchKind::detect(&patch), kind, "{}", patch);
+        }
+    }
+
+    #[test]
+    fn reports_malformed_operation_by_index() {
+        let patch = json!([{"op": "add", "path": "/b", "value": 2}, {"path": "/c", "value": 3}]);
+        match apply_patch(json!({"a": 1}), &patch, &CancelToken::new()) {
+            Err(PatchError::Operation { index, message, .. }) => {
+                assert_eq!(index, 1);
+                assert_eq!(message, "缺少 op 字段");
+            }
+            other => panic!("应报告第2个操作出错: {:?}", other),
+        }
+
+        let patch = json!([{"op": "add", "path": "/b", "value": 2}, 1]);
+        let Err(PatchError::Operation { index: 1, .. }) = apply_patch(json!({}), &patch, &CancelToken::new()) else {
+            panic!("非对象的操作应报告出错");
+        };
+    }
+
+    #[test]
+    fn stops_when_cancelled() {
+        let token = CancelToken::new();
+        token.cancel();
+        let patch = json!([{"op": "add", "path": "/a", "value": 1}]);
+        assert!(matches!(apply_patch(json!({}), &patch, &token), Ok(None)));
+    }
+}
@@ -0,0 +1,279 @@
+use super::jsonpath::query_pointers;
+use super::relaxed::push_pointer_segment;
+use super::CancelToken;
+use regex::{Captures, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// 脱敏方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaskMode {
+    /// 整体替换为 ******
+    Full,
+    /// 保留末尾4个字符，其余替换为 *
+    Partial,
+    /// 替换为SHA-256摘要的前16位，相同的原值得到相同的结果
+    Hash,
+}
+
+/// 按取值识别的敏感信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ValueDetector {
+    Jwt,
+    Email,
+    Phone,
+    ChineseId,
+    CreditCard,
+}
+
+impl ValueDetector {
+    pub const ALL: [ValueDetector; 5] = [
+        ValueDetector::Jwt,
+        ValueDetector::Email,
+        ValueDetector::Phone,
+        ValueDetector::ChineseId,
+        ValueDetector::CreditCard,
+    ];
+
+    fn regex(self) -> &'static Regex {
+        static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+        let patterns = PATTERNS.get_or_init(|| {
+            [
+                r"eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*",
+                r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}",
+                r"(?:\+?86[- ]?)?\b1[3-9]\d{9}\b",
+                r"\b\d{17}[\dXx]\b",
+                r"\b\d(?:[ -]?\d){12,18}\b",
+            ]
+            .iter()
+            .map(|p| Regex::new(p).expect("内置脱敏规则无效"))
+            .collect()
+        });
+        &patterns[self as usize]
+    }
+
+    /// 正则匹配后的进一步校验，减少误判
+    fn verify(self, text: &str) -> bool {
+        match self {
+            ValueDetector::ChineseId => chinese_id_checksum(text),
+            ValueDetector::CreditCard => luhn(text),
+            _ => true,
+        }
+    }
+}
+
+/// 脱敏规则，保存在配置中
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactRules {
+    /// 键名规则（不区分大小写的正则），匹配的键对应的整个值被脱敏
+    pub key_patterns: Vec<String>,
+    /// 在所有字符串中查找并脱敏的敏感信息
+    pub detectors: Vec<ValueDetector>,
+    /// JSONPath表达式，命中的节点整体被脱敏
+    pub paths: Vec<String>,
+    pub mode: MaskMode,
+}
+
+impl Default for RedactRules {
+    fn default() -> Self {
+        Self {
+            key_patterns: ["password", "token", "authorization", "id_card"].map(String::from).to_vec(),
+            detectors: ValueDetector::ALL.to_vec(),
+            paths: Vec::new(),
+            mode: MaskMode::Full,
+        }
+    }
+}
+
+/// 按规则脱敏，返回脱敏的值的个数；任务被取消时返回Ok(None)
+pub fn redact(value: &mut Value, rules: &RedactRules, token: &CancelToken) -> Result<Option<usize>, String> {
+    let keys = rules
+        .key_patterns
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            RegexBuilder::new(p)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("无效的键名规则 \"{}\": {}", p, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut targets = HashSet::new();
+    for path in rules.paths.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let Some(pointers) = query_pointers(path, value, token).map_err(|e| format!("JSONPath \"{}\": {}", path, e))? else {
+            return Ok(None);
+        };
+        targets.extend(pointers);
+    }
+    if keys.is_empty() && targets.is_empty() && rules.detectors.is_empty() {
+        return Err("请至少配置一条脱敏规则".to_string());
+    }
+
+    let mut redactor = Redactor {
+        keys,
+        targets,
+        detectors: &rules.detectors,
+        mode: rules.mode,
+        token,
+        count: 0,
+    };
+    if !redactor.visit(value, None, &mut String::new()) {
+        return Ok(None);
+    }
+    Ok(Some(redactor.count))
+}
+
+struct Redactor<'a> {
+    keys: Vec<Regex>,
+    targets: HashSet<String>,
+    detectors: &'a [ValueDetector],
+    mode: MaskMode,
+    token: &'a CancelToken,
+    count: usize,
+}
+
+impl Redactor<'_> {
+    /// 被取消时返回false
+    fn visit(&mut self, value: &mut Value, key: Option<&str>, pointer: &mut String) -> bool {
+        if self.token.is_cancelled() {
+            return false;
+        }
+        if key.is_some_and(|key| self.keys.iter().any(|re| re.is_match(key))) || self.targets.contains(pointer.as_str()) {
+            self.mask_all(value);
+            return true;
+        }
+        let len = pointer.len();
+        match value {
+            Value::Object(map) => {
+                for (k, v) in map.iter_mut() {
+                    push_pointer_segment(pointer, k);
+                    let done = self.visit(v, Some(k), pointer);
+                    pointer.truncate(len);
+                    if !done {
+                        return false;
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (i, v) in items.iter_mut().enumerate() {
+                    push_pointer_segment(pointer, &i.to_string());
+                    let done = self.visit(v, None, pointer);
+                    pointer.truncate(len);
+                    if !done {
+                        return false;
+                    }
+                }
+            }
+            Value::String(s) => {
+                if let Some(replaced) = self.detect(s) {
+                    *s = replaced;
+                }
+            }
+            // 数字形式的手机号、证件号、卡号
+            Value::Number(n) => {
+                if let Some(replaced) = self.detect(&n.to_string()) {
+                    *value = Value::String(replaced);
+                }
+            }
+            Value::Bool(_) | Value::Null => {}
+        }
+        true
+    }
+
+    /// 脱敏整个子树中的所有标量，null保持不变
+    fn mask_all(&mut self, value: &mut Value) {
+        match value {
+            Value::Object(map) => map.values_mut().for_each(|v| self.mask_all(v)),
+            Value::Array(items) => items.iter_mut().for_each(|v| self.mask_all(v)),
+            Value::Null => {}
+            Value::String(s) => {
+                *s = mask(s, self.mode);
+                self.count += 1;
+            }
+            scalar => {
+                *scalar = Value::String(mask(&scalar.to_string(), self.mode));
+                self.count += 1;
+            }
+        }
+    }
+
+    /// 替换字符串中识别出的敏感片段，没有命中时返回None
+    fn detect(&mut self, text: &str) -> Option<String> {
+        let mut current: Option<String> = None;
+        for &detector in self.detectors {
+            let source = current.as_deref().unwrap_or(text);
+            let mut hits = 0;
+            let replaced = detector.regex().replace_all(source, |caps: &Captures| {
+                let found = &caps[0];
+                if detector.verify(found) {
+                    hits += 1;
+                    mask(found, self.mode)
+                } else {
+                    found.to_string()
+                }
+            });
+            if hits > 0 {
+                self.count += hits;
+                current = Some(replaced.into_owned());
+            }
+        }
+        current
+    }
+}
+
+fn mask(text: &str, mode: MaskMode) -> String {
+    match mode {
+        MaskMode::Full => "******".to_string(),
+        MaskMode::Partial => {
+            let len = text.chars().count();
+            // 过短的值整体遮盖，避免保留的部分就是原值
+            if len <= 4 {
+                return "*".repeat(len.max(1));
+            }
+            let mut out = "*".repeat(len - 4);
+            out.extend(text.chars().skip(len - 4));
+            out
+        }
+        MaskMode::Hash => {
+            let digest = Sha256::digest(text.as_bytes());
+            let hex: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+            format!("sha256:{}", hex)
+        }
+    }
+}
+
+/// 18位身份证号的校验码（GB 11643）
+fn chinese_id_checksum(id: &str) -> bool {
+    const WEIGHTS: [u32; 17] = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
+    const CODES: &[u8; 11] = b"10X98765432";
+    let bytes = id.as_bytes();
+    if bytes.len() != 18 {
+        return false;
+    }
+    let sum: u32 = bytes[..17].iter().zip(WEIGHTS).map(|(b, w)| (b - b'0') as u32 * w).sum();
+    CODES[(sum % 11) as usize] == bytes[17].to_ascii_uppercase()
+}
+
+/// 银行卡号的Luhn校验
+fn luhn(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match i % 2 {
+            1 if d * 2 > 9 => d * 2 - 9,
+            1 => d * 2,
+            _ => d,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
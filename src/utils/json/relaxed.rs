@@ -0,0 +1,509 @@
+use super::{CancelToken, JsonError, TaskError, ERR_INVALID_JSON};
+use serde_json::{Map, Number, Value};
+use std::collections::{HashMap, HashSet};
+
+/// 最大嵌套层数，与serde_json的递归限制一致，避免过深的输入耗尽栈空间
+const MAX_NESTING_DEPTH: usize = 128;
+
+/// 某个节点上附带的注释，注释按原文保留（包含 // 或 /* */ 标记）
+#[derive(Debug, Clone, Default)]
+pub struct NodeComments {
+    /// 位于节点之前、独占一行的注释
+    pub leading: Vec<String>,
+    /// 位于节点之后、同一行的注释
+    pub trailing: Vec<String>,
+    /// 位于容器内最后一个元素之后、右括号之前的注释
+    pub dangling: Vec<String>,
+}
+
+/// 以JSON Pointer为键记录文档中的注释
+#[derive(Debug, Clone, Default)]
+pub struct CommentMap {
+    nodes: HashMap<String, NodeComments>,
+    // 内部含有注释的容器，这类容器不能被压成单行
+    containers: HashSet<String>,
+}
+
+impl CommentMap {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get(&self, pointer: &str) -> Option<&NodeComments> {
+        self.nodes.get(pointer)
+    }
+
+    /// 容器内部（含子孙节点）是否带有注释
+    pub fn has_inner(&self, pointer: &str) -> bool {
+        self.containers.contains(pointer)
+    }
+
+    fn node(&mut self, pointer: &str) -> &mut NodeComments {
+        // 记录所有祖先容器
+        let mut end = pointer.len();
+        while let Some(i) = pointer[..end].rfind('/') {
+            self.containers.insert(pointer[..i].to_string());
+            end = i;
+        }
+        self.nodes.entry(pointer.to_string()).or_default()
+    }
+
+    fn add_dangling(&mut self, pointer: &str, comments: Vec<String>) {
+        if !comments.is_empty() {
+            self.containers.insert(pointer.to_string());
+            self.node(pointer).dangling.extend(comments);
+        }
+    }
+}
+
+/// 在JSON Pointer后追加一段路径，按RFC 6901转义 ~ 和 /
+pub(crate) fn push_pointer_segment(pointer: &mut String, segment: &str) {
+    pointer.push('/');
+    for c in segment.chars() {
+        match c {
+            '~' => pointer.push_str("~0"),
+            '/' => pointer.push_str("~1"),
+            c => pointer.push(c),
+        }
+    }
+}
+
+/// 宽松解析JSON5/JSONC文本：支持注释、尾随逗号、单引号字符串、无引号键、
+/// 十六进制及省略整数部分的数字等语法，结果规范化为标准JSON值
+pub(super) fn parse_relaxed(
+    source: &str,
+    token: &CancelToken,
+) -> Result<(Value, CommentMap), TaskError> {
+    let mut parser = Parser {
+        src: source,
+        pos: 0,
+        depth: 0,
+        comments: CommentMap::default(),
+        token,
+    };
+
+    let leading = parser.trivia()?;
+    if !leading.is_empty() {
+        parser.comments.node("").leading = leading.into_iter().map(|(c, _)| c).collect();
+    }
+    if parser.pos >= source.len() {
+        return Err(parser.error("输入内容为空"));
+    }
+
+    let mut pointer = String::new();
+    let value = parser.parse_value(&mut pointer)?;
+
+    let trailing = parser.trivia()?;
+    if !trailing.is_empty() {
+        parser.comments.node("").trailing = trailing.into_iter().map(|(c, _)| c).collect();
+    }
+    if parser.pos < source.len() {
+        return Err(parser.error("JSON已结束，但后面还有多余内容"));
+    }
+
+    Ok((value, parser.comments))
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    /// 当前所在的数组/对象层数
+    depth: usize,
+    comments: CommentMap,
+    token: &'a CancelToken,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> TaskError {
+        TaskError::Failed(JsonError::at_offset(
+            format!("{}: {}", ERR_INVALID_JSON, message),
+            self.src,
+            self.pos,
+        ))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    /// 跳过空白和注释，返回遇到的注释及其之前是否出现过换行
+    fn trivia(&mut self) -> Result<Vec<(String, bool)>, TaskError> {
+        let mut comments = Vec::new();
+        let mut newline = false;
+        loop {
+            let rest = &self.src[self.pos..];
+            if rest.starts_with("//") {
+                let len = rest.find('\n').unwrap_or(rest.len());
+                comments.push((rest[..len].trim_end().to_string(), newline));
+                self.pos += len;
+            } else if let Some(body) = rest.strip_prefix("/*") {
+                let Some(end) = body.find("*/") else {
+                    return Err(self.error("块注释缺少结束标记 */"));
+                };
+                comments.push((rest[..end + 4].to_string(), newline));
+                self.pos += end + 4;
+            } else {
+                match self.peek() {
+                    Some('\n') => newline = true,
+                    // JSON5允许的其他空白字符，包括BOM和不换行空格
+                    Some(c) if c.is_whitespace() || c == '\u{FEFF}' => {}
+                    _ => return Ok(comments),
+                }
+                self.bump();
+            }
+        }
+    }
+
+    fn parse_value(&mut self, pointer: &mut String) -> Result<Value, TaskError> {
+        match self.peek() {
+            Some(open @ ('{' | '[')) => {
+                if self.depth >= MAX_NESTING_DEPTH {
+                    return Err(self.error(&format!("嵌套层数过深，最多支持{}层", MAX_NESTING_DEPTH)));
+                }
+                self.depth += 1;
+                let value = if open == '{' {
+                    self.parse_object(pointer)
+                } else {
+                    self.parse_array(pointer)
+                };
+                self.depth -= 1;
+                value
+            }
+            Some(quote @ ('"' | '\'')) => self.parse_string(quote).map(Value::String),
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'I' | 'N') => {
+                self.parse_number()
+            }
+            Some(c) if c.is_alphabetic() => {
+                let start = self.pos;
+                let word = self.identifier();
+                match word {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "null" => Ok(Value::Null),
+                    _ => {
+                        self.pos = start;
+                        Err(self.error("无法识别的字面量，应为 true、false 或 null"))
+                    }
+                }
+            }
+            Some(_) => Err(self.error("此处需要一个合法的值")),
+            None => Err(self.error("内容不完整，缺少值")),
+        }
+    }
+
+    fn parse_array(&mut self, pointer: &mut String) -> Result<Value, TaskError> {
+        if self.token.is_cancelled() {
+            return Err(TaskError::Cancelled);
+        }
+        self.bump(); // [
+        let mut items = Vec::new();
+        let mut pending = self.comment_texts()?;
+
+        loop {
+            if self.peek() == Some(']') {
+                self.bump();
+                self.comments.add_dangling(pointer, pending);
+                return Ok(Value::Array(items));
+            }
+
+            let len = pointer.len();
+            push_pointer_segment(pointer, &items.len().to_string());
+            if !pending.is_empty() {
+                self.comments.node(pointer).leading.append(&mut pending);
+            }
+            items.push(self.parse_value(pointer)?);
+            let (comma, next) = self.after_element(pointer)?;
+            pointer.truncate(len);
+            pending = next;
+
+            if !comma {
+                if self.peek() != Some(']') {
+                    return Err(self.error("缺少逗号或右方括号 ]"));
+                }
+                self.bump();
+                self.comments.add_dangling(pointer, pending);
+                return Ok(Value::Array(items));
+            }
+        }
+    }
+
+    fn parse_object(&mut self, pointer: &mut String) -> Result<Value, TaskError> {
+        if self.token.is_cancelled() {
+            return Err(TaskError::Cancelled);
+        }
+        self.bump(); // {
+        let mut map = Map::new();
+        let mut pending = self.comment_texts()?;
+
+        loop {
+            let key = match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    self.comments.add_dangling(pointer, pending);
+                    return Ok(Value::Object(map));
+                }
+                Some(quote @ ('"' | '\'')) => self.parse_string(quote)?,
+                Some(c) if c.is_alphabetic() || c == '_' || c == '$' => self.identifier().to_string(),
+                Some(_) => return Err(self.error("对象的键必须是字符串或标识符")),
+                None => return Err(self.error("内容不完整，缺少右花括号 }")),
+            };
+
+            let len = pointer.len();
+            push_pointer_segment(pointer, &key);
+            pending.extend(self.comment_texts()?);
+            if self.bump() != Some(':') {
+                return Err(self.error("键后面缺少冒号 :"));
+            }
+            pending.extend(self.comment_texts()?);
+            if !pending.is_empty() {
+                self.comments.node(pointer).leading.append(&mut pending);
+            }
+
+            let value = self.parse_value(pointer)?;
+            map.insert(key, value);
+            let (comma, next) = self.after_element(pointer)?;
+            pointer.truncate(len);
+            pending = next;
+
+            if !comma {
+                if self.peek() != Some('}') {
+                    return Err(self.error("缺少逗号或右花括号 }"));
+                }
+                self.bump();
+                self.comments.add_dangling(pointer, pending);
+                return Ok(Value::Object(map));
+            }
+        }
+    }
+
+    /// 处理元素之后的逗号和注释：同一行的注释归属当前元素，其余留给下一个元素
+    fn after_element(&mut self, pointer: &str) -> Result<(bool, Vec<String>), TaskError> {
+        let mut trivia = self.trivia()?;
+        let comma = self.peek() == Some(',');
+        if comma {
+            self.bump();
+            trivia.extend(self.trivia()?);
+        }
+
+        let mut next = Vec::new();
+        for (comment, newline) in trivia {
+            if !newline && next.is_empty() {
+                self.comments.node(pointer).trailing.push(comment);
+            } else {
+                next.push(comment);
+            }
+        }
+        Ok((comma, next))
+    }
+
+    fn comment_texts(&mut self) -> Result<Vec<String>, TaskError> {
+        Ok(self.trivia()?.into_iter().map(|(c, _)| c).collect())
+    }
+
+    fn identifier(&mut self) -> &str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '_' || c == '$' {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn parse_string(&mut self, quote: char) -> Result<String, TaskError> {
+        self.bump(); // 开始引号
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.bump() else {
+                return Err(self.error("字符串缺少结束引号"));
+            };
+            match c {
+                c if c == quote => return Ok(out),
+                '\n' | '\r' => {
+                    self.pos -= 1;
+                    return Err(self.error("字符串中包含未转义的换行符"));
+                }
+                '\\' => self.parse_escape(&mut out)?,
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self, out: &mut String) -> Result<(), TaskError> {
+        let Some(c) = self.bump() else {
+            return Err(self.error("字符串缺少结束引号"));
+        };
+        match c {
+            'b' => out.push('\u{08}'),
+            'f' => out.push('\u{0C}'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            'v' => out.push('\u{0B}'),
+            '0' => out.push('\0'),
+            // 反斜杠加换行表示续行
+            '\r' => {
+                if self.peek() == Some('\n') {
+                    self.bump();
+                }
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => {}
+            'x' => {
+                let code = self.hex_digits(2)?;
+                out.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+            }
+            'u' => {
+                let high = self.hex_digits(4)?;
+                let code = if (0xD800..0xDC00).contains(&high) && self.src[self.pos..].starts_with("\\u") {
+                    // 只有紧跟低代理项时才组合，否则回退，让后一个转义单独解析
+                    let start = self.pos;
+                    self.pos += 2;
+                    match self.hex_digits(4) {
+                        Ok(low) if (0xDC00..0xE000).contains(&low) => 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00),
+                        _ => {
+                            self.pos = start;
+                            high
+                        }
+                    }
+                } else {
+                    high
+                };
+                out.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+            }
+            c if c.is_ascii_digit() => {
+                self.pos -= 1;
+                return Err(self.error("字符串中包含无效的转义序列"));
+            }
+            // 其他字符转义后表示字符本身，如 \" \' \\ \/
+            c => out.push(c),
+        }
+        Ok(())
+    }
+
+    fn hex_digits(&mut self, count: usize) -> Result<u32, TaskError> {
+        let digits = self.src.get(self.pos..self.pos + count).unwrap_or("");
+        match u32::from_str_radix(digits, 16) {
+            Ok(code) if digits.len() == count && digits.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                self.pos += count;
+                Ok(code)
+            }
+            _ => Err(self.error("字符串中包含无效的转义序列")),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Value, TaskError> {
+        let start = self.pos;
+        let mut text = String::new();
+        match self.peek() {
+            Some('-') => {
+                text.push('-');
+                self.bump();
+            }
+            Some('+') => {
+                self.bump();
+            }
+            _ => {}
+        }
+
+        let body_start = self.pos;
+        if self.peek().is_some_and(char::is_alphabetic) {
+            let word = self.identifier();
+            if word == "Infinity" || word == "NaN" {
+                self.pos = start;
+                return Err(self.error("标准JSON不支持 Infinity 和 NaN"));
+            }
+            self.pos = body_start;
+            return Err(self.error("数字格式无效"));
+        }
+
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-') {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        let body = &self.src[body_start..self.pos];
+
+        if let Some(hex) = body.strip_prefix("0x").or_else(|| body.strip_prefix("0X")) {
+            match u128::from_str_radix(hex, 16) {
+                Ok(n) => text.push_str(&n.to_string()),
+                Err(_) => {
+                    self.pos = start;
+                    return Err(self.error("十六进制数字格式无效"));
+                }
+            }
+        } else {
+            // 补全省略的整数部分，去掉末尾多余的小数点
+            let (mantissa, exponent) = match body.find(['e', 'E']) {
+                Some(i) => body.split_at(i),
+                None => (body, ""),
+            };
+            if mantissa.starts_with('.') {
+                text.push('0');
+            }
+            text.push_str(mantissa.strip_suffix('.').unwrap_or(mantissa));
+            text.push_str(exponent);
+        }
+
+        match serde_json::from_str::<Number>(&text) {
+            Ok(n) => Ok(Value::Number(n)),
+            Err(_) => {
+                self.pos = start;
+                Err(self.error("数字格式无效"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<(Value, CommentMap), TaskError> {
+        parse_relaxed(source, &CancelToken::new())
+    }
+
+    #[test]
+    fn accepts_nesting_up_to_limit() {
+        let source = format!("{}{}", "[".repeat(MAX_NESTING_DEPTH), "]".repeat(MAX_NESTING_DEPTH));
+        assert!(parse(&source).is_ok());
+    }
+
+    #[test]
+    fn rejects_deep_nesting_with_position() {
+        let source = "[{\"a\":".repeat(50_000);
+        let Err(TaskError::Failed(error)) = parse(&source) else {
+            panic!("过深的嵌套应返回解析错误");
+        };
+        assert!(error.message.contains("嵌套层数过深"));
+        let location = error.location.expect("错误应带有位置");
+        // 在第129层的左括号处出错，每段 [{"a": 含2层、占6字节
+        assert_eq!(location.offset, MAX_NESTING_DEPTH / 2 * 6);
+    }
+
+    #[test]
+    fn combines_only_real_surrogate_pairs() {
+        let decode = |source: &str| parse(source).ok().map(|(value, _)| value);
+        assert_eq!(decode(r#""\uD83D\uDE00""#), Some(Value::from("😀")));
+        assert_eq!(decode(r#""\uD800\u0041""#), Some(Value::from("\u{FFFD}A")));
+        assert_eq!(decode(r#""\uD800x""#), Some(Value::from("\u{FFFD}x")));
+        assert_eq!(decode(r#""\uDC00""#), Some(Value::from("\u{FFFD}")));
+        assert_eq!(decode(r#""\uD800\u+123""#), None);
+        assert_eq!(decode(r#""\u+123""#), None);
+    }
+
+    #[test]
+    fn rejects_deep_arrays_without_overflow() {
+        let source = "[".repeat(100_000);
+        assert!(matches!(parse(&source), Err(TaskError::Failed(_))));
+    }
+}
@@ -0,0 +1,1175 @@
+use super::jsonpath::json_equal;
+use super::relaxed::push_pointer_segment;
+use super::{CancelToken, JsonError};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// 不是子模式的关键字，建立索引时跳过其内容
+const NON_SCHEMA_KEYWORDS: [&str; 4] = ["const", "enum", "examples", "default"];
+
+/// 支持的JSON Schema版本
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SchemaDraft {
+    Draft7,
+    Draft2019,
+    #[default]
+    Draft2020,
+}
+
+impl SchemaDraft {
+    /// 根据 $schema 的URI识别版本
+    pub fn from_uri(uri: &str) -> Option<Self> {
+        if uri.contains("draft-07") {
+            Some(SchemaDraft::Draft7)
+        } else if uri.contains("2019-09") {
+            Some(SchemaDraft::Draft2019)
+        } else if uri.contains("2020-12") {
+            Some(SchemaDraft::Draft2020)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchemaDraft::Draft7 => "Draft 7",
+            SchemaDraft::Draft2019 => "Draft 2019-09",
+            SchemaDraft::Draft2020 => "Draft 2020-12",
+        }
+    }
+}
+
+/// Schema校验选项
+#[derive(Debug, Clone, Default)]
+pub struct SchemaOptions {
+    pub draft: Option<SchemaDraft>, // None时按 $schema 识别，缺省为2020-12
+    pub base_dir: Option<PathBuf>,  // 解析相对 $ref 文件的目录，缺省为当前目录
+    pub check_formats: bool,        // 是否将 format 作为断言校验
+}
+
+/// 一处校验错误
+#[derive(Debug, Clone)]
+pub struct SchemaViolation {
+    pub instance_path: String,         // 出错值在实例中的JSON Pointer
+    pub schema_path: String,           // 出错关键字在Schema中的路径（经过 $ref 时包含 $ref）
+    pub message: String,
+    pub span: Option<(usize, usize)>, // 出错值在格式化输出中的字节范围
+}
+
+/// Schema校验的结果
+#[derive(Debug, Clone)]
+pub struct SchemaReport {
+    pub draft: SchemaDraft,
+    pub formatted: String, // 格式化后的实例，违规位置基于此文本
+    pub violations: Vec<SchemaViolation>,
+}
+
+/// Schema校验无法进行的原因
+#[derive(Debug, Clone)]
+pub enum SchemaError {
+    Instance(JsonError), // 待校验的JSON解析失败
+    Schema(JsonError),   // Schema解析失败
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Instance(e) => write!(f, "JSON: {}", e),
+            SchemaError::Schema(e) => write!(f, "Schema: {}", e),
+        }
+    }
+}
+
+/// 确定校验使用的版本：选项优先，其次按 $schema 识别，缺省为2020-12
+pub fn detect_draft(schema: &Value, options: &SchemaOptions) -> SchemaDraft {
+    options
+        .draft
+        .or_else(|| schema.get("$schema").and_then(Value::as_str).and_then(SchemaDraft::from_uri))
+        .unwrap_or_default()
+}
+
+/// 使用Schema校验实例，返回所有校验错误；任务被取消时返回None
+pub fn validate_schema(
+    schema: &Value,
+    instance: &Value,
+    options: &SchemaOptions,
+    token: &CancelToken,
+) -> Option<Vec<SchemaViolation>> {
+    let draft = detect_draft(schema, options);
+    let base_dir = options
+        .base_dir
+        .clone()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+
+    // 没有绝对 $id 的根Schema视为位于基础目录中的文件，以便按相对路径解析引用
+    let root_uri = match schema.get("$id").and_then(Value::as_str) {
+        Some(id) if has_scheme(id) => split_fragment(id).0.to_string(),
+        _ => path_to_uri(&base_dir.join("schema.json")),
+    };
+    let mut registry = Registry {
+        draft,
+        base_dir,
+        root_uri: root_uri.clone(),
+        ..Default::default()
+    };
+    registry.add_document(Rc::new(schema.clone()), &root_uri);
+
+    let validator = Validator {
+        registry: RefCell::new(registry),
+        draft,
+        check_formats: options.check_formats,
+        token,
+        regexes: RefCell::new(HashMap::new()),
+        active_refs: RefCell::new(HashSet::new()),
+    };
+    let mut errors = Vec::new();
+    let mut scope = vec![root_uri.clone()];
+    validator
+        .validate(schema, &root_uri, instance, "", "", &mut scope, &mut errors)
+        .ok()?;
+    Some(errors)
+}
+
+/// Schema在已加载文档中的位置
+#[derive(Clone)]
+struct Location {
+    doc: Rc<Value>,
+    pointer: String,
+}
+
+/// 已加载的Schema文档及其中的资源和锚点
+#[derive(Default)]
+struct Registry {
+    draft: SchemaDraft,
+    base_dir: PathBuf,
+    root_uri: String,
+    resources: HashMap<String, Location>,       // 资源URI -> 位置
+    anchors: HashMap<String, Location>,         // "URI#锚点" -> 位置
+    dynamic_anchors: HashMap<String, Location>, // $dynamicAnchor 和 $recursiveAnchor
+}
+
+impl Registry {
+    fn add_document(&mut self, doc: Rc<Value>, uri: &str) {
+        self.resources.insert(
+            uri.to_string(),
+            Location {
+                doc: doc.clone(),
+                pointer: String::new(),
+            },
+        );
+        let mut pointer = String::new();
+        self.index(&doc, &doc, uri, &mut pointer);
+    }
+
+    /// 遍历文档，登记内嵌资源（$id）和锚点
+    fn index(&mut self, doc: &Rc<Value>, value: &Value, base: &str, pointer: &mut String) {
+        match value {
+            Value::Object(map) => {
+                let location = || Location {
+                    doc: doc.clone(),
+                    pointer: pointer.clone(),
+                };
+                let mut base = base.to_string();
+                if let Some(id) = map.get("$id").and_then(Value::as_str) {
+                    if self.draft == SchemaDraft::Draft7 && id.starts_with('#') {
+                        self.anchors.insert(format!("{}{}", base, id), location());
+                    } else {
+                        let uri = resolve_uri(&base, id);
+                        let (resource, fragment) = split_fragment(&uri);
+                        base = resource.to_string();
+                        self.resources.insert(base.clone(), location());
+                        if !fragment.is_empty() {
+                            self.anchors.insert(uri.clone(), location());
+                        }
+                    }
+                }
+                if let Some(anchor) = map.get("$anchor").and_then(Value::as_str) {
+                    self.anchors.insert(format!("{}#{}", base, anchor), location());
+                }
+                if let Some(anchor) = map.get("$dynamicAnchor").and_then(Value::as_str) {
+                    self.anchors.insert(format!("{}#{}", base, anchor), location());
+                    self.dynamic_anchors.insert(format!("{}#{}", base, anchor), location());
+                }
+                if map.get("$recursiveAnchor") == Some(&Value::Bool(true)) {
+                    self.dynamic_anchors.insert(format!("{}#", base), location());
+                }
+                let len = pointer.len();
+                for (key, child) in map {
+                    if NON_SCHEMA_KEYWORDS.contains(&key.as_str()) {
+                        continue;
+                    }
+                    push_pointer_segment(pointer, key);
+                    self.index(doc, child, &base, pointer);
+                    pointer.truncate(len);
+                }
+            }
+            Value::Array(items) => {
+                let len = pointer.len();
+                for (i, child) in items.iter().enumerate() {
+                    push_pointer_segment(pointer, &i.to_string());
+                    self.index(doc, child, base, pointer);
+                    pointer.truncate(len);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 从本地文件加载引用的Schema文档
+    fn load(&mut self, uri: &str) -> Result<(), String> {
+        let root_dir = &self.root_uri[..self.root_uri.rfind('/').map_or(0, |i| i + 1)];
+        let path = if let Some(relative) = uri.strip_prefix(root_dir) {
+            self.base_dir.join(relative)
+        } else if uri.starts_with("file://") {
+            uri_to_path(uri)
+        } else {
+            return Err(format!("无法解析引用 \"{}\"，仅支持本地文件", uri));
+        };
+        let text = fs::read_to_string(&path).map_err(|e| format!("无法读取引用文件 {}: {}", path.display(), e))?;
+        let doc: Value = serde_json::from_str(&text).map_err(|e| format!("引用文件 {} 不是有效的JSON: {}", path.display(), e))?;
+        self.add_document(Rc::new(doc), uri);
+        Ok(())
+    }
+
+    /// 解析绝对URI，返回目标位置及其所在资源的基础URI
+    fn resolve(&mut self, uri: &str) -> Result<(Location, String), String> {
+        let (resource, fragment) = split_fragment(uri);
+        if !self.resources.contains_key(resource) {
+            self.load(resource)?;
+        }
+        if fragment.is_empty() {
+            return Ok((self.resources[resource].clone(), resource.to_string()));
+        }
+        if !fragment.starts_with('/') {
+            return self
+                .anchors
+                .get(uri)
+                .map(|location| (location.clone(), resource.to_string()))
+                .ok_or_else(|| format!("找不到锚点 \"{}\"", uri));
+        }
+
+        // JSON Pointer片段，沿途遇到 $id 时更新基础URI
+        let root = &self.resources[resource];
+        let mut base = resource.to_string();
+        let mut current = root.doc.pointer(&root.pointer).ok_or_else(|| format!("找不到引用 \"{}\"", uri))?;
+        let mut pointer = root.pointer.clone();
+        for segment in percent_decode(fragment).split('/').skip(1) {
+            let key = segment.replace("~1", "/").replace("~0", "~");
+            current = match current {
+                Value::Object(map) => map.get(&key),
+                Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            }
+            .ok_or_else(|| format!("找不到引用 \"{}\"", uri))?;
+            push_pointer_segment(&mut pointer, &key);
+            if let Some(id) = current.get("$id").and_then(Value::as_str) {
+                if !id.starts_with('#') {
+                    base = split_fragment(&resolve_uri(&base, id)).0.to_string();
+                }
+            }
+        }
+        Ok((
+            Location {
+                doc: root.doc.clone(),
+                pointer,
+            },
+            base,
+        ))
+    }
+}
+
+/// 校验过程中被取消
+struct Cancelled;
+
+/// 子模式已检查过的属性和元素，用于 unevaluatedProperties/unevaluatedItems
+#[derive(Default)]
+struct Evaluated {
+    props: HashSet<String>,
+    items: usize,     // 前多少个元素已检查
+    all_items: bool,  // 是否所有元素都已检查
+    contains: HashSet<usize>,
+}
+
+impl Evaluated {
+    fn merge(&mut self, other: Evaluated) {
+        self.props.extend(other.props);
+        self.items = self.items.max(other.items);
+        self.all_items |= other.all_items;
+        self.contains.extend(other.contains);
+    }
+}
+
+type Outcome = Result<Evaluated, Cancelled>;
+
+struct Validator<'t> {
+    registry: RefCell<Registry>,
+    draft: SchemaDraft,
+    check_formats: bool,
+    token: &'t CancelToken,
+    regexes: RefCell<HashMap<String, Option<Regex>>>,
+    /// 正在展开的引用：(目标文档, 目标位置, 实例路径)，用于发现不消耗实例的循环引用
+    active_refs: RefCell<HashSet<(*const Value, String, String)>>,
+}
+
+fn child(path: &str, segment: &str) -> String {
+    let mut out = path.to_string();
+    push_pointer_segment(&mut out, segment);
+    out
+}
+
+fn violation(errors: &mut Vec<SchemaViolation>, instance_path: &str, schema_path: &str, message: String) {
+    errors.push(SchemaViolation {
+        instance_path: instance_path.to_string(),
+        schema_path: schema_path.to_string(),
+        message,
+        span: None,
+    });
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn is_type(value: &Value, name: &str) -> bool {
+    match name {
+        "integer" => value.as_f64().is_some_and(|x| x.fract() == 0.0),
+        "number" => value.is_number(),
+        other => type_name(value) == other,
+    }
+}
+
+impl Validator<'_> {
+    fn regex(&self, pattern: &str) -> Option<Regex> {
+        self.regexes
+            .borrow_mut()
+            .entry(pattern.to_string())
+            .or_insert_with(|| Regex::new(pattern).ok())
+            .clone()
+    }
+
+    /// 在子错误列表中校验，返回是否通过及检查过的属性/元素
+    fn try_validate(
+        &self,
+        schema: &Value,
+        base: &str,
+        instance: &Value,
+        ipath: &str,
+        spath: &str,
+        scope: &mut Vec<String>,
+    ) -> Result<(bool, Evaluated), Cancelled> {
+        let mut errors = Vec::new();
+        let evaluated = self.validate(schema, base, instance, ipath, spath, scope, &mut errors)?;
+        Ok((errors.is_empty(), evaluated))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn validate(
+        &self,
+        schema: &Value,
+        base: &str,
+        instance: &Value,
+        ipath: &str,
+        spath: &str,
+        scope: &mut Vec<String>,
+        errors: &mut Vec<SchemaViolation>,
+    ) -> Outcome {
+        if self.token.is_cancelled() {
+            return Err(Cancelled);
+        }
+        let map = match schema {
+            Value::Bool(true) => return Ok(Evaluated::default()),
+            Value::Bool(false) => {
+                violation(errors, ipath, spath, "Schema为 false，不允许任何值".to_string());
+                return Ok(Evaluated::default());
+            }
+            Value::Object(map) => map,
+            _ => return Ok(Evaluated::default()),
+        };
+
+        // 进入新的资源时更新基础URI和动态作用域
+        let mut base = base.to_string();
+        let mut pushed = false;
+        if let Some(id) = map.get("$id").and_then(Value::as_str) {
+            if !(self.draft == SchemaDraft::Draft7 && id.starts_with('#')) {
+                base = split_fragment(&resolve_uri(&base, id)).0.to_string();
+                if scope.last() != Some(&base) {
+                    scope.push(base.clone());
+                    pushed = true;
+                }
+            }
+        }
+        let result = self.validate_keywords(map, &base, instance, ipath, spath, scope, errors);
+        if pushed {
+            scope.pop();
+        }
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn validate_keywords(
+        &self,
+        map: &Map<String, Value>,
+        base: &str,
+        instance: &Value,
+        ipath: &str,
+        spath: &str,
+        scope: &mut Vec<String>,
+        errors: &mut Vec<SchemaViolation>,
+    ) -> Outcome {
+        let mut evaluated = Evaluated::default();
+        let kw = |name: &str| child(spath, name);
+
+        if let Some(reference) = map.get("$ref").and_then(Value::as_str) {
+            let uri = resolve_uri(base, reference);
+            evaluated.merge(self.validate_ref(&uri, None, instance, ipath, &kw("$ref"), scope, errors)?);
+            // Draft 7 中 $ref 的同级关键字会被忽略
+            if self.draft == SchemaDraft::Draft7 {
+                return Ok(evaluated);
+            }
+        }
+        if self.draft == SchemaDraft::Draft2020 {
+            if let Some(reference) = map.get("$dynamicRef").and_then(Value::as_str) {
+                let uri = resolve_uri(base, reference);
+                let anchor = split_fragment(&uri).1.to_string();
+                let dynamic = (!anchor.is_empty() && !anchor.starts_with('/')).then_some(anchor);
+                evaluated.merge(self.validate_ref(&uri, dynamic, instance, ipath, &kw("$dynamicRef"), scope, errors)?);
+            }
+        }
+        if self.draft == SchemaDraft::Draft2019 {
+            if let Some(reference) = map.get("$recursiveRef").and_then(Value::as_str) {
+                let uri = resolve_uri(base, reference);
+                evaluated.merge(self.validate_ref(&uri, Some(String::new()), instance, ipath, &kw("$recursiveRef"), scope, errors)?);
+            }
+        }
+
+        self.validate_generic(map, instance, ipath, spath, errors);
+        self.validate_applicators(map, base, instance, ipath, spath, scope, errors, &mut evaluated)?;
+        match instance {
+            Value::Object(object) => {
+                self.validate_object(map, base, object, ipath, spath, scope, errors, &mut evaluated)?
+            }
+            Value::Array(items) => {
+                self.validate_array(map, base, items, ipath, spath, scope, errors, &mut evaluated)?
+            }
+            _ => {}
+        }
+        Ok(evaluated)
+    }
+
+    /// 跟随引用校验；dynamic为Some时按动态作用域重新定位（$dynamicRef 或 $recursiveRef）
+    #[allow(clippy::too_many_arguments)]
+    fn validate_ref(
+        &self,
+        uri: &str,
+        dynamic: Option<String>,
+        instance: &Value,
+        ipath: &str,
+        spath: &str,
+        scope: &mut Vec<String>,
+        errors: &mut Vec<SchemaViolation>,
+    ) -> Outcome {
+        let resolved = self.registry.borrow_mut().resolve(uri);
+        let (mut location, mut target_base) = match resolved {
+            Ok(found) => found,
+            Err(message) => {
+                violation(errors, ipath, spath, message);
+                return Ok(Evaluated::default());
+            }
+        };
+
+        if let Some(anchor) = dynamic {
+            let target = location.doc.pointer(&location.pointer);
+            let is_dynamic = match anchor.as_str() {
+                "" => target.and_then(|t| t.get("$recursiveAnchor")) == Some(&Value::Bool(true)),
+                name => target.and_then(|t| t.get("$dynamicAnchor")).and_then(Value::as_str) == Some(name),
+            };
+            if is_dynamic {
+                let registry = self.registry.borrow();
+                // 从最外层的动态作用域开始查找同名的动态锚点
+                if let Some((uri, found)) = scope
+                    .iter()
+                    .find_map(|uri| registry.dynamic_anchors.get(&format!("{}#{}", uri, anchor)).map(|l| (uri, l)))
+                {
+                    location = found.clone();
+                    target_base = uri.clone();
+                }
+            }
+        }
+
+        let Some(target) = location.doc.pointer(&location.pointer) else {
+            violation(errors, ipath, spath, format!("找不到引用 \"{}\"", uri));
+            return Ok(Evaluated::default());
+        };
+        // 同一实例位置再次进入同一个目标时会无限递归，报告为错误
+        let key = (Rc::as_ptr(&location.doc), location.pointer.clone(), ipath.to_string());
+        if !self.active_refs.borrow_mut().insert(key.clone()) {
+            violation(errors, ipath, spath, format!("引用 \"{}\" 形成循环，在同一位置无限展开", uri));
+            return Ok(Evaluated::default());
+        }
+        let pushed = scope.last() != Some(&target_base);
+        if pushed {
+            scope.push(target_base.clone());
+        }
+        let result = self.validate(target, &target_base, instance, ipath, spath, scope, errors);
+        if pushed {
+            scope.pop();
+        }
+        self.active_refs.borrow_mut().remove(&key);
+        result
+    }
+
+    /// 与类型无关以及针对标量的关键字
+    fn validate_generic(&self, map: &Map<String, Value>, instance: &Value, ipath: &str, spath: &str, errors: &mut Vec<SchemaViolation>) {
+        let kw = |name: &str| child(spath, name);
+
+        match map.get("type") {
+            Some(Value::String(name)) if !is_type(instance, name) => {
+                violation(errors, ipath, &kw("type"), format!("类型应为 {}，实际为 {}", name, type_name(instance)));
+            }
+            Some(Value::Array(names)) if !names.iter().filter_map(Value::as_str).any(|n| is_type(instance, n)) => {
+                let expected: Vec<&str> = names.iter().filter_map(Value::as_str).collect();
+                violation(
+                    errors,
+                    ipath,
+                    &kw("type"),
+                    format!("类型应为 {} 之一，实际为 {}", expected.join("/"), type_name(instance)),
+                );
+            }
+            _ => {}
+        }
+        if let Some(Value::Array(options)) = map.get("enum") {
+            if !options.iter().any(|v| json_equal(v, instance)) {
+                violation(errors, ipath, &kw("enum"), format!("值必须是 {} 之一", Value::Array(options.clone())));
+            }
+        }
+        if let Some(expected) = map.get("const") {
+            if !json_equal(expected, instance) {
+                violation(errors, ipath, &kw("const"), format!("值必须等于 {}", expected));
+            }
+        }
+
+        if let Value::Number(n) = instance {
+            let x = n.as_f64().unwrap_or(0.0);
+            let bound = |name: &str| map.get(name).and_then(Value::as_f64);
+            if let Some(m) = bound("multipleOf").filter(|m| *m > 0.0) {
+                let q = x / m;
+                if (q - q.round()).abs() > 1e-9 * q.abs().max(1.0) {
+                    violation(errors, ipath, &kw("multipleOf"), format!("{} 不是 {} 的倍数", n, m));
+                }
+            }
+            if let Some(limit) = bound("maximum").filter(|l| x > *l) {
+                violation(errors, ipath, &kw("maximum"), format!("{} 大于最大值 {}", n, limit));
+            }
+            if let Some(limit) = bound("exclusiveMaximum").filter(|l| x >= *l) {
+                violation(errors, ipath, &kw("exclusiveMaximum"), format!("{} 必须小于 {}", n, limit));
+            }
+            if let Some(limit) = bound("minimum").filter(|l| x < *l) {
+                violation(errors, ipath, &kw("minimum"), format!("{} 小于最小值 {}", n, limit));
+            }
+            if let Some(limit) = bound("exclusiveMinimum").filter(|l| x <= *l) {
+                violation(errors, ipath, &kw("exclusiveMinimum"), format!("{} 必须大于 {}", n, limit));
+            }
+        }
+
+        if let Value::String(s) = instance {
+            let len = s.chars().count() as u64;
+            if let Some(limit) = map.get("maxLength").and_then(Value::as_u64).filter(|l| len > *l) {
+                violation(errors, ipath, &kw("maxLength"), format!("字符串长度 {} 超过最大长度 {}", len, limit));
+            }
+            if let Some(limit) = map.get("minLength").and_then(Value::as_u64).filter(|l| len < *l) {
+                violation(errors, ipath, &kw("minLength"), format!("字符串长度 {} 小于最小长度 {}", len, limit));
+            }
+            if let Some(pattern) = map.get("pattern").and_then(Value::as_str) {
+                match self.regex(pattern) {
+                    Some(re) if !re.is_match(s) => {
+                        violation(errors, ipath, &kw("pattern"), format!("字符串不匹配正则 {}", pattern));
+                    }
+                    None => violation(errors, ipath, &kw("pattern"), format!("无效的正则表达式 {}", pattern)),
+                    _ => {}
+                }
+            }
+            if self.check_formats {
+                if let Some(format) = map.get("format").and_then(Value::as_str) {
+                    if !check_format(format, s) {
+                        violation(errors, ipath, &kw("format"), format!("不是有效的 {} 格式", format));
+                    }
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn validate_applicators(
+        &self,
+        map: &Map<String, Value>,
+        base: &str,
+        instance: &Value,
+        ipath: &str,
+        spath: &str,
+        scope: &mut Vec<String>,
+        errors: &mut Vec<SchemaViolation>,
+        evaluated: &mut Evaluated,
+    ) -> Result<(), Cancelled> {
+        let kw = |name: &str| child(spath, name);
+
+        if let Some(Value::Array(schemas)) = map.get("allOf") {
+            for (i, sub) in schemas.iter().enumerate() {
+                evaluated.merge(self.validate(sub, base, instance, ipath, &child(&kw("allOf"), &i.to_string()), scope, errors)?);
+            }
+        }
+        if let Some(Value::Array(schemas)) = map.get("anyOf") {
+            let mut any = false;
+            for (i, sub) in schemas.iter().enumerate() {
+                let (valid, sub_eval) = self.try_validate(sub, base, instance, ipath, &child(&kw("anyOf"), &i.to_string()), scope)?;
+                if valid {
+                    any = true;
+                    evaluated.merge(sub_eval);
+                }
+            }
+            if !any {
+                violation(errors, ipath, &kw("anyOf"), format!("不满足 anyOf 中的任何一个子模式（共 {} 个）", schemas.len()));
+            }
+        }
+        if let Some(Value::Array(schemas)) = map.get("oneOf") {
+            let mut matched = Vec::new();
+            for (i, sub) in schemas.iter().enumerate() {
+                let (valid, sub_eval) = self.try_validate(sub, base, instance, ipath, &child(&kw("oneOf"), &i.to_string()), scope)?;
+                if valid {
+                    matched.push(i);
+                    evaluated.merge(sub_eval);
+                }
+            }
+            match matched.len() {
+                1 => {}
+                0 => violation(errors, ipath, &kw("oneOf"), format!("不满足 oneOf 中的任何一个子模式（共 {} 个）", schemas.len())),
+                _ => {
+                    let list: Vec<String> = matched.iter().map(|i| i.to_string()).collect();
+                    violation(errors, ipath, &kw("oneOf"), format!("同时满足 oneOf 中的多个子模式（下标 {}）", list.join("、")));
+                }
+            }
+        }
+        if let Some(sub) = map.get("not") {
+            if self.try_validate(sub, base, instance, ipath, &kw("not"), scope)?.0 {
+                violation(errors, ipath, &kw("not"), "不应满足 not 中的子模式".to_string());
+            }
+        }
+        if let Some(condition) = map.get("if") {
+            let (valid, sub_eval) = self.try_validate(condition, base, instance, ipath, &kw("if"), scope)?;
+            if valid {
+                evaluated.merge(sub_eval);
+                if let Some(then) = map.get("then") {
+                    evaluated.merge(self.validate(then, base, instance, ipath, &kw("then"), scope, errors)?);
+                }
+            } else if let Some(otherwise) = map.get("else") {
+                evaluated.merge(self.validate(otherwise, base, instance, ipath, &kw("else"), scope, errors)?);
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn validate_object(
+        &self,
+        map: &Map<String, Value>,
+        base: &str,
+        object: &Map<String, Value>,
+        ipath: &str,
+        spath: &str,
+        scope: &mut Vec<String>,
+        errors: &mut Vec<SchemaViolation>,
+        evaluated: &mut Evaluated,
+    ) -> Result<(), Cancelled> {
+        let kw = |name: &str| child(spath, name);
+
+        if let Some(Value::Array(required)) = map.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    violation(errors, ipath, &kw("required"), format!("缺少必需属性 \"{}\"", name));
+                }
+            }
+        }
+        let count = object.len() as u64;
+        if let Some(limit) = map.get("maxProperties").and_then(Value::as_u64).filter(|l| count > *l) {
+            violation(errors, ipath, &kw("maxProperties"), format!("属性数量 {} 超过上限 {}", count, limit));
+        }
+        if let Some(limit) = map.get("minProperties").and_then(Value::as_u64).filter(|l| count < *l) {
+            violation(errors, ipath, &kw("minProperties"), format!("属性数量 {} 少于下限 {}", count, limit));
+        }
+
+        let properties = map.get("properties").and_then(Value::as_object);
+        let patterns: Vec<(&String, Option<Regex>, &Value)> = map
+            .get("patternProperties")
+            .and_then(Value::as_object)
+            .map(|p| p.iter().map(|(pat, sub)| (pat, self.regex(pat), sub)).collect())
+            .unwrap_or_default();
+
+        for (key, value) in object {
+            let ipath_key = child(ipath, key);
+            let mut matched = false;
+            if let Some(sub) = properties.and_then(|p| p.get(key)) {
+                matched = true;
+                self.validate(sub, base, value, &ipath_key, &child(&kw("properties"), key), scope, errors)?;
+            }
+            for (pattern, regex, sub) in &patterns {
+                if regex.as_ref().is_some_and(|re| re.is_match(key)) {
+                    matched = true;
+                    self.validate(sub, base, value, &ipath_key, &child(&kw("patternProperties"), pattern), scope, errors)?;
+                }
+            }
+            if matched {
+                evaluated.props.insert(key.clone());
+            } else if let Some(sub) = map.get("additionalProperties") {
+                evaluated.props.insert(key.clone());
+                if sub == &Value::Bool(false) {
+                    violation(errors, &ipath_key, &kw("additionalProperties"), format!("不允许额外的属性 \"{}\"", key));
+                } else {
+                    self.validate(sub, base, value, &ipath_key, &kw("additionalProperties"), scope, errors)?;
+                }
+            }
+            if let Some(sub) = map.get("propertyNames") {
+                let name = Value::String(key.clone());
+                let mut name_errors = Vec::new();
+                self.validate(sub, base, &name, &ipath_key, &kw("propertyNames"), scope, &mut name_errors)?;
+                for mut e in name_errors {
+                    e.message = format!("属性名 \"{}\" 无效: {}", key, e.message);
+                    errors.push(e);
+                }
+            }
+        }
+
+        // 属性依赖：Draft 7 为 dependencies，之后拆分为 dependentRequired 和 dependentSchemas
+        let mut dependent_required = Vec::new();
+        let mut dependent_schemas = Vec::new();
+        if self.draft == SchemaDraft::Draft7 {
+            if let Some(Value::Object(deps)) = map.get("dependencies") {
+                for (key, dep) in deps {
+                    match dep {
+                        Value::Array(names) => dependent_required.push((key, names, "dependencies")),
+                        sub => dependent_schemas.push((key, sub, "dependencies")),
+                    }
+                }
+            }
+        } else {
+            if let Some(Value::Object(deps)) = map.get("dependentRequired") {
+                for (key, dep) in deps {
+                    if let Value::Array(names) = dep {
+                        dependent_required.push((key, names, "dependentRequired"));
+                    }
+                }
+            }
+            if let Some(Value::Object(deps)) = map.get("dependentSchemas") {
+                dependent_schemas.extend(deps.iter().map(|(key, sub)| (key, sub, "dependentSchemas")));
+            }
+        }
+        for (key, names, keyword) in dependent_required {
+            if !object.contains_key(key) {
+                continue;
+            }
+            for name in names.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    violation(errors, ipath, &child(&kw(keyword), key), format!("存在属性 \"{}\" 时必须同时存在 \"{}\"", key, name));
+                }
+            }
+        }
+        for (key, sub, keyword) in dependent_schemas {
+            if object.contains_key(key) {
+                let instance = Value::Object(object.clone());
+                evaluated.merge(self.validate(sub, base, &instance, ipath, &child(&kw(keyword), key), scope, errors)?);
+            }
+        }
+
+        if self.draft != SchemaDraft::Draft7 {
+            if let Some(sub) = map.get("unevaluatedProperties") {
+                for (key, value) in object {
+                    if evaluated.props.contains(key) {
+                        continue;
+                    }
+                    let ipath_key = child(ipath, key);
+                    if sub == &Value::Bool(false) {
+                        violation(errors, &ipath_key, &kw("unevaluatedProperties"), format!("不允许未声明的属性 \"{}\"", key));
+                    } else {
+                        self.validate(sub, base, value, &ipath_key, &kw("unevaluatedProperties"), scope, errors)?;
+                    }
+                }
+                evaluated.props.extend(object.keys().cloned());
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn validate_array(
+        &self,
+        map: &Map<String, Value>,
+        base: &str,
+        items: &[Value],
+        ipath: &str,
+        spath: &str,
+        scope: &mut Vec<String>,
+        errors: &mut Vec<SchemaViolation>,
+        evaluated: &mut Evaluated,
+    ) -> Result<(), Cancelled> {
+        let kw = |name: &str| child(spath, name);
+        let count = items.len() as u64;
+
+        if let Some(limit) = map.get("maxItems").and_then(Value::as_u64).filter(|l| count > *l) {
+            violation(errors, ipath, &kw("maxItems"), format!("元素数量 {} 超过上限 {}", count, limit));
+        }
+        if let Some(limit) = map.get("minItems").and_then(Value::as_u64).filter(|l| count < *l) {
+            violation(errors, ipath, &kw("minItems"), format!("元素数量 {} 少于下限 {}", count, limit));
+        }
+        if map.get("uniqueItems") == Some(&Value::Bool(true)) {
+            'outer: for i in 0..items.len() {
+                for j in i + 1..items.len() {
+                    if json_equal(&items[i], &items[j]) {
+                        violation(errors, ipath, &kw("uniqueItems"), format!("元素不唯一，下标 {} 与 {} 重复", i, j));
+                        break 'outer;
+                    }
+                }
+            }
+        }
+
+        // 元组校验：2020-12 使用 prefixItems，之前的版本使用数组形式的 items
+        let (tuple, tuple_keyword, rest, rest_keyword) = match self.draft {
+            SchemaDraft::Draft2020 => (map.get("prefixItems"), "prefixItems", map.get("items"), "items"),
+            _ => match map.get("items") {
+                Some(Value::Array(_)) => (map.get("items"), "items", map.get("additionalItems"), "additionalItems"),
+                other => (None, "", other, "items"),
+            },
+        };
+        let mut prefix = 0;
+        if let Some(Value::Array(schemas)) = tuple {
+            for (i, (sub, item)) in schemas.iter().zip(items).enumerate() {
+                self.validate(sub, base, item, &child(ipath, &i.to_string()), &child(&kw(tuple_keyword), &i.to_string()), scope, errors)?;
+            }
+            prefix = schemas.len().min(items.len());
+            evaluated.items = evaluated.items.max(prefix);
+        }
+        if let Some(sub) = rest {
+            for (i, item) in items.iter().enumerate().skip(prefix) {
+                if sub == &Value::Bool(false) {
+                    violation(errors, &child(ipath, &i.to_string()), &kw(rest_keyword), format!("不允许额外的元素（下标 {}）", i));
+                } else {
+                    self.validate(sub, base, item, &child(ipath, &i.to_string()), &kw(rest_keyword), scope, errors)?;
+                }
+            }
+            evaluated.all_items = true;
+        }
+
+        if let Some(sub) = map.get("contains") {
+            let mut matched = HashSet::new();
+            for (i, item) in items.iter().enumerate() {
+                if self.try_validate(sub, base, item, &child(ipath, &i.to_string()), &kw("contains"), scope)?.0 {
+                    matched.insert(i);
+                }
+            }
+            let (min, max) = if self.draft == SchemaDraft::Draft7 {
+                (1, None)
+            } else {
+                (
+                    map.get("minContains").and_then(Value::as_u64).unwrap_or(1) as usize,
+                    map.get("maxContains").and_then(Value::as_u64).map(|m| m as usize),
+                )
+            };
+            if matched.len() < min {
+                violation(errors, ipath, &kw("contains"), format!("满足 contains 的元素有 {} 个，至少需要 {} 个", matched.len(), min));
+            }
+            if let Some(max) = max.filter(|m| matched.len() > *m) {
+                violation(errors, ipath, &kw("maxContains"), format!("满足 contains 的元素有 {} 个，最多允许 {} 个", matched.len(), max));
+            }
+            if self.draft == SchemaDraft::Draft2020 {
+                evaluated.contains.extend(matched);
+            }
+        }
+
+        if self.draft != SchemaDraft::Draft7 {
+            if let Some(sub) = map.get("unevaluatedItems") {
+                if !evaluated.all_items {
+                    for (i, item) in items.iter().enumerate().skip(evaluated.items) {
+                        if evaluated.contains.contains(&i) {
+                            continue;
+                        }
+                        let ipath_item = child(ipath, &i.to_string());
+                        if sub == &Value::Bool(false) {
+                            violation(errors, &ipath_item, &kw("unevaluatedItems"), format!("不允许未声明的元素（下标 {}）", i));
+                        } else {
+                            self.validate(sub, base, item, &ipath_item, &kw("unevaluatedItems"), scope, errors)?;
+                        }
+                    }
+                }
+                evaluated.all_items = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 校验常见的 format，未知格式一律视为通过
+pub(crate) fn check_format(format: &str, s: &str) -> bool {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    match format {
+        "date-time" => chrono::DateTime::parse_from_rfc3339(s).is_ok(),
+        "date" => s.len() == 10 && chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok(),
+        "time" => chrono::DateTime::parse_from_rfc3339(&format!("1970-01-01T{}", s)).is_ok(),
+        "email" | "idn-email" => match s.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty() && !domain.is_empty() && !domain.contains('@') && !s.chars().any(char::is_whitespace)
+            }
+            None => false,
+        },
+        "ipv4" => s.parse::<Ipv4Addr>().is_ok(),
+        "ipv6" => s.parse::<Ipv6Addr>().is_ok(),
+        "hostname" | "idn-hostname" => {
+            s.len() <= 253
+                && s.trim_end_matches('.').split('.').all(|label| {
+                    !label.is_empty()
+                        && label.len() <= 63
+                        && !label.starts_with('-')
+                        && !label.ends_with('-')
+                        && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+                })
+        }
+        "uri" | "iri" => has_scheme(s) && !s.chars().any(char::is_whitespace),
+        "uri-reference" | "iri-reference" => !s.chars().any(char::is_whitespace),
+        "uuid" => {
+            let parts: Vec<&str> = s.split('-').collect();
+            parts.len() == 5
+                && parts.iter().zip([8, 4, 4, 4, 12]).all(|(p, n)| p.len() == n && p.chars().all(|c| c.is_ascii_hexdigit()))
+        }
+        "regex" => Regex::new(s).is_ok(),
+        "json-pointer" => {
+            (s.is_empty() || s.starts_with('/'))
+                && s.split('~').skip(1).all(|part| part.starts_with('0') || part.starts_with('1'))
+        }
+        _ => true,
+    }
+}
+
+/// 判断URI是否带有协议头（如 https:、file:、urn:）
+fn has_scheme(uri: &str) -> bool {
+    match uri.find(':') {
+        Some(i) if i > 0 => {
+            let scheme = &uri[..i];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        }
+        _ => false,
+    }
+}
+
+/// 拆分URI和片段（不含 #）
+fn split_fragment(uri: &str) -> (&str, &str) {
+    match uri.find('#') {
+        Some(i) => (&uri[..i], &uri[i + 1..]),
+        None => (uri, ""),
+    }
+}
+
+/// 按RFC 3986将引用解析为绝对URI
+fn resolve_uri(base: &str, reference: &str) -> String {
+    let (base, _) = split_fragment(base);
+    if has_scheme(reference) {
+        return normalize_uri(reference);
+    }
+    if reference.is_empty() {
+        return base.to_string();
+    }
+    if reference.starts_with('#') {
+        return format!("{}{}", base, reference);
+    }
+    let scheme_end = base.find(':').map_or(0, |i| i + 1);
+    if reference.starts_with("//") {
+        return normalize_uri(&format!("{}{}", &base[..scheme_end], reference));
+    }
+    let authority_end = if base[scheme_end..].starts_with("//") {
+        base[scheme_end + 2..].find('/').map_or(base.len(), |i| scheme_end + 2 + i)
+    } else {
+        scheme_end
+    };
+    if reference.starts_with('/') {
+        return normalize_uri(&format!("{}{}", &base[..authority_end], reference));
+    }
+    match base[authority_end..].rfind('/') {
+        Some(i) => normalize_uri(&format!("{}{}", &base[..authority_end + i + 1], reference)),
+        None => normalize_uri(&format!("{}/{}", &base[..authority_end], reference)),
+    }
+}
+
+/// 去掉路径中的 . 和 .. 片段
+fn normalize_uri(uri: &str) -> String {
+    let (uri, fragment) = match uri.find('#') {
+        Some(i) => (&uri[..i], &uri[i..]),
+        None => (uri, ""),
+    };
+    let scheme_end = uri.find(':').map_or(0, |i| i + 1);
+    let path_start = if uri[scheme_end..].starts_with("//") {
+        uri[scheme_end + 2..].find('/').map_or(uri.len(), |i| scheme_end + 2 + i)
+    } else {
+        scheme_end
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in uri[path_start..].split('/') {
+        match segment {
+            "." => {}
+            ".." => {
+                if segments.len() > 1 {
+                    segments.pop();
+                }
+            }
+            s => segments.push(s),
+        }
+    }
+    format!("{}{}{}", &uri[..path_start], segments.join("/"), fragment)
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() && bytes[i + 1..i + 3].iter().all(u8::is_ascii_hexdigit) {
+            if let Ok(b) = u8::from_str_radix(&s[i + 1..i + 3], 16) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let text = path.to_string_lossy().replace('\\', "/");
+    if text.starts_with('/') {
+        format!("file://{}", text)
+    } else {
+        format!("file:///{}", text)
+    }
+}
+
+fn uri_to_path(uri: &str) -> PathBuf {
+    let path = percent_decode(uri.trim_start_matches("file://"));
+    // Windows 路径形如 /C:/dir/file.json
+    let bytes = path.as_bytes();
+    if bytes.len() > 2 && bytes[0] == b'/' && bytes[2] == b':' {
+        return PathBuf::from(&path[1..]);
+    }
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn check(schema: Value, instance: Value) -> Vec<(String, String)> {
+        validate_schema(&schema, &instance, &SchemaOptions::default(), &CancelToken::new())
+            .unwrap()
+            .into_iter()
+            .map(|v| (v.instance_path, v.schema_path))
+            .collect()
+    }
+
+    #[test]
+    fn follows_ref_into_defs() {
+        let schema = json!({
+            "$defs": {"pos": {"type": "integer", "minimum": 0}},
+            "properties": {"a": {"$ref": "#/$defs/pos"}}
+        });
+        assert!(check(schema.clone(), json!({"a": 1})).is_empty());
+        assert_eq!(
+            check(schema, json!({"a": -1})),
+            [("/a".to_string(), "/properties/a/$ref/minimum".to_string())]
+        );
+    }
+
+    #[test]
+    fn recursive_ref_descends_into_instance() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"child": {"$ref": "#"}, "v": {"type": "integer"}}
+        });
+        let errors = check(schema, json!({"child": {"child": {"v": "x"}}}));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "/child/child/v");
+    }
+
+    #[test]
+    fn reports_ref_cycle_instead_of_overflowing() {
+        let schema = json!({"$defs": {"a": {"$ref": "#/$defs/a"}}, "$ref": "#/$defs/a"});
+        let errors = validate_schema(&schema, &json!(1), &SchemaOptions::default(), &CancelToken::new()).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("循环"));
+
+        let schema = json!({"$defs": {"a": {"anyOf": [{"$ref": "#/$defs/b"}]}, "b": {"allOf": [{"$ref": "#/$defs/a"}]}}, "$ref": "#/$defs/a"});
+        assert!(!check(schema, json!({})).is_empty());
+    }
+
+    #[test]
+    fn dynamic_ref_resolves_to_outermost_anchor() {
+        let schema = json!({
+            "$id": "https://example.com/strict-tree",
+            "$dynamicAnchor": "node",
+            "$ref": "tree",
+            "unevaluatedProperties": false,
+            "$defs": {
+                "tree": {
+                    "$id": "https://example.com/tree",
+                    "$dynamicAnchor": "node",
+                    "type": "object",
+                    "properties": {
+                        "data": true,
+                        "children": {"type": "array", "items": {"$dynamicRef": "#node"}}
+                    }
+                }
+            }
+        });
+        assert!(check(schema.clone(), json!({"children": [{"data": 1}]})).is_empty());
+        let errors = check(schema, json!({"children": [{"daat": 1}]}));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "/children/0/daat");
+    }
+
+    #[test]
+    fn unevaluated_properties_sees_through_applicators() {
+        let schema = json!({
+            "allOf": [{"properties": {"a": true}}],
+            "if": {"required": ["c"]},
+            "then": {"properties": {"c": true}},
+            "unevaluatedProperties": false
+        });
+        assert!(check(schema.clone(), json!({"a": 1, "c": 2})).is_empty());
+        assert_eq!(
+            check(schema, json!({"a": 1, "b": 2})),
+            [("/b".to_string(), "/unevaluatedProperties".to_string())]
+        );
+    }
+
+    #[test]
+    fn unevaluated_items_after_prefix_and_contains() {
+        let schema = json!({"prefixItems": [{"type": "integer"}], "unevaluatedItems": false});
+        assert!(check(schema.clone(), json!([1])).is_empty());
+        assert_eq!(check(schema, json!([1, 2])), [("/1".to_string(), "/unevaluatedItems".to_string())]);
+
+        let schema = json!({"contains": {"type": "string"}, "unevaluatedItems": {"type": "integer"}});
+        assert!(check(schema.clone(), json!(["a", 1])).is_empty());
+        assert_eq!(check(schema, json!(["a", true])).len(), 1);
+    }
+}
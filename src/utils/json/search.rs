@@ -0,0 +1,133 @@
+use super::relaxed::push_pointer_segment;
+use regex::{Regex, RegexBuilder};
+use serde_json::{Map, Value};
+
+/// 查找选项
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// 按正则表达式匹配，否则按子串匹配
+    pub regex: bool,
+    pub case_sensitive: bool,
+}
+
+/// 编译后的查找条件，匹配对象键和标量值
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    Text { needle: String, case_sensitive: bool },
+    Regex(Regex),
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, options: &SearchOptions) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Err("请输入查找内容".to_string());
+        }
+        if options.regex {
+            return RegexBuilder::new(pattern)
+                .case_insensitive(!options.case_sensitive)
+                .build()
+                .map(Matcher::Regex)
+                .map_err(|e| format!("无效的正则表达式: {}", e));
+        }
+        let needle = if options.case_sensitive {
+            pattern.to_string()
+        } else {
+            pattern.to_lowercase()
+        };
+        Ok(Matcher::Text {
+            needle,
+            case_sensitive: options.case_sensitive,
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Text { needle, case_sensitive: true } => text.contains(needle.as_str()),
+            Matcher::Text { needle, .. } => text.to_lowercase().contains(needle.as_str()),
+            Matcher::Regex(regex) => regex.is_match(text),
+        }
+    }
+
+    /// 节点自身是否匹配：键匹配，或标量值的文本匹配（字符串按原始内容，不含引号）
+    fn matches_node(&self, key: Option<&str>, value: &Value) -> bool {
+        if key.is_some_and(|key| self.is_match(key)) {
+            return true;
+        }
+        match value {
+            Value::String(s) => self.is_match(s),
+            Value::Number(n) => self.is_match(&n.to_string()),
+            Value::Bool(b) => self.is_match(if *b { "true" } else { "false" }),
+            Value::Null => self.is_match("null"),
+            Value::Array(_) | Value::Object(_) => false,
+        }
+    }
+}
+
+/// 按文档顺序返回所有匹配节点的JSON Pointer
+pub fn find_matches(root: &Value, matcher: &Matcher) -> Vec<String> {
+    let mut matches = Vec::new();
+    let mut pointer = String::new();
+    collect_matches(root, None, matcher, &mut pointer, &mut matches);
+    matches
+}
+
+fn collect_matches(value: &Value, key: Option<&str>, matcher: &Matcher, pointer: &mut String, out: &mut Vec<String>) {
+    if matcher.matches_node(key, value) {
+        out.push(pointer.clone());
+    }
+    let len = pointer.len();
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                push_pointer_segment(pointer, k);
+                collect_matches(v, Some(k), matcher, pointer, out);
+                pointer.truncate(len);
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                push_pointer_segment(pointer, &i.to_string());
+                collect_matches(v, None, matcher, pointer, out);
+                pointer.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 过滤文档：只保留匹配的节点及其祖先，匹配节点的子树完整保留；
+/// 数组中被移除的元素不占位，下标会重新编号。没有任何匹配时返回None
+pub fn filter_value(root: &Value, matcher: &Matcher) -> Option<Value> {
+    filter_node(root, None, matcher)
+}
+
+fn filter_node(value: &Value, key: Option<&str>, matcher: &Matcher) -> Option<Value> {
+    if matcher.matches_node(key, value) {
+        return Some(value.clone());
+    }
+    match value {
+        Value::Object(map) => {
+            let kept: Map<String, Value> = map
+                .iter()
+                .filter_map(|(k, v)| filter_node(v, Some(k), matcher).map(|v| (k.clone(), v)))
+                .collect();
+            (!kept.is_empty()).then_some(Value::Object(kept))
+        }
+        Value::Array(items) => {
+            let kept: Vec<Value> = items.iter().filter_map(|v| filter_node(v, None, matcher)).collect();
+            (!kept.is_empty()).then_some(Value::Array(kept))
+        }
+        _ => None,
+    }
+}
+
+/// JSON Pointer的所有祖先（不含自身），从根节点开始
+pub(super) fn ancestors(pointer: &str) -> impl Iterator<Item = &str> {
+    std::iter::once("").chain(
+        pointer
+            .match_indices('/')
+            .skip(1)
+            .map(move |(i, _)| &pointer[..i]),
+    )
+    .filter(move |ancestor| ancestor.len() < pointer.len())
+}
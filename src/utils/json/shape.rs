@@ -0,0 +1,177 @@
+use super::schema::check_format;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde_json::{Number, Value};
+use std::collections::HashMap;
+
+/// 推断枚举时允许的最多不同取值
+const ENUM_MAX_VALUES: usize = 10;
+/// 需要检测的字符串格式，按优先级排列
+const DETECTED_FORMATS: [&str; 6] = ["date-time", "uuid", "email", "date", "uri", "ipv4"];
+/// 记录的数字小数位数上限
+const MAX_DECIMALS: usize = 6;
+
+/// 同一位置上所有样本值的统计，供推断Schema和生成假数据共用
+#[derive(Default)]
+pub(super) struct Shape {
+    pub nulls: usize,
+    pub booleans: usize,
+    pub integers: usize,
+    pub numbers: usize,            // 带小数的数字
+    pub range: Option<(f64, f64)>, // 所有数字的取值范围
+    pub decimals: usize,           // 数字的最多小数位数
+    pub strings: Option<StringShape>,
+    pub arrays: Option<Box<ArrayShape>>,
+    pub objects: Option<ObjectShape>,
+}
+
+pub(super) struct StringShape {
+    pub count: usize,
+    pub values: HashMap<String, usize>, // 不同取值，超过上限后不再记录
+    pub distinct_overflow: bool,
+    pub order: Vec<String>,             // 取值的首次出现顺序
+    pub formats: Vec<&'static str>,     // 所有取值都符合的格式
+    pub min_len: usize,                 // 字符数
+    pub max_len: usize,
+    pub cjk: bool,                      // 是否出现中文字符
+    pub times: Option<(i64, i64)>,      // 所有取值都是日期时间时的范围（秒），否则为None
+}
+
+#[derive(Default)]
+pub(super) struct ArrayShape {
+    pub items: Shape,
+    pub has_items: bool,
+    pub min_len: usize,
+    pub max_len: usize,
+}
+
+#[derive(Default)]
+pub(super) struct ObjectShape {
+    pub count: usize,
+    pub keys: Vec<String>,                      // 键的首次出现顺序
+    pub props: HashMap<String, (usize, Shape)>, // 键 -> (出现次数, 值的统计)
+}
+
+impl Shape {
+    /// 加入一个样本值
+    pub fn add(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.nulls += 1,
+            Value::Bool(_) => self.booleans += 1,
+            Value::Number(n) => {
+                if n.is_i64() || n.is_u64() {
+                    self.integers += 1;
+                } else {
+                    self.numbers += 1;
+                    self.decimals = self.decimals.max(decimals_of(n));
+                }
+                let v = n.as_f64().unwrap_or_default();
+                let (min, max) = self.range.unwrap_or((v, v));
+                self.range = Some((min.min(v), max.max(v)));
+            }
+            Value::String(s) => self.add_string(s),
+            Value::Array(items) => {
+                let array = self.arrays.get_or_insert_with(|| {
+                    Box::new(ArrayShape {
+                        min_len: usize::MAX,
+                        ..Default::default()
+                    })
+                });
+                array.min_len = array.min_len.min(items.len());
+                array.max_len = array.max_len.max(items.len());
+                for item in items {
+                    array.items.add(item);
+                    array.has_items = true;
+                }
+            }
+            Value::Object(map) => {
+                let object = self.objects.get_or_insert_with(Default::default);
+                object.count += 1;
+                for (key, child) in map {
+                    let (count, shape) = object.props.entry(key.clone()).or_insert_with(|| {
+                        object.keys.push(key.clone());
+                        (0, Shape::default())
+                    });
+                    *count += 1;
+                    shape.add(child);
+                }
+            }
+        }
+    }
+
+    fn add_string(&mut self, s: &str) {
+        let strings = self.strings.get_or_insert_with(|| StringShape {
+            count: 0,
+            values: HashMap::new(),
+            distinct_overflow: false,
+            order: Vec::new(),
+            formats: DETECTED_FORMATS.to_vec(),
+            min_len: usize::MAX,
+            max_len: 0,
+            cjk: false,
+            times: parse_time(s).map(|t| (t, t)),
+        });
+        strings.count += 1;
+        strings.formats.retain(|format| matches_format(format, s));
+        let len = s.chars().count();
+        strings.min_len = strings.min_len.min(len);
+        strings.max_len = strings.max_len.max(len);
+        strings.cjk |= s.chars().any(is_cjk);
+        strings.times = match (strings.times, parse_time(s)) {
+            (Some((min, max)), Some(t)) => Some((min.min(t), max.max(t))),
+            _ => None,
+        };
+        if let Some(count) = strings.values.get_mut(s) {
+            *count += 1;
+        } else if strings.values.len() < ENUM_MAX_VALUES {
+            strings.values.insert(s.to_string(), 1);
+            strings.order.push(s.to_string());
+        } else {
+            strings.distinct_overflow = true;
+        }
+    }
+}
+
+impl StringShape {
+    /// 取值种类少且有重复时视为枚举
+    pub fn is_enum(&self) -> bool {
+        !self.distinct_overflow && !self.values.is_empty() && self.count >= self.values.len() * 2
+    }
+}
+
+/// 样本的格式检测；uri 只认 http(s) 地址，避免把 "user:1" 之类的标识当作URI
+fn matches_format(format: &str, s: &str) -> bool {
+    match format {
+        "uri" => (s.starts_with("http://") || s.starts_with("https://")) && check_format(format, s),
+        _ => check_format(format, s),
+    }
+}
+
+fn decimals_of(n: &Number) -> usize {
+    let text = n.to_string();
+    match text.split_once('.') {
+        Some((_, fraction)) => fraction.trim_end_matches(|c: char| !c.is_ascii_digit()).len().min(MAX_DECIMALS),
+        None => 0,
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    ('\u{4E00}'..='\u{9FFF}').contains(&c)
+}
+
+/// 解析常见的日期时间写法，返回Unix秒数（按UTC计）
+fn parse_time(s: &str) -> Option<i64> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Some(t.timestamp());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y/%m/%d %H:%M:%S"] {
+        if let Ok(t) = NaiveDateTime::parse_from_str(s, format) {
+            return Some(t.and_utc().timestamp());
+        }
+    }
+    if s.len() == 10 {
+        if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return d.and_hms_opt(0, 0, 0).map(|t| t.and_utc().timestamp());
+        }
+    }
+    None
+}
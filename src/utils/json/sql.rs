@@ -0,0 +1,373 @@
+use super::schema::check_format;
+use super::CancelToken;
+use chrono::{DateTime, NaiveDateTime, Timelike};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// 每条INSERT语句包含的最大行数
+const INSERT_BATCH_SIZE: usize = 100;
+/// MySQL中不超过该长度的字符串列使用VARCHAR，否则使用TEXT
+const MYSQL_VARCHAR_LIMIT: usize = 255;
+/// 展开嵌套对象时列名各段之间的连接符
+const COLUMN_SEPARATOR: &str = "_";
+
+/// SQL方言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    MySql,
+    PostgreSql,
+    Sqlite,
+}
+
+impl SqlDialect {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SqlDialect::MySql => "MySQL",
+            SqlDialect::PostgreSql => "PostgreSQL",
+            SqlDialect::Sqlite => "SQLite",
+        }
+    }
+
+    fn quote_identifier(&self, name: &str) -> String {
+        match self {
+            SqlDialect::MySql => format!("`{}`", name.replace('`', "``")),
+            _ => format!("\"{}\"", name.replace('"', "\"\"")),
+        }
+    }
+
+    fn quote_string(&self, text: &str) -> String {
+        let escaped = text.replace('\'', "''");
+        // MySQL默认将反斜杠视为转义符
+        match self {
+            SqlDialect::MySql => format!("'{}'", escaped.replace('\\', "\\\\")),
+            _ => format!("'{}'", escaped),
+        }
+    }
+}
+
+/// 嵌套对象的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NestedColumns {
+    /// 整个对象存为一个JSON列
+    Json,
+    /// 展开为多列，列名以下划线连接，如 address_city；数组仍存为JSON列
+    Flatten,
+}
+
+/// SQL生成选项
+#[derive(Debug, Clone)]
+pub struct SqlOptions {
+    pub dialect: SqlDialect,
+    pub table: String,
+    pub nested: NestedColumns,
+}
+
+/// 列类型，按各行取值逐步合并
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    /// 只出现过null
+    Unknown,
+    Boolean,
+    Integer,
+    BigInteger,
+    /// 超出64位整数范围
+    Decimal,
+    Real,
+    Date,
+    /// with_zone: 是否带时区偏移；fraction: 是否出现非零的小数秒
+    Timestamp { with_zone: bool, fraction: bool },
+    Text,
+    Json,
+}
+
+impl ColumnType {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Null => ColumnType::Unknown,
+            Value::Bool(_) => ColumnType::Boolean,
+            Value::Number(n) => match n.as_i64() {
+                Some(v) if i32::try_from(v).is_ok() => ColumnType::Integer,
+                Some(_) => ColumnType::BigInteger,
+                None if n.is_f64() => ColumnType::Real,
+                // 科学计数法等无法按整数读取的写法
+                None if n.to_string().contains(['.', 'e', 'E']) => ColumnType::Real,
+                None => ColumnType::Decimal,
+            },
+            Value::String(s) => {
+                if check_format("date", s) {
+                    ColumnType::Date
+                } else if check_format("date-time", s) {
+                    ColumnType::Timestamp {
+                        with_zone: true,
+                        fraction: DateTime::parse_from_rfc3339(s).is_ok_and(|t| t.nanosecond() != 0),
+                    }
+                } else if let Some(t) = parse_naive_timestamp(s) {
+                    ColumnType::Timestamp {
+                        with_zone: false,
+                        fraction: t.nanosecond() != 0,
+                    }
+                } else {
+                    ColumnType::Text
+                }
+            }
+            Value::Array(_) | Value::Object(_) => ColumnType::Json,
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        use ColumnType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Unknown, t) | (t, Unknown) => t,
+            (Integer, BigInteger) | (BigInteger, Integer) => BigInteger,
+            (Integer | BigInteger | Decimal, Decimal) | (Decimal, Integer | BigInteger) => Decimal,
+            (Integer | BigInteger | Decimal | Real, Real) | (Real, Integer | BigInteger | Decimal) => Real,
+            (Timestamp { with_zone: a, fraction: x }, Timestamp { with_zone: b, fraction: y }) => Timestamp {
+                with_zone: a || b,
+                fraction: x || y,
+            },
+            (Date | Timestamp { .. }, Date | Timestamp { .. }) => Text,
+            (Json, _) | (_, Json) => Json,
+            _ => Text,
+        }
+    }
+
+    fn sql_name(&self, dialect: SqlDialect, max_len: usize) -> &'static str {
+        use ColumnType::*;
+        match (dialect, self) {
+            (SqlDialect::Sqlite, Boolean | Integer | BigInteger) => "INTEGER",
+            (SqlDialect::Sqlite, Decimal) => "NUMERIC",
+            (SqlDialect::Sqlite, Real) => "REAL",
+            (SqlDialect::Sqlite, _) => "TEXT",
+            (_, Boolean) => "BOOLEAN",
+            (SqlDialect::MySql, Integer) => "INT",
+            (SqlDialect::PostgreSql, Integer) => "INTEGER",
+            (_, BigInteger) => "BIGINT",
+            (SqlDialect::MySql, Decimal) => "DECIMAL(65, 0)",
+            (SqlDialect::PostgreSql, Decimal) => "NUMERIC",
+            (SqlDialect::MySql, Real) => "DOUBLE",
+            (SqlDialect::PostgreSql, Real) => "DOUBLE PRECISION",
+            (_, Date) => "DATE",
+            // DATETIME默认不保存小数秒，需要显式指定精度
+            (SqlDialect::MySql, Timestamp { fraction: true, .. }) => "DATETIME(6)",
+            (SqlDialect::MySql, Timestamp { fraction: false, .. }) => "DATETIME",
+            (SqlDialect::PostgreSql, Timestamp { with_zone: true, .. }) => "TIMESTAMPTZ",
+            (SqlDialect::PostgreSql, Timestamp { with_zone: false, .. }) => "TIMESTAMP",
+            (SqlDialect::MySql, Json) => "JSON",
+            (SqlDialect::PostgreSql, Json) => "JSONB",
+            (SqlDialect::MySql, Text | Unknown) if max_len <= MYSQL_VARCHAR_LIMIT => "VARCHAR(255)",
+            (_, Text | Unknown) => "TEXT",
+        }
+    }
+}
+
+struct Column {
+    name: String,
+    ty: ColumnType,
+    nullable: bool,
+    /// 字符串的最大长度（字符数）
+    max_len: usize,
+    /// 出现该列的行数，少于总行数时允许为空
+    present: usize,
+    /// 展开嵌套对象时，该列只由null值产生
+    from_null: bool,
+}
+
+/// 由对象数组（或JSON Lines中的多个对象）推断建表语句，并生成分批的INSERT语句；
+/// 任务被取消时返回Ok(None)
+pub fn generate_sql(rows: &[Value], options: &SqlOptions, token: &CancelToken) -> Result<Option<String>, String> {
+    let table = options.table.trim();
+    if table.is_empty() {
+        return Err("请输入表名".to_string());
+    }
+    if rows.is_empty() {
+        return Err("没有可导出的行".to_string());
+    }
+
+    // 每行展开为 (列名, 值) 列表
+    let mut records: Vec<Vec<(String, &Value)>> = Vec::with_capacity(rows.len());
+    for (i, row) in rows.iter().enumerate() {
+        if token.is_cancelled() {
+            return Ok(None);
+        }
+        let Value::Object(map) = row else {
+            return Err(format!("第{}行不是对象，只能导出对象数组", i + 1));
+        };
+        let mut cells = Vec::new();
+        collect_cells(map, "", options.nested, &mut cells);
+        // 展开后的列名可能与原有的键重名，如 {"a_b": 1, "a": {"b": 2}}
+        let mut names = HashSet::new();
+        if let Some((name, _)) = cells.iter().find(|(name, _)| !names.insert(name.as_str())) {
+            return Err(format!("第{}行展开后出现重复的列名 \"{}\"", i + 1, name));
+        }
+        records.push(cells);
+    }
+
+    let mut columns: Vec<Column> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    // 列名在MySQL等数据库中不区分大小写，按小写检查冲突
+    let mut lower_names: HashMap<String, String> = HashMap::new();
+    for cells in &records {
+        for (name, value) in cells {
+            let position = match positions.get(name) {
+                Some(&position) => position,
+                None => {
+                    if let Some(existing) = lower_names.insert(name.to_lowercase(), name.clone()) {
+                        return Err(format!("列名冲突: \"{}\" 与 \"{}\"", existing, name));
+                    }
+                    columns.push(Column {
+                        name: name.clone(),
+                        ty: ColumnType::Unknown,
+                        nullable: false,
+                        max_len: 0,
+                        present: 0,
+                        from_null: true,
+                    });
+                    positions.insert(name.clone(), columns.len() - 1);
+                    columns.len() - 1
+                }
+            };
+            let column = &mut columns[position];
+            column.ty = column.ty.merge(ColumnType::of(value));
+            match value {
+                Value::Null => column.nullable = true,
+                Value::String(s) => column.max_len = column.max_len.max(s.chars().count()),
+                _ => {}
+            }
+            column.present += 1;
+            column.from_null &= value.is_null();
+        }
+    }
+    // 某行为null、其他行为对象时，null产生的列被展开后的列取代
+    if options.nested == NestedColumns::Flatten {
+        let names: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+        columns.retain(|column| {
+            let prefix = format!("{}{}", column.name, COLUMN_SEPARATOR);
+            !(column.from_null && names.iter().any(|name| name.starts_with(&prefix)))
+        });
+    }
+    if columns.is_empty() {
+        return Err("对象中没有可导出的字段".to_string());
+    }
+    for column in &mut columns {
+        column.nullable |= column.present < records.len();
+    }
+
+    let dialect = options.dialect;
+    let table_name = dialect.quote_identifier(table);
+    let mut sql = String::new();
+    let _ = writeln!(sql, "CREATE TABLE {} (", table_name);
+    for (i, column) in columns.iter().enumerate() {
+        let _ = write!(
+            sql,
+            "  {} {}{}",
+            dialect.quote_identifier(&column.name),
+            column.ty.sql_name(dialect, column.max_len),
+            if column.nullable { "" } else { " NOT NULL" }
+        );
+        sql.push_str(if i + 1 < columns.len() { ",\n" } else { "\n" });
+    }
+    sql.push_str(");\n");
+
+    let column_list = columns
+        .iter()
+        .map(|c| dialect.quote_identifier(&c.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    for batch in records.chunks(INSERT_BATCH_SIZE) {
+        if token.is_cancelled() {
+            return Ok(None);
+        }
+        let _ = write!(sql, "\nINSERT INTO {} ({}) VALUES\n", table_name, column_list);
+        for (i, cells) in batch.iter().enumerate() {
+            let values = columns
+                .iter()
+                .map(|column| {
+                    let value = cells.iter().find(|(name, _)| *name == column.name).map(|(_, v)| *v);
+                    literal(value.unwrap_or(&Value::Null), column.ty, dialect)
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = write!(sql, "  ({})", values);
+            sql.push_str(if i + 1 < batch.len() { ",\n" } else { ";\n" });
+        }
+    }
+    Ok(Some(sql))
+}
+
+fn collect_cells<'v>(map: &'v Map<String, Value>, prefix: &str, nested: NestedColumns, out: &mut Vec<(String, &'v Value)>) {
+    for (key, value) in map {
+        let name = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}{}{}", prefix, COLUMN_SEPARATOR, key)
+        };
+        match value {
+            Value::Object(child) if nested == NestedColumns::Flatten => collect_cells(child, &name, nested, out),
+            _ => out.push((name, value)),
+        }
+    }
+}
+
+/// 按列类型输出值的SQL字面量
+fn literal(value: &Value, ty: ColumnType, dialect: SqlDialect) -> String {
+    match (value, ty) {
+        (Value::Null, _) => "NULL".to_string(),
+        (Value::Bool(b), ColumnType::Boolean) => match dialect {
+            SqlDialect::Sqlite => if *b { "1" } else { "0" }.to_string(),
+            _ => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        },
+        (Value::Number(n), ColumnType::Integer | ColumnType::BigInteger | ColumnType::Decimal | ColumnType::Real) => {
+            n.to_string()
+        }
+        (Value::String(s), ColumnType::Timestamp { .. }) if dialect == SqlDialect::MySql => {
+            // MySQL的DATETIME不接受时区后缀，统一转换为UTC；%.f 只在有小数秒时输出
+            let text = DateTime::parse_from_rfc3339(s)
+                .map(|t| t.naive_utc())
+                .ok()
+                .or_else(|| parse_naive_timestamp(s))
+                .map_or_else(|| s.clone(), |t| t.format("%Y-%m-%d %H:%M:%S%.f").to_string());
+            dialect.quote_string(&text)
+        }
+        (Value::String(s), ColumnType::Json) => dialect.quote_string(&Value::String(s.clone()).to_string()),
+        (Value::String(s), _) => dialect.quote_string(s),
+        (other, ColumnType::Json) => dialect.quote_string(&other.to_string()),
+        // 类型不一致的列按文本保存
+        (other, _) => dialect.quote_string(&other.to_string()),
+    }
+}
+
+fn parse_naive_timestamp(s: &str) -> Option<NaiveDateTime> {
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mysql(rows: Value) -> String {
+        let options = SqlOptions {
+            dialect: SqlDialect::MySql,
+            table: "t".to_string(),
+            nested: NestedColumns::Json,
+        };
+        let Value::Array(rows) = rows else { unreachable!() };
+        generate_sql(&rows, &options, &CancelToken::new()).unwrap().unwrap()
+    }
+
+    #[test]
+    fn keeps_fractional_seconds_in_mysql_datetime() {
+        let sql = mysql(json!([
+            {"at": "2024-05-01T10:00:00.123456+08:00", "plain": "2024-05-01 10:00:00"},
+            {"at": "2024-05-01T10:00:00Z", "plain": "2024-05-01 11:00:00"}
+        ]));
+        assert!(sql.contains("`at` DATETIME(6) NOT NULL"), "{}", sql);
+        assert!(sql.contains("`plain` DATETIME NOT NULL"), "{}", sql);
+        assert!(sql.contains("'2024-05-01 02:00:00.123456'"), "{}", sql);
+        assert!(sql.contains("'2024-05-01 10:00:00'"), "{}", sql);
+    }
+}
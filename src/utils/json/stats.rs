@@ -0,0 +1,344 @@
+use super::relaxed::push_pointer_segment;
+use super::{write_json, CancelToken, FormatOptions};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+
+/// 各排行榜保留的条目数
+const TOP_N: usize = 10;
+/// 参与重复值统计的最小压缩字节数，过短的值重复没有意义
+const DUPLICATE_MIN_BYTES: usize = 8;
+/// 参与重复值统计的最大压缩字节数，避免为大型子树生成文本
+const DUPLICATE_MAX_BYTES: usize = 4096;
+/// 字符串长度分布的区间上限（按字符计），最后一个区间不设上限
+const STRING_BUCKETS: [(usize, &str); 6] = [
+    (0, "空字符串"),
+    (8, "1-8"),
+    (32, "9-32"),
+    (128, "33-128"),
+    (1024, "129-1024"),
+    (usize::MAX, ">1024"),
+];
+
+/// 各类型节点的数量
+#[derive(Debug, Clone, Default)]
+pub struct TypeCounts {
+    pub objects: usize,
+    pub arrays: usize,
+    pub strings: usize,
+    pub integers: usize,
+    pub floats: usize,
+    pub booleans: usize,
+    pub nulls: usize,
+}
+
+/// 重复出现的值
+#[derive(Debug, Clone)]
+pub struct DuplicateValue {
+    /// 压缩后的值文本，过长时截断
+    pub preview: String,
+    pub count: usize,
+    /// 单个值压缩后的字节数
+    pub bytes: usize,
+    /// 首次出现位置的JSON Pointer
+    pub first_pointer: String,
+}
+
+/// JSON文档的结构统计和体积分析
+#[derive(Debug, Clone, Default)]
+pub struct JsonStats {
+    /// 最大嵌套深度，根节点为1
+    pub max_depth: usize,
+    pub types: TypeCounts,
+    /// 出现次数最多的键
+    pub top_keys: Vec<(String, usize)>,
+    /// 压缩后字节数最大的子树（JSON Pointer, 字节数），不含根节点
+    pub largest_subtrees: Vec<(String, usize)>,
+    /// 字符串长度分布（区间, 数量）
+    pub string_lengths: Vec<(&'static str, usize)>,
+    /// 重复的值，按重复造成的冗余字节数排序
+    pub duplicates: Vec<DuplicateValue>,
+    pub input_bytes: usize,
+    pub pretty_bytes: usize,
+    pub minified_bytes: usize,
+    pub gzip_bytes: usize,
+}
+
+/// 统计JSON文档，input_bytes为原始输入大小；任务被取消时返回None
+pub fn compute_stats(
+    value: &Value,
+    input_bytes: usize,
+    options: &FormatOptions,
+    token: &CancelToken,
+) -> Option<JsonStats> {
+    let mut collector = Collector {
+        token,
+        ensure_ascii: options.ensure_ascii,
+        pointer: String::new(),
+        stats: JsonStats::default(),
+        keys: HashMap::new(),
+        subtrees: Vec::new(),
+        string_buckets: [0; STRING_BUCKETS.len()],
+        values: HashMap::new(),
+    };
+    collector.visit(value, 1)?;
+
+    let Collector {
+        mut stats,
+        keys,
+        mut subtrees,
+        string_buckets,
+        values,
+        ..
+    } = collector;
+
+    let mut keys: Vec<(String, usize)> = keys.into_iter().collect();
+    keys.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    keys.truncate(TOP_N);
+    stats.top_keys = keys;
+
+    subtrees.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+    subtrees.truncate(TOP_N);
+    stats.largest_subtrees = subtrees;
+
+    stats.string_lengths = STRING_BUCKETS
+        .iter()
+        .zip(string_buckets)
+        .map(|((_, label), count)| (*label, count))
+        .collect();
+
+    let mut duplicates: Vec<DuplicateValue> = values
+        .into_iter()
+        .filter(|(_, (count, _))| *count > 1)
+        .map(|(text, (count, first_pointer))| DuplicateValue {
+            preview: preview(&text),
+            count,
+            bytes: text.len(),
+            first_pointer,
+        })
+        .collect();
+    duplicates.sort_by(|a, b| {
+        let waste = |d: &DuplicateValue| (d.count - 1) * d.bytes;
+        waste(b).cmp(&waste(a)).then_with(|| a.first_pointer.cmp(&b.first_pointer))
+    });
+    duplicates.truncate(TOP_N);
+    stats.duplicates = duplicates;
+
+    // 体积估算：格式化、压缩以及压缩后再gzip
+    let minified = write_json(value, options, false, token)?;
+    stats.pretty_bytes = write_json(value, options, true, token)?.len();
+    stats.minified_bytes = minified.len();
+    stats.gzip_bytes = gzip_len(minified.as_bytes());
+    stats.input_bytes = input_bytes;
+    Some(stats)
+}
+
+/// 遍历时的累积状态
+struct Collector<'a> {
+    token: &'a CancelToken,
+    ensure_ascii: bool,
+    pointer: String,
+    stats: JsonStats,
+    keys: HashMap<String, usize>,
+    subtrees: Vec<(String, usize)>,
+    string_buckets: [usize; STRING_BUCKETS.len()],
+    values: HashMap<String, (usize, String)>, // 压缩文本 -> (出现次数, 首次出现位置)
+}
+
+impl Collector<'_> {
+    /// 统计节点并返回其压缩后的字节数
+    fn visit(&mut self, value: &Value, depth: usize) -> Option<usize> {
+        if self.token.is_cancelled() {
+            return None;
+        }
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+        let types = &mut self.stats.types;
+        let bytes = match value {
+            Value::Null => {
+                types.nulls += 1;
+                4
+            }
+            Value::Bool(b) => {
+                types.booleans += 1;
+                if *b { 4 } else { 5 }
+            }
+            Value::Number(n) => {
+                if n.is_i64() || n.is_u64() {
+                    types.integers += 1;
+                } else {
+                    types.floats += 1;
+                }
+                n.to_string().len()
+            }
+            Value::String(s) => {
+                types.strings += 1;
+                let chars = s.chars().count();
+                let bucket = STRING_BUCKETS.iter().position(|(max, _)| chars <= *max).unwrap_or(0);
+                self.string_buckets[bucket] += 1;
+                escaped_bytes(s, self.ensure_ascii)
+            }
+            Value::Array(items) => {
+                types.arrays += 1;
+                let len = self.pointer.len();
+                let mut bytes = 2 + items.len().saturating_sub(1);
+                for (i, item) in items.iter().enumerate() {
+                    push_pointer_segment(&mut self.pointer, &i.to_string());
+                    bytes += self.visit(item, depth + 1)?;
+                    self.pointer.truncate(len);
+                }
+                bytes
+            }
+            Value::Object(map) => {
+                types.objects += 1;
+                let len = self.pointer.len();
+                let mut bytes = 2 + map.len().saturating_sub(1);
+                for (key, item) in map {
+                    *self.keys.entry(key.clone()).or_insert(0) += 1;
+                    push_pointer_segment(&mut self.pointer, key);
+                    bytes += escaped_bytes(key, self.ensure_ascii) + 1 + self.visit(item, depth + 1)?;
+                    self.pointer.truncate(len);
+                }
+                bytes
+            }
+        };
+
+        if depth > 1 && (value.is_object() || value.is_array()) {
+            self.subtrees.push((self.pointer.clone(), bytes));
+        }
+        if (DUPLICATE_MIN_BYTES..=DUPLICATE_MAX_BYTES).contains(&bytes) {
+            let text = serde_json::to_string(value).unwrap_or_default();
+            let entry = self.values.entry(text).or_insert_with(|| (0, self.pointer.clone()));
+            entry.0 += 1;
+        }
+        Some(bytes)
+    }
+}
+
+/// 转义后字符串（含引号）的字节数
+fn escaped_bytes(s: &str, ensure_ascii: bool) -> usize {
+    2 + s
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' | '\n' | '\r' | '\t' | '\u{08}' | '\u{0C}' => 2,
+            c if (c as u32) < 0x20 => 6,
+            c if ensure_ascii && !c.is_ascii() => 6 * c.len_utf16(),
+            c => c.len_utf8(),
+        })
+        .sum::<usize>()
+}
+
+fn gzip_len(data: &[u8]) -> usize {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let _ = encoder.write_all(data);
+    encoder.finish().map_or(0, |out| out.len())
+}
+
+/// 截断过长的值文本用于展示
+fn preview(text: &str) -> String {
+    const MAX_CHARS: usize = 60;
+    if text.chars().count() <= MAX_CHARS {
+        return text.to_string();
+    }
+    let head: String = text.chars().take(MAX_CHARS).collect();
+    format!("{}…", head)
+}
+
+/// 以KB/MB等单位显示字节数
+fn human_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// 相对原大小节省的比例
+fn saving(from: usize, to: usize) -> String {
+    if from == 0 {
+        return "0%".to_string();
+    }
+    format!("{:.1}%", (from as f64 - to as f64) / from as f64 * 100.0)
+}
+
+fn display_pointer(pointer: &str) -> &str {
+    if pointer.is_empty() { "(根节点)" } else { pointer }
+}
+
+impl fmt::Display for JsonStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "== 体积 ==")?;
+        writeln!(f, "输入: {}", human_bytes(self.input_bytes))?;
+        writeln!(f, "格式化: {}", human_bytes(self.pretty_bytes))?;
+        writeln!(
+            f,
+            "压缩: {}（比输入节省 {}）",
+            human_bytes(self.minified_bytes),
+            saving(self.input_bytes, self.minified_bytes)
+        )?;
+        writeln!(
+            f,
+            "压缩+gzip: {}（比输入节省 {}，比压缩后节省 {}）",
+            human_bytes(self.gzip_bytes),
+            saving(self.input_bytes, self.gzip_bytes),
+            saving(self.minified_bytes, self.gzip_bytes)
+        )?;
+
+        let t = &self.types;
+        writeln!(f, "\n== 结构 ==")?;
+        writeln!(f, "最大深度: {}", self.max_depth)?;
+        writeln!(
+            f,
+            "节点总数: {}",
+            t.objects + t.arrays + t.strings + t.integers + t.floats + t.booleans + t.nulls
+        )?;
+        writeln!(f, "对象: {}  数组: {}  字符串: {}", t.objects, t.arrays, t.strings)?;
+        writeln!(f, "整数: {}  小数: {}  布尔: {}  null: {}", t.integers, t.floats, t.booleans, t.nulls)?;
+
+        if !self.top_keys.is_empty() {
+            writeln!(f, "\n== 高频键 ==")?;
+            for (key, count) in &self.top_keys {
+                writeln!(f, "{:>8}  {}", count, key)?;
+            }
+        }
+
+        if !self.largest_subtrees.is_empty() {
+            writeln!(f, "\n== 最大子树（压缩后） ==")?;
+            for (pointer, bytes) in &self.largest_subtrees {
+                let share = *bytes as f64 / self.minified_bytes.max(1) as f64 * 100.0;
+                writeln!(f, "{:>10}  {:>5.1}%  {}", human_bytes(*bytes), share, display_pointer(pointer))?;
+            }
+        }
+
+        if self.types.strings > 0 {
+            writeln!(f, "\n== 字符串长度分布（字符） ==")?;
+            for (label, count) in &self.string_lengths {
+                writeln!(f, "{:>10}  {}", label, count)?;
+            }
+        }
+
+        if !self.duplicates.is_empty() {
+            writeln!(f, "\n== 重复值 ==")?;
+            for duplicate in &self.duplicates {
+                writeln!(
+                    f,
+                    "{:>4}次  冗余 {:>9}  首次出现于 {}  {}",
+                    duplicate.count,
+                    human_bytes((duplicate.count - 1) * duplicate.bytes),
+                    display_pointer(&duplicate.first_pointer),
+                    duplicate.preview
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,336 @@
+use super::format::write_escaped;
+use super::locate::locate_pointers;
+use super::relaxed::push_pointer_segment;
+use super::search::{ancestors, filter_value, find_matches, Matcher};
+use super::{write_json, CancelToken, FormatOptions};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// 节点值摘要的最大字符数
+const SUMMARY_MAX_CHARS: usize = 80;
+
+/// 树形视图中的一行，对应一个可见节点
+#[derive(Debug, Clone)]
+pub struct TreeRow {
+    pub depth: usize,
+    pub key: String,        // 对象键或数组下标，根节点为 "$"
+    pub kind: &'static str, // 类型标记：object/array/string/number/boolean/null
+    pub summary: String,    // 标量显示值，容器显示子节点数量
+    pub child_count: usize,
+    pub expanded: bool,
+    pub pointer: String, // 节点的JSON Pointer，作为行的唯一标识
+    pub matched: bool,   // 是否为查找命中的节点
+}
+
+impl TreeRow {
+    pub fn is_container(&self) -> bool {
+        matches!(self.kind, "object" | "array")
+    }
+}
+
+/// 查找状态
+struct TreeSearch {
+    matcher: Matcher,
+    matches: Vec<String>,
+    matched: HashSet<String>,
+    current: Option<usize>,
+    /// 过滤时可见的节点：匹配节点及其祖先，匹配节点的子树另行判断
+    visible: Option<HashSet<String>>,
+}
+
+/// 已解析JSON文档的树形模型，只为展开的节点生成子行
+pub struct JsonTree {
+    root: Value,
+    expanded: HashSet<String>,
+    search: Option<TreeSearch>,
+}
+
+impl JsonTree {
+    /// 创建树形模型，默认展开根节点
+    pub fn new(root: Value) -> Self {
+        let mut expanded = HashSet::new();
+        expanded.insert(String::new());
+        Self {
+            root,
+            expanded,
+            search: None,
+        }
+    }
+
+    pub fn root(&self) -> &Value {
+        &self.root
+    }
+
+    /// 按当前展开状态生成可见行
+    pub fn rows(&self) -> Vec<TreeRow> {
+        let mut rows = Vec::new();
+        let mut pointer = String::new();
+        self.collect_rows(&self.root, "$".to_string(), 0, &mut pointer, false, &mut rows);
+        rows
+    }
+
+    /// in_match表示节点位于某个匹配节点的子树中，过滤时不再隐藏
+    fn collect_rows(
+        &self,
+        value: &Value,
+        key: String,
+        depth: usize,
+        pointer: &mut String,
+        in_match: bool,
+        rows: &mut Vec<TreeRow>,
+    ) {
+        let matched = self.search.as_ref().is_some_and(|search| search.matched.contains(pointer.as_str()));
+        let visible = self.search.as_ref().and_then(|search| search.visible.as_ref());
+        if !in_match && visible.is_some_and(|visible| !visible.contains(pointer.as_str())) {
+            return;
+        }
+        let in_match = in_match || matched;
+        let expanded = self.expanded.contains(pointer.as_str());
+        rows.push(TreeRow {
+            depth,
+            key,
+            kind: kind_of(value),
+            summary: summarize(value),
+            child_count: child_count(value),
+            expanded,
+            pointer: pointer.clone(),
+            matched,
+        });
+        if !expanded {
+            return;
+        }
+
+        let len = pointer.len();
+        match value {
+            Value::Object(map) => {
+                for (k, v) in map {
+                    push_pointer_segment(pointer, k);
+                    self.collect_rows(v, k.clone(), depth + 1, pointer, in_match, rows);
+                    pointer.truncate(len);
+                }
+            }
+            Value::Array(items) => {
+                for (i, v) in items.iter().enumerate() {
+                    push_pointer_segment(pointer, &i.to_string());
+                    self.collect_rows(v, i.to_string(), depth + 1, pointer, in_match, rows);
+                    pointer.truncate(len);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 切换节点的展开状态，标量节点不受影响
+    pub fn toggle(&mut self, pointer: &str) {
+        if self.root.pointer(pointer).map_or(0, child_count) == 0 {
+            return;
+        }
+        if !self.expanded.remove(pointer) {
+            self.expanded.insert(pointer.to_string());
+        }
+    }
+
+    /// 展开全部容器节点
+    pub fn expand_all(&mut self) {
+        let mut pointer = String::new();
+        collect_containers(&self.root, &mut pointer, &mut self.expanded);
+    }
+
+    /// 折叠到只显示根节点的直接子节点
+    pub fn collapse_all(&mut self) {
+        self.expanded.clear();
+        self.expanded.insert(String::new());
+    }
+
+    /// 查找匹配的键或值，返回匹配数；filter为true时隐藏没有匹配的分支，并展开通往匹配节点的路径
+    pub fn search(&mut self, matcher: Matcher, filter: bool) -> usize {
+        let matches = find_matches(&self.root, &matcher);
+        let matched: HashSet<String> = matches.iter().cloned().collect();
+        let visible = filter.then(|| {
+            let mut visible = HashSet::new();
+            for pointer in &matches {
+                visible.extend(ancestors(pointer).map(str::to_string));
+            }
+            // 展开通往匹配节点的路径，匹配节点自身保持原有展开状态
+            self.expanded.extend(visible.iter().cloned());
+            visible.extend(matched.iter().cloned());
+            visible
+        });
+        let count = matches.len();
+        self.search = Some(TreeSearch {
+            matcher,
+            matched,
+            matches,
+            current: None,
+            visible,
+        });
+        count
+    }
+
+    /// 清除查找和过滤
+    pub fn clear_search(&mut self) {
+        self.search = None;
+    }
+
+    /// 移动到下一个（或上一个）匹配并展开其祖先节点，返回(序号, 匹配总数, JSON Pointer)，序号从0开始
+    pub fn step_match(&mut self, forward: bool) -> Option<(usize, usize, String)> {
+        let search = self.search.as_mut()?;
+        let count = search.matches.len();
+        if count == 0 {
+            return None;
+        }
+        let index = match (search.current, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(i), true) => (i + 1) % count,
+            (Some(i), false) => (i + count - 1) % count,
+        };
+        search.current = Some(index);
+        let pointer = search.matches[index].clone();
+        self.expanded.extend(ancestors(&pointer).map(str::to_string));
+        Some((index, count, pointer))
+    }
+
+    /// 将当前文档（过滤时只含匹配的分支）格式化为文本，
+    /// 并返回各匹配节点所在的行（从0开始），顺序与step_match的序号一致
+    pub fn search_text(&self, options: &FormatOptions) -> (String, Vec<usize>) {
+        let (value, matches) = match &self.search {
+            Some(search) if search.visible.is_some() => {
+                // 过滤后没有剩下任何分支时输出空文本，而不是 null
+                let Some(filtered) = filter_value(&self.root, &search.matcher) else {
+                    return (String::new(), Vec::new());
+                };
+                let matches = find_matches(&filtered, &search.matcher);
+                (filtered, matches)
+            }
+            Some(search) => (self.root.clone(), search.matches.clone()),
+            None => (self.root.clone(), Vec::new()),
+        };
+        let text = write_json(&value, options, true, &CancelToken::new()).unwrap_or_default();
+        let spans = locate_pointers(&text, &matches.iter().cloned().collect());
+
+        // 匹配按文档顺序排列，偏移递增，逐段累计换行数即可
+        let mut lines = Vec::with_capacity(matches.len());
+        let (mut offset, mut line) = (0, 0);
+        for pointer in &matches {
+            let start = spans.get(pointer).map_or(offset, |(start, _)| *start).max(offset);
+            line += text.as_bytes()[offset..start].iter().filter(|b| **b == b'\n').count();
+            offset = start;
+            lines.push(line);
+        }
+        (text, lines)
+    }
+
+    /// 将节点的JSON Pointer转换为JSONPath规范化路径，如 $['store']['book'][0]
+    pub fn jsonpath(&self, pointer: &str) -> Option<String> {
+        self.root.pointer(pointer)?;
+        let mut path = String::from("$");
+        let mut current = &self.root;
+        for segment in pointer_segments(pointer) {
+            match current {
+                Value::Array(items) => {
+                    let index: usize = segment.parse().ok()?;
+                    path.push_str(&format!("[{}]", index));
+                    current = items.get(index)?;
+                }
+                Value::Object(map) => {
+                    path.push_str("['");
+                    push_normalized_name(&mut path, &segment);
+                    path.push_str("']");
+                    current = map.get(&segment)?;
+                }
+                _ => return None,
+            }
+        }
+        Some(path)
+    }
+}
+
+/// 拆分JSON Pointer并还原 ~1 和 ~0 转义
+fn pointer_segments(pointer: &str) -> impl Iterator<Item = String> + '_ {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+}
+
+/// 按RFC 9535规范化路径的规则转义名称：单引号和反斜杠加转义，控制字符用 \uXXXX 表示
+fn push_normalized_name(path: &mut String, name: &str) {
+    for c in name.chars() {
+        match c {
+            '\'' => path.push_str("\\'"),
+            '\\' => path.push_str("\\\\"),
+            '\u{8}' => path.push_str("\\b"),
+            '\u{c}' => path.push_str("\\f"),
+            '\n' => path.push_str("\\n"),
+            '\r' => path.push_str("\\r"),
+            '\t' => path.push_str("\\t"),
+            c if (c as u32) < 0x20 => path.push_str(&format!("\\u{:04x}", c as u32)),
+            c => path.push(c),
+        }
+    }
+}
+
+fn collect_containers(value: &Value, pointer: &mut String, out: &mut HashSet<String>) {
+    if child_count(value) == 0 {
+        return;
+    }
+    out.insert(pointer.clone());
+    let len = pointer.len();
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                push_pointer_segment(pointer, k);
+                collect_containers(v, pointer, out);
+                pointer.truncate(len);
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                push_pointer_segment(pointer, &i.to_string());
+                collect_containers(v, pointer, out);
+                pointer.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn kind_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn child_count(value: &Value) -> usize {
+    match value {
+        Value::Array(items) => items.len(),
+        Value::Object(map) => map.len(),
+        _ => 0,
+    }
+}
+
+/// 生成节点摘要，过长的字符串截断显示
+fn summarize(value: &Value) -> String {
+    match value {
+        Value::Object(map) => format!("{{{}}}", map.len()),
+        Value::Array(items) => format!("[{}]", items.len()),
+        Value::String(s) => {
+            let mut out = String::new();
+            if s.chars().count() > SUMMARY_MAX_CHARS {
+                let truncated: String = s.chars().take(SUMMARY_MAX_CHARS).collect();
+                write_escaped(&mut out, &truncated, false);
+                out.insert_str(out.len() - 1, "...");
+            } else {
+                write_escaped(&mut out, s, false);
+            }
+            out
+        }
+        other => other.to_string(),
+    }
+}